@@ -0,0 +1,213 @@
+//! 考核组成模块
+//!
+//! 课程可以定义带权重的考核项（例如作业20%、期中30%、期末50%），
+//! 为每个学生记录各考核项的分数，并按权重计算课程总评成绩。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::student::grade::LetterGrade;
+use crate::student::grading_scale::GradingScale;
+
+/// 权重之和允许的误差
+const WEIGHT_TOLERANCE: f64 = 1e-6;
+
+/// 一个考核项
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssessmentComponent {
+    pub name: String,
+    /// 权重，百分比
+    pub weight: f64,
+}
+
+impl AssessmentComponent {
+    pub fn new(name: &str, weight: f64) -> AppResult<Self> {
+        if name.trim().is_empty() {
+            return Err(AppError::ValidationError("考核项名称不能为空".to_string()));
+        }
+        if !(weight > 0.0 && weight <= 100.0) {
+            return Err(AppError::ValidationError(
+                format!("考核项 {} 的权重 {} 必须在0-100之间且大于0", name, weight)
+            ));
+        }
+        Ok(AssessmentComponent {
+            name: name.to_string(),
+            weight,
+        })
+    }
+}
+
+impl Course {
+    /// 设置课程的考核项，权重之和必须为100%
+    ///
+    /// 已记录的分数中，不再存在的考核项会被删除。
+    pub fn set_assessment_components(&mut self, components: Vec<AssessmentComponent>) -> AppResult<()> {
+        for (index, component) in components.iter().enumerate() {
+            AssessmentComponent::new(&component.name, component.weight)?;
+            if components[..index].iter().any(|c| c.name == component.name) {
+                return Err(AppError::ValidationError(
+                    format!("考核项 {} 重复", component.name)
+                ));
+            }
+        }
+
+        let total: f64 = components.iter().map(|c| c.weight).sum();
+        if !components.is_empty() && (total - 100.0).abs() > WEIGHT_TOLERANCE {
+            return Err(AppError::ValidationError(
+                format!("考核项权重之和为 {}%，必须为100%", total)
+            ));
+        }
+
+        for scores in self.component_scores.values_mut() {
+            scores.retain(|name, _| components.iter().any(|c| &c.name == name));
+        }
+        self.component_scores.retain(|_, scores| !scores.is_empty());
+        self.components = components;
+        Ok(())
+    }
+
+    pub fn assessment_components(&self) -> &[AssessmentComponent] {
+        &self.components
+    }
+
+    /// 记录学生某个考核项的分数，重复记录会覆盖之前的分数
+    pub fn record_component_score(&mut self, student_id: u32, component: &str, score: f64) -> AppResult<()> {
+        if !self.is_enrolled(student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 没有注册课程 {}", student_id, self.name)
+            ));
+        }
+        self.insert_component_score(student_id, component, score)
+    }
+
+    /// 恢复保存过的考核项分数，已退出课程的学生保留退出前的分数
+    pub(crate) fn restore_component_score(&mut self, student_id: u32, component: &str, score: f64) -> AppResult<()> {
+        if !self.is_enrolled(student_id) && !self.is_withdrawn(student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 没有注册课程 {}", student_id, self.name)
+            ));
+        }
+        self.insert_component_score(student_id, component, score)
+    }
+
+    fn insert_component_score(&mut self, student_id: u32, component: &str, score: f64) -> AppResult<()> {
+        if !self.components.iter().any(|c| c.name == component) {
+            return Err(AppError::NotFound(
+                format!("课程 {} 没有考核项 {}", self.name, component)
+            ));
+        }
+        if !(0.0..=100.0).contains(&score) {
+            return Err(AppError::ValidationError(
+                format!("成绩 {} 必须在0-100之间", score)
+            ));
+        }
+
+        self.component_scores
+            .entry(student_id)
+            .or_default()
+            .insert(component.to_string(), score);
+        Ok(())
+    }
+
+    pub fn component_score(&self, student_id: u32, component: &str) -> Option<f64> {
+        self.component_scores.get(&student_id)?.get(component).copied()
+    }
+
+    /// 全部考核项分数 (学生ID, 考核项名称, 分数)，按学生ID和考核项名称排序
+    pub(crate) fn component_score_records(&self) -> Vec<(u32, &str, f64)> {
+        self.component_scores.iter()
+            .flat_map(|(&id, scores)| scores.iter().map(move |(name, &score)| (id, name.as_str(), score)))
+            .collect()
+    }
+
+    /// 按权重计算的总评成绩，所有考核项都有分数后才返回
+    pub fn final_score(&self, student_id: u32) -> Option<f64> {
+        if self.components.is_empty() {
+            return None;
+        }
+        let scores = self.component_scores.get(&student_id)?;
+
+        self.components.iter()
+            .map(|c| scores.get(&c.name).map(|score| score * c.weight / 100.0))
+            .sum()
+    }
+
+    /// 总评成绩对应的等级
+    pub fn final_letter_grade(&self, student_id: u32, scale: &GradingScale) -> Option<LetterGrade> {
+        self.final_score(student_id).map(|score| scale.letter_for(score))
+    }
+}
+
+impl EnrollmentManager {
+    /// 记录学生在某门课程某个考核项上的分数
+    pub fn record_component_score(
+        &mut self,
+        course_id: u32,
+        student_id: u32,
+        component: &str,
+        score: f64,
+    ) -> AppResult<()> {
+        self.run_operation("record_component_score", |manager| {
+            manager.update_course(course_id, |course| {
+                course.record_component_score(student_id, component, score)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Student;
+
+    fn weighted_course() -> Course {
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.enroll_student(1).unwrap();
+        course.set_assessment_components(vec![
+            AssessmentComponent::new("作业", 20.0).unwrap(),
+            AssessmentComponent::new("期中", 30.0).unwrap(),
+            AssessmentComponent::new("期末", 50.0).unwrap(),
+        ]).unwrap();
+        course
+    }
+
+    #[test]
+    fn test_weights_must_sum_to_100() {
+        let mut course = weighted_course();
+        let result = course.set_assessment_components(vec![
+            AssessmentComponent::new("作业", 20.0).unwrap(),
+            AssessmentComponent::new("期末", 50.0).unwrap(),
+        ]);
+        assert!(result.is_err());
+        assert!(AssessmentComponent::new("期末", 0.0).is_err());
+        assert!(course.set_assessment_components(vec![
+            AssessmentComponent::new("期末", 50.0).unwrap(),
+            AssessmentComponent::new("期末", 50.0).unwrap(),
+        ]).is_err());
+        assert_eq!(course.assessment_components().len(), 3);
+    }
+
+    #[test]
+    fn test_final_score_from_weights() {
+        let mut course = weighted_course();
+        course.record_component_score(1, "作业", 90.0).unwrap();
+        course.record_component_score(1, "期中", 80.0).unwrap();
+        assert_eq!(course.final_score(1), None);
+
+        course.record_component_score(1, "期末", 70.0).unwrap();
+        // 90*0.2 + 80*0.3 + 70*0.5 = 77
+        assert!((course.final_score(1).unwrap() - 77.0).abs() < 1e-9);
+        assert_eq!(course.final_letter_grade(1, &GradingScale::standard()), Some(LetterGrade::C));
+        let student = Student::new(1, "张三".to_string(), 20).unwrap();
+        assert_eq!(course.student_score(&student), course.final_score(1));
+    }
+
+    #[test]
+    fn test_record_rejects_unknown_component_and_student() {
+        let mut course = weighted_course();
+        assert!(course.record_component_score(1, "实验", 90.0).is_err());
+        assert!(course.record_component_score(2, "期末", 90.0).is_err());
+        assert!(course.record_component_score(1, "期末", 101.0).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! 考勤模块
+//!
+//! 每次上课以上课日期和当天的节次标识（同一天上两次课的课程各记各的），
+//! 记录每个注册学生的出勤情况。
+//! 出勤率 = (出勤 + 迟到) / (有记录的课次 - 请假)，没有记录的课次不计入。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+use std::fmt;
+use std::str::FromStr;
+
+/// 默认的出勤率警戒线（百分比），低于它的学生会被标记出来
+pub const DEFAULT_ATTENDANCE_THRESHOLD: f64 = 80.0;
+
+/// 检查出勤率警戒线在0-100之间
+pub(crate) fn check_attendance_threshold(threshold: f64) -> AppResult<()> {
+    if !(0.0..=100.0).contains(&threshold) {
+        return Err(AppError::ValidationError(
+            format!("出勤率警戒线 {} 必须在0-100之间", threshold)
+        ));
+    }
+    Ok(())
+}
+
+/// 一次课：上课日期和当天的节次（从1开始）
+///
+/// 文本形式为 `2024-09-02#2`，省略节次时是当天第1节。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct Session {
+    pub date: Date,
+    period: u8,
+}
+
+impl Session {
+    pub fn new(date: Date, period: u8) -> AppResult<Self> {
+        if period == 0 {
+            return Err(AppError::ValidationError("节次从1开始".to_string()));
+        }
+        Ok(Session { date, period })
+    }
+
+    pub fn period(&self) -> u8 {
+        self.period
+    }
+}
+
+/// 当天的第1节
+impl From<Date> for Session {
+    fn from(date: Date) -> Self {
+        Session { date, period: 1 }
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}#{}", self.date, self.period)
+    }
+}
+
+impl FromStr for Session {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        match s.trim().split_once('#') {
+            Some((date, period)) => {
+                let period = period.parse()
+                    .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的节次", period)))?;
+                Session::new(date.parse()?, period)
+            }
+            None => Ok(Session::from(s.parse::<Date>()?)),
+        }
+    }
+}
+
+impl From<Session> for String {
+    fn from(session: Session) -> Self {
+        session.to_string()
+    }
+}
+
+impl TryFrom<String> for Session {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        value.parse()
+    }
+}
+
+/// 一次课的出勤情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AttendanceStatus {
+    Present,
+    /// 迟到算作出勤
+    Late,
+    Absent,
+    /// 请假不计入出勤率
+    Excused,
+}
+
+impl AttendanceStatus {
+    pub const ALL: [AttendanceStatus; 4] = [
+        AttendanceStatus::Present, AttendanceStatus::Late, AttendanceStatus::Absent, AttendanceStatus::Excused,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttendanceStatus::Present => "出勤",
+            AttendanceStatus::Late => "迟到",
+            AttendanceStatus::Absent => "缺勤",
+            AttendanceStatus::Excused => "请假",
+        }
+    }
+}
+
+impl fmt::Display for AttendanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for AttendanceStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let s = s.trim();
+        AttendanceStatus::ALL.iter()
+            .find(|status| status.as_str() == s)
+            .copied()
+            .ok_or_else(|| AppError::InvalidInput(format!("'{}' 不是有效的出勤情况", s)))
+    }
+}
+
+/// 出勤情况的次数汇总
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttendanceSummary {
+    pub present: usize,
+    pub late: usize,
+    pub absent: usize,
+    pub excused: usize,
+}
+
+impl AttendanceSummary {
+    fn count(&mut self, status: AttendanceStatus) {
+        match status {
+            AttendanceStatus::Present => self.present += 1,
+            AttendanceStatus::Late => self.late += 1,
+            AttendanceStatus::Absent => self.absent += 1,
+            AttendanceStatus::Excused => self.excused += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.present + self.late + self.absent + self.excused
+    }
+
+    /// 出勤率（百分比），除请假外没有任何记录时为 `None`
+    pub fn rate(&self) -> Option<f64> {
+        let counted = self.present + self.late + self.absent;
+        (counted > 0).then(|| (self.present + self.late) as f64 / counted as f64 * 100.0)
+    }
+}
+
+impl Course {
+    /// 记录学生在某次课的出勤情况，同一次课重复记录时覆盖原记录
+    pub fn record_attendance(&mut self, session: Session, student_id: u32, status: AttendanceStatus) -> AppResult<()> {
+        if !self.is_enrolled(student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 没有注册课程 {}", student_id, self.name)
+            ));
+        }
+        self.attendance.entry(session).or_default().insert(student_id, status);
+        Ok(())
+    }
+
+    /// 恢复保存过的考勤记录，已退出课程的学生保留退出前的记录
+    pub(crate) fn restore_attendance(&mut self, session: Session, student_id: u32, status: AttendanceStatus) -> AppResult<()> {
+        if !self.is_enrolled(student_id) && !self.is_withdrawn(student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 没有注册课程 {}", student_id, self.name)
+            ));
+        }
+        self.attendance.entry(session).or_default().insert(student_id, status);
+        Ok(())
+    }
+
+    /// 学生在某次课的出勤情况
+    pub fn attendance_status(&self, session: Session, student_id: u32) -> Option<AttendanceStatus> {
+        self.attendance.get(&session)?.get(&student_id).copied()
+    }
+
+    /// 有考勤记录的课次，按日期和节次排序
+    pub fn attendance_sessions(&self) -> Vec<Session> {
+        self.attendance.keys().copied().collect()
+    }
+
+    /// 全部考勤记录 (课次, 学生ID, 出勤情况)，按课次和学生ID排序
+    pub(crate) fn attendance_records(&self) -> Vec<(Session, u32, AttendanceStatus)> {
+        self.attendance.iter()
+            .flat_map(|(&session, records)| records.iter().map(move |(&id, &status)| (session, id, status)))
+            .collect()
+    }
+
+    /// 一个学生的出勤汇总
+    pub fn student_attendance(&self, student_id: u32) -> AttendanceSummary {
+        let mut summary = AttendanceSummary::default();
+        for status in self.attendance.values().filter_map(|records| records.get(&student_id)) {
+            summary.count(*status);
+        }
+        summary
+    }
+
+    /// 全体注册学生的出勤汇总
+    pub fn attendance_summary(&self) -> AttendanceSummary {
+        let mut summary = AttendanceSummary::default();
+        for (student_id, status) in self.attendance.values().flat_map(|records| records.iter()) {
+            if self.is_enrolled(*student_id) {
+                summary.count(*status);
+            }
+        }
+        summary
+    }
+
+    /// 出勤率低于警戒线（百分比）的注册学生ID，按从小到大排序；没有出勤率的学生不计入
+    pub fn students_below_attendance(&self, threshold: f64) -> Vec<u32> {
+        self.student_ids()
+            .into_iter()
+            .filter(|&id| self.student_attendance(id).rate().is_some_and(|rate| rate < threshold))
+            .collect()
+    }
+
+    /// 移除学生的全部考勤记录，没有记录的课次一并删除
+    pub(crate) fn clear_attendance(&mut self, student_id: u32) {
+        for records in self.attendance.values_mut() {
+            records.remove(&student_id);
+        }
+        self.attendance.retain(|_, records| !records.is_empty());
+    }
+}
+
+impl EnrollmentManager {
+    /// 记录学生在某门课程某次课的出勤情况
+    pub fn record_attendance(
+        &mut self,
+        course_id: u32,
+        session: Session,
+        student_id: u32,
+        status: AttendanceStatus,
+    ) -> AppResult<()> {
+        self.run_operation("record_attendance", |manager| {
+            if manager.get_student(student_id).is_none() {
+                return Err(AppError::NotFound(format!("学生ID {} 不存在", student_id)));
+            }
+            manager.update_course(course_id, |course| course.record_attendance(session, student_id, status))
+        })
+    }
+
+    /// 按管理器的出勤率警戒线，列出课程中出勤率偏低的学生ID
+    pub fn students_below_attendance(&self, course_id: u32) -> AppResult<Vec<u32>> {
+        let course = self.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(format!("课程ID {} 不存在", course_id)))?;
+        Ok(course.students_below_attendance(self.attendance_threshold()))
+    }
+
+    /// 学生在已注册的各门课程中的出勤汇总，按课程ID排序
+    pub fn student_attendance(&self, student_id: u32) -> AppResult<Vec<(u32, AttendanceSummary)>> {
+        if self.get_student(student_id).is_none() {
+            return Err(AppError::NotFound(format!("学生ID {} 不存在", student_id)));
+        }
+        let mut courses: Vec<&Course> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.is_enrolled(student_id))
+            .collect();
+        courses.sort_by_key(|c| c.id);
+        Ok(courses.into_iter().map(|c| (c.id, c.student_attendance(student_id))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::test_support::{date, manager_with};
+    use crate::Student;
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四"), (3, "王五")], &[(101, "Rust编程", 3)]);
+        for id in 1..=3 {
+            manager.enroll_student_in_course(id, 101).unwrap();
+        }
+        use AttendanceStatus::*;
+        let sessions = [
+            ("2024-09-02", [Present, Absent, Excused]),
+            ("2024-09-09", [Late, Absent, Present]),
+            ("2024-09-16", [Present, Present, Excused]),
+            ("2024-09-23", [Present, Absent, Absent]),
+        ];
+        for (day, statuses) in sessions {
+            for (student_id, status) in (1..=3).zip(statuses) {
+                manager.record_attendance(101, date(day).into(), student_id, status).unwrap();
+            }
+        }
+        manager
+    }
+
+    #[test]
+    fn test_attendance_rates() {
+        let manager = manager();
+        let course = manager.get_course(101).unwrap();
+        assert_eq!(course.attendance_sessions().len(), 4);
+        assert_eq!(course.attendance_status(date("2024-09-09").into(), 1), Some(AttendanceStatus::Late));
+
+        assert_eq!(course.student_attendance(1).rate(), Some(100.0));
+        assert_eq!(course.student_attendance(2).rate(), Some(25.0));
+        // 请假的两次课不计入
+        assert_eq!(course.student_attendance(3).rate(), Some(50.0));
+        assert_eq!(course.student_attendance(9), AttendanceSummary::default());
+        assert_eq!(course.student_attendance(9).rate(), None);
+
+        let summary = course.attendance_summary();
+        assert_eq!(summary.total(), 12);
+        assert_eq!(summary.rate(), Some(60.0));
+        assert_eq!(manager.student_attendance(2).unwrap()[0].1.absent, 3);
+    }
+
+    #[test]
+    fn test_sessions_on_the_same_day() {
+        let mut manager = manager();
+        let second = Session::new(date("2024-09-02"), 2).unwrap();
+        manager.record_attendance(101, second, 1, AttendanceStatus::Absent).unwrap();
+        let course = manager.get_course(101).unwrap();
+        assert_eq!(course.attendance_sessions().len(), 5);
+        assert_eq!(course.attendance_status(date("2024-09-02").into(), 1), Some(AttendanceStatus::Present));
+        assert_eq!(course.student_attendance(1).rate(), Some(80.0));
+
+        assert_eq!(second.to_string(), "2024-09-02#2");
+        assert_eq!("2024-09-02#2".parse::<Session>().unwrap(), second);
+        assert_eq!("2024-09-02".parse::<Session>().unwrap().period(), 1);
+        assert!("2024-09-02#0".parse::<Session>().is_err());
+        assert!("2024-09-02#第二节".parse::<Session>().is_err());
+    }
+
+    #[test]
+    fn test_flags_students_below_threshold() {
+        let mut manager = manager();
+        assert_eq!(manager.students_below_attendance(101).unwrap(), vec![2, 3]);
+        manager.set_attendance_threshold(50.0).unwrap();
+        assert_eq!(manager.students_below_attendance(101).unwrap(), vec![2]);
+        assert!(manager.set_attendance_threshold(120.0).is_err());
+        assert!(manager.students_below_attendance(999).is_err());
+    }
+
+    #[test]
+    fn test_attendance_requires_enrollment() {
+        let mut manager = manager();
+        manager.add_student(Student::new(4, "赵六".to_string(), 20).unwrap()).unwrap();
+        assert!(manager.record_attendance(101, date("2024-09-02").into(), 4, AttendanceStatus::Present).is_err());
+        assert!(manager.record_attendance(101, date("2024-09-02").into(), 9, AttendanceStatus::Present).is_err());
+
+        // 退课后考勤记录随注册记录一起删除
+        manager.drop_course(2, 101, date("2024-09-10")).unwrap();
+        let course = manager.get_course(101).unwrap();
+        assert_eq!(course.student_attendance(2).total(), 0);
+        assert_eq!(course.attendance_summary().total(), 8);
+        assert_eq!("迟到".parse::<AttendanceStatus>().unwrap(), AttendanceStatus::Late);
+        assert!("旷课".parse::<AttendanceStatus>().is_err());
+    }
+}
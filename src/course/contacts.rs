@@ -0,0 +1,102 @@
+//! 学生联系方式管理模块
+//!
+//! 学生邮箱在整个管理器内不能重复（不区分大小写），
+//! 添加学生和通过 `update_student` 修改学生时都会检查。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::contact::{ContactInfo, Guardian};
+use crate::Student;
+
+impl EnrollmentManager {
+    /// 修改学生的联系方式
+    pub fn update_contact(&mut self, student_id: u32, contact: ContactInfo) -> AppResult<()> {
+        self.run_operation("update_contact", |manager| {
+            manager.update_student(student_id, |student| {
+                student.contact = contact;
+                Ok(())
+            })
+        })
+    }
+
+    pub fn add_guardian(&mut self, student_id: u32, guardian: Guardian) -> AppResult<()> {
+        self.run_operation("add_guardian", |manager| {
+            manager.update_student(student_id, |student| student.add_guardian(guardian))
+        })
+    }
+
+    pub fn remove_guardian(&mut self, student_id: u32, name: &str) -> AppResult<Guardian> {
+        self.run_operation("remove_guardian", |manager| {
+            manager.update_student(student_id, |student| student.remove_guardian(name))
+        })
+    }
+
+    /// 按邮箱查找学生，不区分大小写
+    pub fn find_student_by_email(&self, email: &str) -> Option<&Student> {
+        let email = email.trim();
+        self.list_all_students()
+            .into_iter()
+            .find(|s| s.email().is_some_and(|e| e.eq_ignore_ascii_case(email)))
+    }
+
+    /// 检查学生的邮箱没有被其他学生使用
+    pub(crate) fn check_unique_email(&self, student: &Student) -> AppResult<()> {
+        let Some(email) = student.email() else {
+            return Ok(());
+        };
+        match self.find_student_by_email(email) {
+            Some(other) if other.id != student.id => Err(AppError::ValidationError(
+                format!("邮箱 {} 已被学生 {}（ID {}）使用", email, other.name, other.id)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::test_support::manager_with;
+
+    fn contact(email: &str) -> ContactInfo {
+        ContactInfo::new(Some(email.to_string()), None, None).unwrap()
+    }
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四")], &[]);
+        manager.update_contact(1, contact("zhangsan@example.com")).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_duplicate_emails_are_rejected() {
+        let mut manager = manager();
+        let duplicate = Student::new(3, "王五".to_string(), 20).unwrap().with_contact(contact("ZhangSan@Example.com"));
+        assert!(manager.add_student(duplicate).is_err());
+        assert!(manager.get_student(3).is_none());
+
+        assert!(manager.update_contact(2, contact("zhangsan@example.com")).is_err());
+        assert_eq!(manager.get_student(2).unwrap().email(), None);
+        manager.update_contact(2, contact("lisi@example.com")).unwrap();
+        // 学生保留自己原来的邮箱不算重复
+        manager.update_contact(1, contact("zhangsan@example.com")).unwrap();
+
+        assert_eq!(manager.find_student_by_email(" LISI@example.com").unwrap().id, 2);
+        assert!(manager.find_student_by_email("nobody@example.com").is_none());
+    }
+
+    #[test]
+    fn test_guardians() {
+        let mut manager = manager();
+        let guardian = Guardian::new("李父".to_string(), "父亲".to_string(), contact("lifu@example.com")).unwrap();
+        manager.add_guardian(2, guardian.clone()).unwrap();
+        assert!(manager.add_guardian(2, guardian).is_err());
+        assert!(manager.add_guardian(9, Guardian::new(
+            "王母".to_string(), "母亲".to_string(), contact("wangmu@example.com"),
+        ).unwrap()).is_err());
+
+        assert_eq!(manager.get_student(2).unwrap().guardians[0].relationship(), "父亲");
+        assert_eq!(manager.remove_guardian(2, "李父").unwrap().name(), "李父");
+        assert!(manager.remove_guardian(2, "李父").is_err());
+    }
+}
@@ -1,78 +1,507 @@
 //! 课程注册管理模块
 
+use super::attendance::check_attendance_threshold;
+use super::instructor::Instructor;
+use super::programme::Programme;
+use super::term::Term;
+use super::waitlist::EnrollmentOutcome;
 use crate::errors::{AppError, AppResult};
+use crate::storage::{MemoryStorage, Storage};
+use crate::student::grade::{Grade, GradeMark};
+use crate::student::grading_scale::GradingScale;
 use crate::{Course, Student};
-use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
 
+#[derive(Debug)]
 pub struct EnrollmentManager {
-    courses: HashMap<u32, Course>,
-    students: HashMap<u32, Student>,
+    storage: Box<dyn Storage>,
 }
 
 impl EnrollmentManager {
     pub fn new() -> Self {
-        EnrollmentManager {
-            courses: HashMap::new(),
-            students: HashMap::new(),
-        }
+        Self::with_storage(Box::new(MemoryStorage::new()))
+    }
+    
+    /// 使用指定的存储后端创建管理器
+    pub fn with_storage(storage: Box<dyn Storage>) -> Self {
+        EnrollmentManager { storage }
+    }
+    
+    /// 当前使用的等级制，所有分数成绩的等级都按它评定
+    ///
+    /// 等级制和其他数据一起保存在存储后端中。
+    pub fn grading_scale(&self) -> &GradingScale {
+        self.storage.grading_scale()
+    }
+    
+    /// 更换等级制，已经录入的分数成绩按新等级制重新评定等级
+    ///
+    /// 等级制是追溯的：成绩记录上的等级、成绩单和先修要求检查都按当前等级制评定，
+    /// 更换后它们仍然一致。不以分数记录的成绩不受影响。
+    pub fn set_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        self.run_operation("set_grading_scale", |manager| {
+            let mut regraded = Vec::new();
+            for student in manager.storage.list_students() {
+                let mut updated = student.clone();
+                for grade in &mut updated.grades {
+                    grade.regrade(&scale);
+                }
+                if updated.grades != student.grades {
+                    regraded.push(updated);
+                }
+            }
+            manager.storage.save_grading_scale(scale)?;
+            for student in regraded {
+                manager.storage.save_student(student)?;
+            }
+            Ok(())
+        })
+    }
+    
+    /// 出勤率警戒线（百分比），出勤率低于它的学生会被标记出来
+    ///
+    /// 警戒线和等级制一样保存在存储后端中。
+    pub fn attendance_threshold(&self) -> f64 {
+        self.storage.attendance_threshold()
+    }
+    
+    pub fn set_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        check_attendance_threshold(threshold)?;
+        self.run_operation("set_attendance_threshold", |manager| {
+            manager.storage.save_attendance_threshold(threshold)
+        })
+    }
+    
+    pub(crate) fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+    
+    /// 以指定的操作名执行修改，期间的写入在存储后端中都记在这个操作名下
+    ///
+    /// 操作可以嵌套，写入记在最外层的操作名下。
+    pub(crate) fn run_operation<T>(&mut self, name: &str, run: impl FnOnce(&mut Self) -> T) -> T {
+        self.storage.begin_operation(name);
+        let result = run(self);
+        self.storage.end_operation();
+        result
+    }
+    
+    pub fn add_term(&mut self, term: Term) -> AppResult<()> {
+        self.run_operation("add_term", |manager| {
+            if manager.storage.get_term(term.id).is_some() {
+                return Err(AppError::ValidationError(
+                    format!("学期ID {} 已存在", term.id)
+                ));
+            }
+            
+            manager.storage.save_term(term)
+        })
     }
     
     pub fn add_course(&mut self, course: Course) -> AppResult<()> {
-        if self.courses.contains_key(&course.id) {
-            return Err(AppError::ValidationError(
-                format!("课程ID {} 已存在", course.id)
-            ));
-        }
+        self.run_operation("add_course", |manager| {
+            if manager.storage.get_course(course.id).is_some() {
+                return Err(AppError::ValidationError(
+                    format!("课程ID {} 已存在", course.id)
+                ));
+            }
+            if let Some(term_id) = course.term_id {
+                manager.require_term(term_id)?;
+            }
+            if let Some(instructor_id) = course.instructor_id {
+                manager.require_instructor(instructor_id)?;
+            }
+            if let Some(prerequisite) = course.prerequisite() {
+                manager.check_prerequisite(course.id, prerequisite)?;
+            }
+            
+            manager.storage.save_course(course)
+        })
+    }
+    
+    pub fn add_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        self.run_operation("add_instructor", |manager| {
+            if manager.storage.get_instructor(instructor.id).is_some() {
+                return Err(AppError::ValidationError(
+                    format!("教师ID {} 已存在", instructor.id)
+                ));
+            }
+            
+            manager.storage.save_instructor(instructor)
+        })
+    }
+    
+    /// 添加培养方案，引用的课程必须已经存在
+    pub fn add_programme(&mut self, programme: Programme) -> AppResult<()> {
+        self.run_operation("add_programme", |manager| {
+            if manager.storage.get_programme(programme.id).is_some() {
+                return Err(AppError::ValidationError(
+                    format!("培养方案ID {} 已存在", programme.id)
+                ));
+            }
+            manager.check_programme(&programme)?;
+            
+            manager.storage.save_programme(programme)
+        })
+    }
+    
+    /// 为课程安排授课教师，替换原来的教师
+    pub fn assign_instructor(&mut self, course_id: u32, instructor_id: u32) -> AppResult<()> {
+        self.run_operation("assign_instructor", |manager| {
+            manager.require_instructor(instructor_id)?;
+            manager.update_course(course_id, |course| {
+                course.instructor_id = Some(instructor_id);
+                Ok(())
+            })
+        })
+    }
+    
+    pub(crate) fn require_course(&self, course_id: u32) -> AppResult<&Course> {
+        self.storage.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("课程ID {} 不存在", course_id)
+            ))
+    }
+    
+    pub(crate) fn require_term(&self, term_id: u32) -> AppResult<&Term> {
+        self.storage.get_term(term_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学期ID {} 不存在", term_id)
+            ))
+    }
+    
+    pub fn add_student(&mut self, mut student: Student) -> AppResult<()> {
+        self.run_operation("add_student", |manager| {
+            if manager.storage.get_student(student.id).is_some() {
+                return Err(AppError::ValidationError(
+                    format!("学生ID {} 已存在", student.id)
+                ));
+            }
+            manager.check_unique_email(&student)?;
+            
+            // 学生自带的分数成绩按管理器的等级制评定，与通过管理器录入的成绩一致
+            for grade in &mut student.grades {
+                grade.regrade(manager.storage.grading_scale());
+            }
+            manager.storage.save_student(student)
+        })
+    }
+    
+    /// 为学生注册课程，课程已满时排入候补名单
+    pub fn enroll_student_in_course(
+        &mut self,
+        student_id: u32,
+        course_id: u32,
+    ) -> AppResult<EnrollmentOutcome> {
+        self.run_operation("enroll_student_in_course", |manager| {
+            manager.require_student(student_id)?.require_can_enroll()?;
+            let mut course = manager.require_course(course_id)?.clone();
+            
+            // 候补也需要满足注册条件；已注册的学生由课程报告重复注册
+            if !course.is_enrolled(student_id) {
+                manager.check_can_enroll(student_id, &course)?;
+            }
+            
+            // 注册学生，课程只记录学生ID；在以前学期退出过的学生重新开始修读
+            let outcome = course.enroll_student(student_id)?;
+            if course.is_withdrawn(student_id) {
+                course.clear_student_records(student_id);
+            }
+            manager.storage.save_course(course)?;
+            Ok(outcome)
+        })
+    }
+    
+    /// 检查学生可以注册课程：学生存在且学籍状态允许选课，本学期没有退出过该课程，
+    /// 满足先修要求，并且与已注册的课程没有上课时间冲突
+    pub(crate) fn check_can_enroll(&self, student_id: u32, course: &Course) -> AppResult<()> {
+        self.require_student(student_id)?.require_can_enroll()?;
+        self.check_not_withdrawn(student_id, course)?;
+        self.check_prerequisites(student_id, course.id)?;
+        self.check_schedule_conflicts(student_id, course.id)
+    }
+    
+    /// 修改课程，闭包成功返回后把课程写回存储
+    ///
+    /// 闭包修改了课程ID时返回错误，课程不会写回。
+    pub fn update_course<T, F>(&mut self, course_id: u32, update: F) -> AppResult<T>
+    where
+        F: FnOnce(&mut Course) -> AppResult<T>,
+    {
+        self.run_operation("update_course", |manager| {
+            let mut course = manager.storage.get_course(course_id)
+                .ok_or_else(|| AppError::NotFound(
+                    format!("课程ID {} 不存在", course_id)
+                ))?
+                .clone();
+            
+            let result = update(&mut course)?;
+            if course.id != course_id {
+                return Err(AppError::ValidationError(
+                    format!("课程ID不能修改（{} 改为 {}）", course_id, course.id)
+                ));
+            }
+            manager.storage.save_course(course)?;
+            Ok(result)
+        })
+    }
+    
+    /// 修改学期，闭包成功返回后把学期写回存储
+    pub fn update_term<T, F>(&mut self, term_id: u32, update: F) -> AppResult<T>
+    where
+        F: FnOnce(&mut Term) -> AppResult<T>,
+    {
+        self.run_operation("update_term", |manager| {
+            let mut term = manager.require_term(term_id)?.clone();
+            let result = update(&mut term)?;
+            manager.storage.save_term(term)?;
+            Ok(result)
+        })
+    }
+    
+    /// 修改学生，闭包成功返回后把学生写回存储
+    ///
+    /// 学生数据只保存在这里，所有课程看到的都是修改后的数据。
+    /// 修改了学生ID，或者修改后的邮箱与其他学生重复时不会写回。
+    pub fn update_student<T, F>(&mut self, student_id: u32, update: F) -> AppResult<T>
+    where
+        F: FnOnce(&mut Student) -> AppResult<T>,
+    {
+        self.run_operation("update_student", |manager| {
+            let mut student = manager.require_student(student_id)?.clone();
+            let result = update(&mut student)?;
+            manager.store_student(student_id, student)?;
+            Ok(result)
+        })
+    }
+    
+    /// 修改课程和学生，两者的闭包都成功返回后先写回课程再写回学生
+    ///
+    /// 存储后端逐个写入对象，学生写回失败时把课程写回修改前的状态，
+    /// 课程和学生不会只更新其中一个。
+    pub(crate) fn update_course_and_student<C, S>(
+        &mut self,
+        course_id: u32,
+        student_id: u32,
+        update_course: C,
+        update_student: S,
+    ) -> AppResult<()>
+    where
+        C: FnOnce(&mut Course) -> AppResult<()>,
+        S: FnOnce(&mut Student) -> AppResult<()>,
+    {
+        let previous = self.require_course(course_id)?.clone();
+        let mut student = self.require_student(student_id)?.clone();
+        update_student(&mut student)?;
         
-        self.courses.insert(course.id, course);
+        self.update_course(course_id, update_course)?;
+        if let Err(e) = self.store_student(student_id, student) {
+            if let Err(rollback) = self.storage.save_course(previous) {
+                return Err(AppError::StorageError(
+                    format!("{}；恢复课程ID {} 也失败了: {}", e, course_id, rollback)
+                ));
+            }
+            return Err(e);
+        }
         Ok(())
     }
     
-    pub fn add_student(&mut self, student: Student) -> AppResult<()> {
-        if self.students.contains_key(&student.id) {
+    /// 取得学生的可修改副本，调用 `StudentMut::save` 后写回存储
+    pub fn get_student_mut(&mut self, student_id: u32) -> Option<StudentMut<'_>> {
+        let student = self.storage.get_student(student_id)?.clone();
+        Some(StudentMut { manager: self, student_id, student })
+    }
+    
+    pub(crate) fn require_student(&self, student_id: u32) -> AppResult<&Student> {
+        self.storage.get_student(student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不存在", student_id)
+            ))
+    }
+    
+    /// 把修改过的学生写回存储，学生ID必须保持不变
+    ///
+    /// 只有邮箱改变时才检查它没有被其他学生使用。
+    pub(crate) fn store_student(&mut self, student_id: u32, student: Student) -> AppResult<()> {
+        if student.id != student_id {
             return Err(AppError::ValidationError(
-                format!("学生ID {} 已存在", student.id)
+                format!("学生ID不能修改（{} 改为 {}）", student_id, student.id)
             ));
         }
-        
-        self.students.insert(student.id, student);
-        Ok(())
+        let previous_email = self.storage.get_student(student_id).and_then(|s| s.email());
+        if student.email() != previous_email {
+            self.check_unique_email(&student)?;
+        }
+        self.storage.save_student(student)
     }
     
-    pub fn enroll_student_in_course(&mut self, student_id: u32, course_id: u32) -> AppResult<()> {
-        // 检查学生是否存在
-        let student = self.students.get(&student_id)
-            .ok_or_else(|| AppError::NotFound(
-                format!("学生ID {} 不存在", student_id)
-            ))?;
-        
-        // 检查课程是否存在
-        let course = self.courses.get_mut(&course_id)
+    pub fn add_grade(&mut self, student_id: u32, subject: String, score: f64) -> AppResult<()> {
+        self.run_operation("add_grade", |manager| {
+            let mut student = manager.require_student(student_id)?.clone();
+            student.add_grade_with_scale(subject, score, manager.storage.grading_scale())?;
+            manager.store_student(student_id, student)
+        })
+    }
+    
+    /// 录入学生在某门课程中的成绩，学生必须已经注册该课程
+    pub fn add_course_grade(
+        &mut self,
+        student_id: u32,
+        course_id: u32,
+        subject: String,
+        score: f64,
+    ) -> AppResult<()> {
+        self.run_operation("add_course_grade", |manager| {
+            let course = manager.enrolled_course(student_id, course_id)?;
+            let grade = course.new_grade(subject, score, manager.storage.grading_scale())?;
+            manager.update_student(student_id, |student| {
+                student.grades.push(grade);
+                Ok(())
+            })
+        })
+    }
+    
+    /// 录入学生在某门课程中不以分数记录的成绩，学生必须已经注册该课程
+    ///
+    /// 这条成绩决定学生在该课程的结果，见 `Course::student_mark`。
+    pub fn add_course_mark(
+        &mut self,
+        student_id: u32,
+        course_id: u32,
+        subject: String,
+        mark: GradeMark,
+    ) -> AppResult<()> {
+        self.run_operation("add_course_mark", |manager| {
+            let course = manager.enrolled_course(student_id, course_id)?;
+            let grade = course.new_mark(subject, mark)?;
+            manager.update_student(student_id, |student| {
+                student.grades.push(grade);
+                Ok(())
+            })
+        })
+    }
+    
+    /// 学生已经注册的课程
+    pub(crate) fn enrolled_course(&self, student_id: u32, course_id: u32) -> AppResult<&Course> {
+        let course = self.storage.get_course(course_id)
             .ok_or_else(|| AppError::NotFound(
                 format!("课程ID {} 不存在", course_id)
             ))?;
-        
-        // 注册学生
-        course.enroll_student(student.clone())?;
-        
-        println!("学生 {} 成功注册课程 {}", student.name, course.name);
-        Ok(())
+        if !course.is_enrolled(student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 没有注册课程 {}", student_id, course.name)
+            ));
+        }
+        Ok(course)
+    }
+    
+    /// 录入属于指定学期的成绩
+    pub fn add_grade_in_term(
+        &mut self,
+        student_id: u32,
+        term_id: u32,
+        subject: String,
+        score: f64,
+    ) -> AppResult<()> {
+        self.run_operation("add_grade_in_term", |manager| {
+            manager.require_term(term_id)?;
+            let grade = Grade::with_scale(subject, score, manager.storage.grading_scale())?.in_term(term_id);
+            manager.update_student(student_id, |student| {
+                student.grades.push(grade);
+                Ok(())
+            })
+        })
     }
     
     pub fn get_course(&self, course_id: u32) -> Option<&Course> {
-        self.courses.get(&course_id)
+        self.storage.get_course(course_id)
     }
     
     pub fn get_student(&self, student_id: u32) -> Option<&Student> {
-        self.students.get(&student_id)
+        self.storage.get_student(student_id)
     }
     
     pub fn list_all_courses(&self) -> Vec<&Course> {
-        self.courses.values().collect()
+        self.storage.list_courses()
     }
     
     pub fn list_all_students(&self) -> Vec<&Student> {
-        self.students.values().collect()
+        self.storage.list_students()
+    }
+    
+    /// 注册了指定课程的学生，按ID排序
+    pub fn list_course_students(&self, course_id: u32) -> AppResult<Vec<&Student>> {
+        let course = self.storage.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("课程ID {} 不存在", course_id)
+            ))?;
+        Ok(course.list_students(self))
+    }
+    
+    pub fn get_term(&self, term_id: u32) -> Option<&Term> {
+        self.storage.get_term(term_id)
+    }
+    
+    pub fn get_instructor(&self, instructor_id: u32) -> Option<&Instructor> {
+        self.storage.get_instructor(instructor_id)
+    }
+    
+    /// 所有教师，按ID排序
+    pub fn list_instructors(&self) -> Vec<&Instructor> {
+        let mut instructors = self.storage.list_instructors();
+        instructors.sort_by_key(|i| i.id);
+        instructors
+    }
+    
+    pub fn get_programme(&self, programme_id: u32) -> Option<&Programme> {
+        self.storage.get_programme(programme_id)
+    }
+    
+    /// 所有培养方案，按ID排序
+    pub fn list_programmes(&self) -> Vec<&Programme> {
+        let mut programmes = self.storage.list_programmes();
+        programmes.sort_by_key(|p| p.id);
+        programmes
+    }
+    
+    /// 所有学期，按开始日期排序
+    pub fn list_terms(&self) -> Vec<&Term> {
+        let mut terms = self.storage.list_terms();
+        terms.sort_by_key(|t| (t.start_date, t.id));
+        terms
+    }
+}
+
+/// `EnrollmentManager::get_student_mut` 返回的学生副本
+///
+/// 通过解引用读取和修改学生，调用 `save` 后才写回管理器；
+/// 没有保存就丢弃时修改不生效。
+#[must_use = "修改只有调用 save 后才会写回管理器"]
+pub struct StudentMut<'a> {
+    manager: &'a mut EnrollmentManager,
+    student_id: u32,
+    student: Student,
+}
+
+impl StudentMut<'_> {
+    /// 写回修改，规则与 `EnrollmentManager::update_student` 相同
+    pub fn save(self) -> AppResult<()> {
+        let StudentMut { manager, student_id, student } = self;
+        manager.run_operation("update_student", |manager| manager.store_student(student_id, student))
+    }
+}
+
+impl Deref for StudentMut<'_> {
+    type Target = Student;
+    
+    fn deref(&self) -> &Student {
+        &self.student
+    }
+}
+
+impl DerefMut for StudentMut<'_> {
+    fn deref_mut(&mut self) -> &mut Student {
+        &mut self.student
     }
 }
 
@@ -80,4 +509,25 @@ impl Default for EnrollmentManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// 两个管理器的数据相同即视为相等，不比较存储后端、等级制和出勤率警戒线配置
+impl PartialEq for EnrollmentManager {
+    fn eq(&self, other: &Self) -> bool {
+        let mut students = self.list_all_students();
+        let mut other_students = other.list_all_students();
+        students.sort_by_key(|s| s.id);
+        other_students.sort_by_key(|s| s.id);
+        
+        let mut courses = self.list_all_courses();
+        let mut other_courses = other.list_all_courses();
+        courses.sort_by_key(|c| c.id);
+        other_courses.sort_by_key(|c| c.id);
+        
+        students == other_students
+            && courses == other_courses
+            && self.list_terms() == other.list_terms()
+            && self.list_instructors() == other.list_instructors()
+            && self.list_programmes() == other.list_programmes()
+    }
+}
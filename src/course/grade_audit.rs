@@ -0,0 +1,173 @@
+//! 成绩修改与审计模块
+//!
+//! 通过管理器修改学生的成绩，新分数按管理器的等级制重新评定等级；
+//! 并汇总全部修改记录，用于审计和处理成绩申诉。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::amendment::{GradeAmendment, GradeChange};
+
+/// 审计日志中的一条成绩修改
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GradeAuditEntry {
+    pub student_id: u32,
+    pub student_name: String,
+    pub subject: String,
+    /// 成绩所属的课程，不属于任何课程时为 `None`
+    pub course_id: Option<u32>,
+    pub amendment: GradeAmendment,
+}
+
+impl std::fmt::Display for GradeAuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}] {} ", self.student_id, self.student_name)?;
+        if let Some(course_id) = self.course_id {
+            write!(f, "课程{} ", course_id)?;
+        }
+        write!(f, "{} {}", self.subject, self.amendment)
+    }
+}
+
+impl EnrollmentManager {
+    /// 修改学生不属于任何课程的成绩，同一科目有多条成绩时修改最近录入的一条
+    pub fn amend_grade(&mut self, student_id: u32, subject: &str, change: GradeChange) -> AppResult<GradeAmendment> {
+        self.run_operation("amend_grade", |manager| {
+            let mut student = manager.require_student(student_id)?.clone();
+            let amendment = student.amend_grade_in(None, subject, change, manager.grading_scale())?.clone();
+            manager.store_student(student_id, student)?;
+            Ok(amendment)
+        })
+    }
+
+    /// 修改学生在某门课程中的成绩，学生退出课程后仍然可以修改
+    pub fn amend_course_grade(
+        &mut self,
+        student_id: u32,
+        course_id: u32,
+        subject: &str,
+        change: GradeChange,
+    ) -> AppResult<GradeAmendment> {
+        self.run_operation("amend_course_grade", |manager| {
+            if manager.get_course(course_id).is_none() {
+                return Err(AppError::NotFound(format!("课程ID {} 不存在", course_id)));
+            }
+            let mut student = manager.require_student(student_id)?.clone();
+            let amendment = student.amend_grade_in(Some(course_id), subject, change, manager.grading_scale())?.clone();
+            manager.store_student(student_id, student)?;
+            Ok(amendment)
+        })
+    }
+
+    /// 一个学生的全部成绩修改记录，按修改日期排序
+    pub fn student_grade_audit(&self, student_id: u32) -> AppResult<Vec<GradeAuditEntry>> {
+        if self.get_student(student_id).is_none() {
+            return Err(AppError::NotFound(format!("学生ID {} 不存在", student_id)));
+        }
+        Ok(self.audit_entries(Some(student_id)))
+    }
+
+    /// 所有学生的成绩修改记录，按修改日期和学生ID排序；同一天的修改保持修改顺序
+    pub fn grade_audit_log(&self) -> Vec<GradeAuditEntry> {
+        self.audit_entries(None)
+    }
+
+    fn audit_entries(&self, student_id: Option<u32>) -> Vec<GradeAuditEntry> {
+        let mut students = self.list_all_students();
+        students.retain(|s| student_id.is_none_or(|id| s.id == id));
+        students.sort_by_key(|s| s.id);
+
+        let mut entries: Vec<GradeAuditEntry> = students.into_iter()
+            .flat_map(|student| {
+                student.grade_amendments().into_iter().map(move |(grade, amendment)| GradeAuditEntry {
+                    student_id: student.id,
+                    student_name: student.name.clone(),
+                    subject: grade.subject().to_string(),
+                    course_id: grade.course_id(),
+                    amendment: amendment.clone(),
+                })
+            })
+            .collect();
+        entries.sort_by_key(|e| (e.amendment.date, e.student_id));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::student::amendment::GradeValue;
+    use crate::student::grade::{GradeMark, LetterGrade};
+    use crate::utils::date::Date;
+    use crate::course::test_support::manager_with;
+
+    fn change(value: GradeValue, reason: &str, date: &str) -> GradeChange {
+        GradeChange::new(value, reason, "王老师", date.parse().unwrap()).unwrap()
+    }
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四")], &[(101, "Rust编程", 3)]);
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.add_course_grade(1, 101, "期末".to_string(), 58.0).unwrap();
+        manager.add_grade(2, "数学".to_string(), 75.0).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_amend_course_grade_keeps_history() {
+        let mut manager = manager();
+        manager.amend_course_grade(1, 101, "期末", change(GradeValue::Score(62.0), "卷面复核", "2024-12-20")).unwrap();
+        let amendment = manager.amend_course_grade(
+            1, 101, "期末", change(GradeValue::Mark(GradeMark::Incomplete), "缓考", "2024-12-22"),
+        ).unwrap();
+        assert_eq!(amendment.old_value, GradeValue::Score(62.0));
+
+        let grade = &manager.get_student(1).unwrap().grades_in_course(101)[0];
+        assert_eq!(grade.mark(), Some(GradeMark::Incomplete));
+        assert_eq!(grade.course_id(), Some(101));
+        assert_eq!(grade.amendments().len(), 2);
+        assert_eq!(grade.amendments()[0].old_value, GradeValue::Score(58.0));
+        assert_eq!(
+            grade.amendments()[0].to_string(),
+            "2024-12-20 王老师: 58 -> 62（卷面复核）"
+        );
+
+        manager.amend_course_grade(1, 101, "期末", change(GradeValue::Score(91.0), "补考", "2025-01-10")).unwrap();
+        let grade = &manager.get_student(1).unwrap().grades_in_course(101)[0];
+        assert_eq!(grade.letter_grade(), Some(&LetterGrade::A));
+    }
+
+    #[test]
+    fn test_invalid_amendments_are_rejected() {
+        let mut manager = manager();
+        let date: Date = "2024-12-20".parse().unwrap();
+        assert!(GradeChange::new(GradeValue::Score(60.0), " ", "王老师", date).is_err());
+        assert!(GradeChange::new(GradeValue::Score(60.0), "复核", "", date).is_err());
+
+        // 分数越界、成绩不变、找不到成绩都不会留下记录
+        assert!(manager.amend_course_grade(1, 101, "期末", change(GradeValue::Score(101.0), "复核", "2024-12-20")).is_err());
+        assert!(manager.amend_course_grade(1, 101, "期末", change(GradeValue::Score(58.0), "复核", "2024-12-20")).is_err());
+        assert!(manager.amend_course_grade(1, 101, "期中", change(GradeValue::Score(60.0), "复核", "2024-12-20")).is_err());
+        assert!(manager.amend_course_grade(1, 999, "期末", change(GradeValue::Score(60.0), "复核", "2024-12-20")).is_err());
+        assert!(manager.amend_grade(1, "期末", change(GradeValue::Score(60.0), "复核", "2024-12-20")).is_err());
+        assert!(manager.grade_audit_log().is_empty());
+    }
+
+    #[test]
+    fn test_grade_audit_log() {
+        let mut manager = manager();
+        manager.amend_grade(2, "数学", change(GradeValue::Score(80.0), "登分错误", "2024-12-21")).unwrap();
+        manager.amend_course_grade(1, 101, "期末", change(GradeValue::Score(60.0), "卷面复核", "2024-12-22")).unwrap();
+        manager.amend_grade(2, "数学", change(GradeValue::Score(85.0), "申诉成功", "2024-12-23")).unwrap();
+
+        let log = manager.grade_audit_log();
+        assert_eq!(log.iter().map(|e| e.student_id).collect::<Vec<_>>(), vec![2, 1, 2]);
+        assert_eq!(log[1].course_id, Some(101));
+        assert_eq!(log[1].to_string(), "[1] 张三 课程101 期末 2024-12-22 王老师: 58 -> 60（卷面复核）");
+
+        let history = manager.student_grade_audit(2).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].amendment.new_value, GradeValue::Score(85.0));
+        assert!(manager.student_grade_audit(9).is_err());
+    }
+}
@@ -0,0 +1,497 @@
+//! 成绩册模块
+//!
+//! 把一门课程的学生成绩导出为宽表CSV（每个学生一行，每个科目一列），
+//! 以及把教师修改后的表格导回课程。成绩保存在管理器的学生记录中，
+//! 并记在该课程名下。不以分数记录的成绩在单元格中写作结果代码，例如 `P`、`AU`。
+//!
+//! 导入按（学生, 课程, 科目）更新成绩：已有的成绩被表格中的新值替换，
+//! 因此同一份成绩册可以反复导入。退出课程（W）时留下的成绩不导出也不被替换，
+//! 学生重新注册后的成绩另记一条。
+
+use super::enrollment::EnrollmentManager;
+use super::persistence::grade_value_text;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::student::grade::{Grade, GradeMark};
+use crate::utils::csv;
+use crate::utils::file_handler::{read_file_to_string, write_string_to_file};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// 成绩册中科目列之前的固定列
+const FIXED_COLUMNS: [&str; 2] = ["id", "name"];
+
+/// 单元格出问题时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellPolicy {
+    /// 忽略该单元格，不记录
+    Skip,
+    /// 忽略该单元格，并记录到导入报告中
+    Report,
+    /// 立即中止导入并返回错误，此前已录入的成绩保留
+    Abort,
+}
+
+/// 分数超出0-100范围时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutOfRangePolicy {
+    Skip,
+    Report,
+    Abort,
+    /// 截断到0-100之间后照常录入
+    Clamp,
+}
+
+/// 成绩册导入选项
+#[derive(Debug, Clone)]
+pub struct GradebookImportOptions {
+    pub blank: CellPolicy,
+    pub non_numeric: CellPolicy,
+    pub out_of_range: OutOfRangePolicy,
+}
+
+impl Default for GradebookImportOptions {
+    fn default() -> Self {
+        GradebookImportOptions {
+            blank: CellPolicy::Skip,
+            non_numeric: CellPolicy::Report,
+            out_of_range: OutOfRangePolicy::Report,
+        }
+    }
+}
+
+/// 被拒绝的单元格或行
+#[derive(Debug)]
+pub struct RejectedCell {
+    /// 行号，从1开始
+    pub line_number: usize,
+    /// 出错的科目列；整行出错时为 `None`
+    pub subject: Option<String>,
+    pub value: String,
+    pub error: AppError,
+}
+
+/// 成绩册导入报告
+#[derive(Debug, Default)]
+pub struct GradebookImportReport {
+    pub grades_added: usize,
+    /// 替换了已有成绩的单元格数，与原成绩相同的单元格不计入
+    pub grades_updated: usize,
+    pub rejected: Vec<RejectedCell>,
+}
+
+enum CellOutcome {
+    Add(f64),
+    Mark(GradeMark),
+    Ignore,
+    Reject(AppError),
+    Abort(AppError),
+}
+
+impl EnrollmentManager {
+    /// 导出课程的成绩册CSV，只包含记在该课程名下的成绩
+    pub fn export_gradebook(&self, course_id: u32) -> AppResult<String> {
+        let students = self.list_course_students(course_id)?;
+
+        let subjects: BTreeSet<&str> = students.iter()
+            .flat_map(|s| s.grades_in_course(course_id).into_iter()
+                .filter(|g| !g.is_withdrawn())
+                .map(|g| g.subject()))
+            .collect();
+
+        let mut header: Vec<&str> = FIXED_COLUMNS.to_vec();
+        header.extend(subjects.iter());
+        let mut output = csv::format_line(&header);
+        output.push('\n');
+
+        for student in students {
+            let mut row = vec![student.id.to_string(), student.name.clone()];
+            let grades = student.grades_in_course(course_id);
+            for subject in &subjects {
+                row.push(grades.iter()
+                    .find(|g| g.subject() == *subject && !g.is_withdrawn())
+                    .map(|g| grade_value_text(g))
+                    .unwrap_or_default());
+            }
+            output.push_str(&csv::format_line(&row));
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    /// 导出成绩册到文件
+    pub fn export_gradebook_to_file<P: AsRef<Path>>(&self, course_id: u32, path: P) -> AppResult<()> {
+        write_string_to_file(path, &self.export_gradebook(course_id)?)
+    }
+
+    /// 从CSV文件导入成绩册
+    pub fn import_gradebook_csv<P: AsRef<Path>>(
+        &mut self,
+        course_id: u32,
+        path: P,
+        options: &GradebookImportOptions,
+    ) -> AppResult<GradebookImportReport> {
+        self.run_operation("import_gradebook_csv", |manager| {
+            let content = read_file_to_string(path)?;
+            manager.import_gradebook_str(course_id, &content, options)
+        })
+    }
+
+    /// 从CSV文本导入成绩册，每个有效单元格录入或替换学生的一条课程成绩
+    ///
+    /// 表头必须以 `id,name` 开头且科目列不能重复；同一学生出现多行时，
+    /// 后面的行作为整行错误记入报告。已有修改记录的成绩只能通过成绩修改更正，
+    /// 对应单元格被拒绝。只有表头错误或某个策略为中止时才返回错误，
+    /// 中止时返回触发中止的原始错误，此前已录入的成绩仍会保存。
+    pub fn import_gradebook_str(
+        &mut self,
+        course_id: u32,
+        content: &str,
+        options: &GradebookImportOptions,
+    ) -> AppResult<GradebookImportReport> {
+        self.run_operation("import_gradebook_str", |manager| {
+            let course = manager.get_course(course_id)
+                .ok_or_else(|| AppError::NotFound(
+                    format!("课程ID {} 不存在", course_id)
+                ))?
+                .clone();
+
+            let mut pending: BTreeMap<u32, Vec<Grade>> = BTreeMap::new();
+            let result = manager.collect_gradebook(&course, content, options, &mut pending);
+
+            for (student_id, grades) in pending {
+                manager.update_student(student_id, |student| {
+                    for grade in grades {
+                        let existing = student.grades.iter_mut()
+                            .find(|g| {
+                                g.course_id() == grade.course_id() && g.subject() == grade.subject() && !g.is_withdrawn()
+                            });
+                        match existing {
+                            Some(existing) => *existing = grade,
+                            None => student.grades.push(grade),
+                        }
+                    }
+                    Ok(())
+                })?;
+            }
+            result
+        })
+    }
+
+    /// 解析成绩册，把有效成绩按学生收集到 `pending` 中
+    fn collect_gradebook(
+        &self,
+        course: &Course,
+        content: &str,
+        options: &GradebookImportOptions,
+        pending: &mut BTreeMap<u32, Vec<Grade>>,
+    ) -> AppResult<GradebookImportReport> {
+        let mut lines = content.lines().enumerate();
+        let subjects = match lines.next() {
+            Some((_, header)) => parse_header(header)?,
+            None => return Err(AppError::InvalidInput("成绩册为空".to_string())),
+        };
+
+        let mut report = GradebookImportReport::default();
+        let mut seen_students = BTreeSet::new();
+
+        for (index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_number = index + 1;
+
+            let row = parse_row(course, line, subjects.len()).and_then(|(student_id, cells)| {
+                if seen_students.insert(student_id) {
+                    Ok((student_id, cells))
+                } else {
+                    Err(AppError::ValidationError(
+                        format!("学生ID {} 在成绩册中重复出现", student_id)
+                    ))
+                }
+            });
+            let (student_id, cells) = match row {
+                Ok(row) => row,
+                Err(error) => {
+                    report.rejected.push(RejectedCell {
+                        line_number,
+                        subject: None,
+                        value: line.to_string(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            for (subject, value) in subjects.iter().zip(cells) {
+                let grade = match evaluate_cell(&value, options) {
+                    CellOutcome::Add(score) => course.new_grade(subject.clone(), score, self.grading_scale())?,
+                    CellOutcome::Mark(mark) => course.new_mark(subject.clone(), mark)?,
+                    CellOutcome::Ignore => continue,
+                    CellOutcome::Reject(error) => {
+                        report.rejected.push(RejectedCell {
+                            line_number,
+                            subject: Some(subject.clone()),
+                            value,
+                            error,
+                        });
+                        continue;
+                    }
+                    CellOutcome::Abort(error) => return Err(error),
+                };
+
+                let existing = self.get_student(student_id)
+                    .and_then(|student| student.grades_in_course(course.id).into_iter()
+                        .find(|g| g.subject() == subject && !g.is_withdrawn()));
+                match existing {
+                    Some(existing) if existing.value() == grade.value() => continue,
+                    Some(existing) if !existing.amendments().is_empty() => {
+                        report.rejected.push(RejectedCell {
+                            line_number,
+                            subject: Some(subject.clone()),
+                            value,
+                            error: AppError::ValidationError(
+                                "成绩已有修改记录，只能通过成绩修改更正".to_string()
+                            ),
+                        });
+                        continue;
+                    }
+                    Some(_) => report.grades_updated += 1,
+                    None => report.grades_added += 1,
+                }
+                pending.entry(student_id).or_default().push(grade);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// 解析一行数据，返回学生ID和科目单元格
+fn parse_row(course: &Course, line: &str, subject_count: usize) -> AppResult<(u32, Vec<String>)> {
+    let mut fields = csv::parse_line(line)?;
+    if fields.len() != FIXED_COLUMNS.len() + subject_count {
+        return Err(AppError::InvalidInput(format!(
+            "需要 {} 列，实际有 {} 列", FIXED_COLUMNS.len() + subject_count, fields.len()
+        )));
+    }
+
+    let student_id: u32 = fields[0].trim().parse()?;
+    if !course.is_enrolled(student_id) {
+        return Err(AppError::NotFound(
+            format!("学生ID {} 没有注册课程 {}", student_id, course.name)
+        ));
+    }
+
+    Ok((student_id, fields.split_off(FIXED_COLUMNS.len())))
+}
+
+fn parse_header(line: &str) -> AppResult<Vec<String>> {
+    let fields = csv::parse_line(line)?;
+    let fixed_ok = fields.len() >= FIXED_COLUMNS.len()
+        && fields.iter().zip(FIXED_COLUMNS).all(|(f, c)| f.trim().eq_ignore_ascii_case(c));
+    if !fixed_ok {
+        return Err(AppError::InvalidInput("成绩册表头必须以 id,name 开头".to_string()));
+    }
+
+    let subjects: Vec<String> = fields[FIXED_COLUMNS.len()..].iter()
+        .map(|s| s.trim().to_string())
+        .collect();
+    if subjects.iter().any(|s| s.is_empty()) {
+        return Err(AppError::InvalidInput("科目列名不能为空".to_string()));
+    }
+    let mut seen = BTreeSet::new();
+    if let Some(duplicate) = subjects.iter().find(|s| !seen.insert(s.as_str())) {
+        return Err(AppError::InvalidInput(format!("科目列 {} 重复", duplicate)));
+    }
+    Ok(subjects)
+}
+
+fn evaluate_cell(value: &str, options: &GradebookImportOptions) -> CellOutcome {
+    let value = value.trim();
+    if value.is_empty() {
+        return apply_policy(options.blank, "成绩为空".to_string());
+    }
+
+    if let Ok(mark) = value.parse::<GradeMark>() {
+        return CellOutcome::Mark(mark);
+    }
+
+    let score: f64 = match value.parse() {
+        Ok(score) if f64::is_finite(score) => score,
+        _ => return apply_policy(options.non_numeric, format!("'{}' 不是数字", value)),
+    };
+
+    if (0.0..=100.0).contains(&score) {
+        return CellOutcome::Add(score);
+    }
+
+    let message = format!("成绩 {} 必须在0-100之间", score);
+    match options.out_of_range {
+        OutOfRangePolicy::Skip => CellOutcome::Ignore,
+        OutOfRangePolicy::Report => CellOutcome::Reject(AppError::ValidationError(message)),
+        OutOfRangePolicy::Abort => CellOutcome::Abort(AppError::ValidationError(message)),
+        OutOfRangePolicy::Clamp => CellOutcome::Add(score.clamp(0.0, 100.0)),
+    }
+}
+
+fn apply_policy(policy: CellPolicy, message: String) -> CellOutcome {
+    match policy {
+        CellPolicy::Skip => CellOutcome::Ignore,
+        CellPolicy::Report => CellOutcome::Reject(AppError::ValidationError(message)),
+        CellPolicy::Abort => CellOutcome::Abort(AppError::ValidationError(message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::term::Term;
+    use crate::Student;
+
+    fn sample_manager() -> EnrollmentManager {
+        let mut manager = EnrollmentManager::new();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        manager.add_course_grade(1, 101, "作业".to_string(), 90.0).unwrap();
+        manager.add_course_grade(1, 101, "期末".to_string(), 85.5).unwrap();
+        manager.add_course_grade(2, 101, "作业".to_string(), 70.0).unwrap();
+        // 不属于课程的成绩不出现在成绩册中
+        manager.add_grade(2, "音乐".to_string(), 99.0).unwrap();
+        manager
+    }
+
+    fn course_grade(manager: &EnrollmentManager, student_id: u32, subject: &str) -> Option<f64> {
+        manager.get_student(student_id).unwrap()
+            .grades_in_course(101)
+            .into_iter()
+            .find(|g| g.subject() == subject)
+            .and_then(|g| g.score())
+    }
+
+    #[test]
+    fn test_export_gradebook() {
+        assert_eq!(
+            sample_manager().export_gradebook(101).unwrap(),
+            "id,name,作业,期末\n1,张三,90,85.5\n2,李四,70,\n"
+        );
+        assert!(sample_manager().export_gradebook(999).is_err());
+    }
+
+    #[test]
+    fn test_import_with_default_options() {
+        let mut manager = sample_manager();
+        let csv = "id,name,实验,期中\n1,张三,88,\n2,李四,abc,101\n9,无名,60,60\n";
+
+        let report = manager.import_gradebook_str(101, csv, &GradebookImportOptions::default()).unwrap();
+
+        assert_eq!(report.grades_added, 1);
+        assert_eq!(report.rejected.len(), 3);
+        assert_eq!(report.rejected[0].subject.as_deref(), Some("实验"));
+        assert_eq!(report.rejected[2].line_number, 4);
+        assert!(report.rejected[2].subject.is_none());
+        assert_eq!(course_grade(&manager, 1, "实验"), Some(88.0));
+    }
+
+    #[test]
+    fn test_marks_round_trip() {
+        let mut manager = sample_manager();
+        let csv = "id,name,期末\n1,张三,P\n2,李四,旁听\n";
+        let report = manager.import_gradebook_str(101, csv, &GradebookImportOptions::default()).unwrap();
+        assert_eq!((report.grades_added, report.grades_updated), (1, 1));
+
+        let course = manager.get_course(101).unwrap();
+        let student = manager.get_student(2).unwrap();
+        assert_eq!(course.student_mark(student), Some(GradeMark::Audit));
+        assert_eq!(course.student_score(student), None);
+        assert_eq!(
+            manager.export_gradebook(101).unwrap(),
+            "id,name,作业,期末\n1,张三,90,P\n2,李四,70,AU\n"
+        );
+    }
+
+    #[test]
+    fn test_import_policies() {
+        let options = GradebookImportOptions {
+            blank: CellPolicy::Report,
+            non_numeric: CellPolicy::Skip,
+            out_of_range: OutOfRangePolicy::Clamp,
+        };
+        let mut manager = sample_manager();
+        let report = manager.import_gradebook_str(101, "id,name,期中\n1,张三,105\n2,李四,\n", &options).unwrap();
+        assert_eq!(report.grades_added, 1);
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(course_grade(&manager, 1, "期中"), Some(100.0));
+
+        let options = GradebookImportOptions { non_numeric: CellPolicy::Abort, ..options };
+        let csv = "id,name,实验\n1,张三,75\n2,李四,优秀\n";
+        match manager.import_gradebook_str(101, csv, &options) {
+            Err(AppError::ValidationError(message)) => assert_eq!(message, "'优秀' 不是数字"),
+            other => panic!("应当返回原始错误: {:?}", other),
+        }
+        // 中止之前录入的成绩已经保存
+        assert_eq!(course_grade(&manager, 1, "实验"), Some(75.0));
+        assert!(manager.import_gradebook_str(101, "name,id\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_import_updates_existing_grades() {
+        let mut manager = sample_manager();
+        let csv = "id,name,作业,期末\n1,张三,95,85.5\n2,李四,70,60\n";
+        let report = manager.import_gradebook_str(101, csv, &GradebookImportOptions::default()).unwrap();
+
+        assert_eq!((report.grades_added, report.grades_updated), (1, 1));
+        assert_eq!(course_grade(&manager, 1, "作业"), Some(95.0));
+        assert_eq!(manager.get_student(1).unwrap().grades_in_course(101).len(), 2);
+
+        // 再次导入同一份成绩册不产生任何变化
+        let report = manager.import_gradebook_str(101, csv, &GradebookImportOptions::default()).unwrap();
+        assert_eq!((report.grades_added, report.grades_updated), (0, 0));
+        assert_eq!(manager.export_gradebook(101).unwrap(), "id,name,作业,期末\n1,张三,95,85.5\n2,李四,70,60\n");
+    }
+
+    #[test]
+    fn test_import_rejects_duplicates() {
+        let mut manager = sample_manager();
+        let options = GradebookImportOptions::default();
+        assert!(manager.import_gradebook_str(101, "id,name,期中,期中\n1,张三,80,90\n", &options).is_err());
+
+        let csv = "id,name,期中\n1,张三,80\n1,张三,90\n";
+        let report = manager.import_gradebook_str(101, csv, &options).unwrap();
+        assert_eq!(report.grades_added, 1);
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(report.rejected[0].line_number, 3);
+        assert_eq!(course_grade(&manager, 1, "期中"), Some(80.0));
+    }
+
+    #[test]
+    fn test_withdrawn_attempt_is_kept_after_reenrolment() {
+        let mut manager = sample_manager();
+        for (id, start, end) in [(1, "2024-09-01", "2025-01-15"), (2, "2025-02-20", "2025-07-10")] {
+            let term = Term::new(id, format!("学期{}", id), start.parse().unwrap(), end.parse().unwrap()).unwrap();
+            manager.add_term(term).unwrap();
+        }
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.set_term_deadlines(1, Some("2024-09-15".parse().unwrap()), None).unwrap();
+        manager.add_course_grade(1, 101, "期中".to_string(), 40.0).unwrap();
+        manager.withdraw_from_course(1, 101, "2024-10-08".parse().unwrap()).unwrap();
+        manager.assign_course_to_term(101, 2).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+
+        assert_eq!(manager.export_gradebook(101).unwrap(), "id,name,作业\n1,张三,\n2,李四,70\n");
+        let report = manager.import_gradebook_str(101, "id,name,期中\n1,张三,95\n", &GradebookImportOptions::default()).unwrap();
+        assert_eq!((report.grades_added, report.grades_updated), (1, 0));
+
+        let grades = manager.get_student(1).unwrap().grades_in_course(101);
+        let midterms: Vec<(Option<f64>, bool)> = grades.iter()
+            .filter(|g| g.subject() == "期中")
+            .map(|g| (g.score(), g.is_withdrawn()))
+            .collect();
+        assert_eq!(midterms, vec![(Some(40.0), true), (Some(95.0), false)]);
+        assert_eq!(manager.export_gradebook(101).unwrap(), "id,name,作业,期中\n1,张三,,95\n2,李四,70,\n");
+    }
+}
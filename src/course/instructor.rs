@@ -0,0 +1,202 @@
+//! 教师模块
+//!
+//! 课程通过ID引用教师，教师数据只保存在管理器中；
+//! 并提供按教师汇总的教学工作量报告。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::validator::validate_name;
+use crate::Course;
+
+/// 教师，姓名规则与学生相同
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "InstructorData"))]
+pub struct Instructor {
+    pub id: u32,
+    pub name: String,
+}
+
+impl Instructor {
+    pub fn new(id: u32, name: String) -> AppResult<Self> {
+        validate_name(&name)?;
+        Ok(Instructor { id, name })
+    }
+}
+
+/// 反序列化时的原始数据，经过 `Instructor::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct InstructorData {
+    id: u32,
+    name: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<InstructorData> for Instructor {
+    type Error = AppError;
+
+    fn try_from(data: InstructorData) -> AppResult<Self> {
+        Instructor::new(data.id, data.name)
+    }
+}
+
+impl std::fmt::Display for Instructor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "教师[{}]: {}", self.id, self.name)
+    }
+}
+
+/// 教学工作量中的一门课程
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TaughtCourse {
+    pub course_id: u32,
+    pub course_name: String,
+    pub credits: u8,
+    pub term_id: Option<u32>,
+    pub student_count: usize,
+}
+
+/// 一位教师的教学工作量
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TeachingLoad {
+    pub instructor_id: u32,
+    pub instructor_name: String,
+    /// 按课程ID排序
+    pub courses: Vec<TaughtCourse>,
+    pub total_credits: u32,
+    /// 各课程注册人数之和，同一学生选了多门课程时重复计数
+    pub student_count: usize,
+}
+
+impl TeachingLoad {
+    fn new(instructor: &Instructor, courses: &[&Course]) -> Self {
+        let courses: Vec<TaughtCourse> = courses.iter()
+            .map(|course| TaughtCourse {
+                course_id: course.id,
+                course_name: course.name.clone(),
+                credits: course.credits,
+                term_id: course.term_id,
+                student_count: course.student_count(),
+            })
+            .collect();
+        TeachingLoad {
+            instructor_id: instructor.id,
+            instructor_name: instructor.name.clone(),
+            total_credits: courses.iter().map(|c| u32::from(c.credits)).sum(),
+            student_count: courses.iter().map(|c| c.student_count).sum(),
+            courses,
+        }
+    }
+
+    pub fn course_count(&self) -> usize {
+        self.courses.len()
+    }
+}
+
+impl EnrollmentManager {
+    /// 指定教师讲授的课程，按课程ID排序
+    pub fn courses_taught_by(&self, instructor_id: u32) -> AppResult<Vec<&Course>> {
+        self.require_instructor(instructor_id)?;
+        let mut courses: Vec<&Course> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.instructor_id == Some(instructor_id))
+            .collect();
+        courses.sort_by_key(|c| c.id);
+        Ok(courses)
+    }
+
+    /// 一位教师的教学工作量
+    pub fn teaching_load(&self, instructor_id: u32) -> AppResult<TeachingLoad> {
+        let instructor = self.require_instructor(instructor_id)?;
+        Ok(TeachingLoad::new(instructor, &self.courses_taught_by(instructor_id)?))
+    }
+
+    /// 所有教师的教学工作量，按教师ID排序；没有课程的教师也会列出
+    pub fn teaching_loads(&self) -> Vec<TeachingLoad> {
+        self.list_instructors()
+            .into_iter()
+            .map(|instructor| {
+                let courses = self.courses_taught_by(instructor.id).unwrap_or_default();
+                TeachingLoad::new(instructor, &courses)
+            })
+            .collect()
+    }
+
+    /// 还没有安排教师的课程，按课程ID排序
+    pub fn courses_without_instructor(&self) -> Vec<&Course> {
+        let mut courses: Vec<&Course> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.instructor_id.is_none())
+            .collect();
+        courses.sort_by_key(|c| c.id);
+        courses
+    }
+
+    pub(crate) fn require_instructor(&self, instructor_id: u32) -> AppResult<&Instructor> {
+        self.get_instructor(instructor_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("教师ID {} 不存在", instructor_id)
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::test_support::manager_with;
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四")], &[(101, "Rust编程", 3), (102, "数据库", 2), (103, "体育", 1)]);
+        manager.add_instructor(Instructor::new(1, "王老师".to_string()).unwrap()).unwrap();
+        manager.add_instructor(Instructor::new(2, "李老师".to_string()).unwrap()).unwrap();
+        manager.assign_instructor(101, 1).unwrap();
+        manager.assign_instructor(102, 1).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_instructor_validation() {
+        assert!(Instructor::new(1, " ".to_string()).is_err());
+        assert!(Instructor::new(1, "王老师2".to_string()).is_err());
+
+        let mut manager = manager();
+        assert!(manager.add_instructor(Instructor::new(1, "赵老师".to_string()).unwrap()).is_err());
+        assert!(manager.assign_instructor(103, 9).is_err());
+        assert!(manager.assign_instructor(999, 1).is_err());
+
+        let mut course = Course::new(104, "编译原理".to_string(), 3).unwrap();
+        course.instructor_id = Some(9);
+        assert!(manager.add_course(course).is_err());
+    }
+
+    #[test]
+    fn test_course_display_shows_instructor() {
+        let manager = manager();
+        assert_eq!(manager.get_course(101).unwrap().to_string(), "课程[101]: Rust编程 (3学分, 教师: ID 1, 学生数: 2)");
+        assert_eq!(manager.get_course(103).unwrap().to_string(), "课程[103]: 体育 (1学分, 教师: 未安排, 学生数: 0)");
+    }
+
+    #[test]
+    fn test_teaching_loads() {
+        let manager = manager();
+        let load = manager.teaching_load(1).unwrap();
+        assert_eq!(load.instructor_name, "王老师");
+        assert_eq!(load.course_count(), 2);
+        assert_eq!(load.total_credits, 5);
+        assert_eq!(load.student_count, 3);
+        assert_eq!(load.courses[0].course_id, 101);
+        assert_eq!(load.courses[0].student_count, 2);
+
+        let loads = manager.teaching_loads();
+        assert_eq!(loads.len(), 2);
+        assert_eq!(loads[1].course_count(), 0);
+        assert_eq!(manager.courses_without_instructor()[0].id, 103);
+        assert!(manager.teaching_load(9).is_err());
+    }
+}
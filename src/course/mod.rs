@@ -1,23 +1,73 @@
 //! 课程模块
 
+pub mod assessment;
+pub mod attendance;
+pub mod contacts;
 pub mod enrollment;
+pub mod grade_audit;
+pub mod gradebook;
+pub mod instructor;
+pub mod persistence;
+pub mod prerequisite;
+pub mod programme;
+pub mod roster;
+pub mod schedule;
 pub mod statistics;
+pub mod student_status;
+pub mod term;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod transcript;
+pub mod waitlist;
+pub mod withdrawal;
 
 use crate::errors::{AppError, AppResult};
+use crate::student::grade::{Grade, GradeMark};
+use crate::student::grading_scale::GradingScale;
 use crate::student::Student;
-use std::collections::HashMap;
+use crate::utils::date::Date;
+use self::assessment::AssessmentComponent;
+use self::enrollment::EnrollmentManager;
+use self::attendance::{AttendanceStatus, Session};
+use self::prerequisite::Prerequisite;
+use self::schedule::TimeSlot;
+use self::waitlist::EnrollmentOutcome;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "CourseData", try_from = "CourseData"))]
 pub struct Course {
     pub id: u32,
     pub name: String,
     pub credits: u8,
-    pub instructor: String,
-    students: HashMap<u32, Student>,
+    /// 授课教师ID，未安排教师时为 `None`
+    pub instructor_id: Option<u32>,
+    /// 开课学期，未分配时为 `None`
+    pub term_id: Option<u32>,
+    /// 注册学生的ID，学生数据和成绩只保存在管理器中
+    students: BTreeSet<u32>,
+    /// 座位数，`None` 表示不限人数
+    capacity: Option<u32>,
+    /// 课程已满时排队的学生ID，先到先得
+    waitlist: VecDeque<u32>,
+    /// 在退补选截止后退出课程的学生ID -> 每次退出的日期（按时间顺序），每次退出在成绩单上记一个W
+    withdrawals: BTreeMap<u32, Vec<Date>>,
+    prerequisite: Option<Prerequisite>,
+    time_slots: Vec<TimeSlot>,
+    components: Vec<AssessmentComponent>,
+    /// 学生ID -> 考核项名称 -> 分数
+    component_scores: BTreeMap<u32, BTreeMap<String, f64>>,
+    /// 课次 -> 学生ID -> 出勤情况
+    attendance: BTreeMap<Session, BTreeMap<u32, AttendanceStatus>>,
 }
 
 impl Course {
-    pub fn new(id: u32, name: String, credits: u8, instructor: String) -> AppResult<Self> {
+    /// 创建课程，不安排教师
+    ///
+    /// 教师是在管理器中注册的实体，构造函数不再接收教师姓名：注册教师后用
+    /// `EnrollmentManager::assign_instructor` 安排，或直接设置 `instructor_id`。
+    pub fn new(id: u32, name: String, credits: u8) -> AppResult<Self> {
         if name.trim().is_empty() {
             return Err(AppError::ValidationError(
                 "课程名称不能为空".to_string()
@@ -34,49 +84,268 @@ impl Course {
             id,
             name,
             credits,
-            instructor,
-            students: HashMap::new(),
+            instructor_id: None,
+            term_id: None,
+            students: BTreeSet::new(),
+            capacity: None,
+            waitlist: VecDeque::new(),
+            withdrawals: BTreeMap::new(),
+            prerequisite: None,
+            time_slots: Vec::new(),
+            components: Vec::new(),
+            component_scores: BTreeMap::new(),
+            attendance: BTreeMap::new(),
         })
     }
     
-    pub fn enroll_student(&mut self, student: Student) -> AppResult<()> {
-        if self.students.contains_key(&student.id) {
+    /// 注册学生，课程已满时排入候补名单
+    ///
+    /// 不检查退出记录：退出课程的学生只是不能在同一学期重新注册，
+    /// 由 `EnrollmentManager::enroll_student_in_course` 按学期检查。
+    pub fn enroll_student(&mut self, student_id: u32) -> AppResult<EnrollmentOutcome> {
+        if self.students.contains(&student_id) {
             return Err(AppError::ValidationError(
-                format!("学生 {} 已经注册了这门课程", student.name)
+                format!("学生ID {} 已经注册了课程 {}", student_id, self.name)
+            ));
+        }
+        if let Some(position) = self.waitlist_position(student_id) {
+            return Err(AppError::ValidationError(
+                format!("学生ID {} 已在课程 {} 的候补名单第{}位", student_id, self.name, position)
             ));
         }
         
-        self.students.insert(student.id, student);
+        if self.is_full() {
+            self.waitlist.push_back(student_id);
+            return Ok(EnrollmentOutcome::Waitlisted { position: self.waitlist.len() });
+        }
+        self.students.insert(student_id);
+        Ok(EnrollmentOutcome::Enrolled)
+    }
+    
+    pub fn is_enrolled(&self, student_id: u32) -> bool {
+        self.students.contains(&student_id)
+    }
+    
+    /// 移除已注册的学生并删除其考核项分数和考勤记录，不从候补名单补位
+    pub(crate) fn unenroll_student(&mut self, student_id: u32) -> AppResult<()> {
+        if !self.students.remove(&student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 未找到", student_id)
+            ));
+        }
+        self.clear_student_records(student_id);
         Ok(())
     }
     
-    pub fn get_student(&self, student_id: u32) -> Option<&Student> {
-        self.students.get(&student_id)
+    /// 删除学生在本课程的考核项分数和考勤记录
+    pub(crate) fn clear_student_records(&mut self, student_id: u32) {
+        self.component_scores.remove(&student_id);
+        self.clear_attendance(student_id);
+    }
+    
+    /// 学生在本课程中的成绩
+    ///
+    /// 定义了考核项的课程使用按权重计算的总评成绩，否则使用该学生
+    /// 记在本课程名下的各项分数成绩的平均分（已退出的那次修读的成绩不计入）。
+    /// 学生未注册、成绩不完整，或课程结果是不以分数记录的成绩（见 `student_mark`）时返回 `None`。
+    pub fn student_score(&self, student: &Student) -> Option<f64> {
+        if !self.is_enrolled(student.id) || self.student_mark(student).is_some() {
+            return None;
+        }
+        if !self.components.is_empty() {
+            return self.final_score(student.id);
+        }
+        let scores: Vec<f64> = student.grades_in_course(self.id)
+            .iter()
+            .filter(|g| !g.is_withdrawn())
+            .filter_map(|g| g.score())
+            .collect();
+        if scores.is_empty() {
+            return None;
+        }
+        Some(scores.iter().sum::<f64>() / scores.len() as f64)
     }
     
-    pub fn get_student_mut(&mut self, student_id: u32) -> Option<&mut Student> {
-        self.students.get_mut(&student_id)
+    /// 学生在本课程中不以分数记录的结果
+    ///
+    /// 记在本课程名下的最近一条成绩是非分数成绩时，它决定整门课程的结果（例如通过、旁听），
+    /// 此时课程不再按分数计算成绩；之后再录入分数成绩（例如未完成之后补考）则重新按分数计算。
+    /// 学生未注册或最近一条成绩是分数成绩时返回 `None`。
+    pub fn student_mark(&self, student: &Student) -> Option<GradeMark> {
+        if !self.is_enrolled(student.id) {
+            return None;
+        }
+        student.grades_in_course(self.id)
+            .iter()
+            .rev()
+            .find(|g| !g.is_withdrawn())
+            .and_then(|g| g.mark())
     }
     
-    pub fn remove_student(&mut self, student_id: u32) -> AppResult<Student> {
-        self.students.remove(&student_id)
-            .ok_or_else(|| AppError::NotFound(
-                format!("学生ID {} 未找到", student_id)
-            ))
+    /// 按等级制创建记在本课程名下的成绩，课程安排了学期时成绩也归入该学期
+    pub(crate) fn new_grade(&self, subject: String, score: f64, scale: &GradingScale) -> AppResult<Grade> {
+        Ok(self.tag_grade(Grade::with_scale(subject, score, scale)?))
+    }
+    
+    /// 创建记在本课程名下的非分数成绩
+    pub(crate) fn new_mark(&self, subject: String, mark: GradeMark) -> AppResult<Grade> {
+        Ok(self.tag_grade(Grade::with_mark(subject, mark)?))
+    }
+    
+    fn tag_grade(&self, grade: Grade) -> Grade {
+        let grade = grade.for_course(self.id);
+        match self.term_id {
+            Some(term_id) => grade.in_term(term_id),
+            None => grade,
+        }
     }
     
     pub fn student_count(&self) -> usize {
         self.students.len()
     }
     
-    pub fn list_students(&self) -> Vec<&Student> {
-        self.students.values().collect()
+    /// 注册学生的ID，按从小到大排序
+    pub fn student_ids(&self) -> Vec<u32> {
+        self.students.iter().copied().collect()
+    }
+    
+    /// 注册了这门课程的学生，按学生ID排序
+    ///
+    /// 学生数据从管理器读取，看到的总是最新的成绩；修改学生见
+    /// `EnrollmentManager::get_student_mut` 和 `EnrollmentManager::update_student`。
+    pub fn list_students<'a>(&self, manager: &'a EnrollmentManager) -> Vec<&'a Student> {
+        self.students.iter()
+            .filter_map(|&id| manager.get_student(id))
+            .collect()
+    }
+    
+    /// 注册了这门课程的学生，没有注册时返回 `None`
+    pub fn get_student<'a>(&self, manager: &'a EnrollmentManager, student_id: u32) -> Option<&'a Student> {
+        if self.is_enrolled(student_id) {
+            manager.get_student(student_id)
+        } else {
+            None
+        }
+    }
+}
+
+/// 序列化时的数据形式，注册学生保存为ID列表，反序列化经过 `Course::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CourseData {
+    id: u32,
+    name: String,
+    credits: u8,
+    #[serde(default)]
+    instructor_id: Option<u32>,
+    #[serde(default)]
+    term_id: Option<u32>,
+    #[serde(default)]
+    students: Vec<u32>,
+    #[serde(default)]
+    capacity: Option<u32>,
+    #[serde(default)]
+    waitlist: Vec<u32>,
+    #[serde(default)]
+    withdrawals: BTreeMap<u32, Vec<Date>>,
+    #[serde(default)]
+    prerequisite: Option<Prerequisite>,
+    #[serde(default)]
+    time_slots: Vec<TimeSlot>,
+    #[serde(default)]
+    components: Vec<AssessmentComponent>,
+    #[serde(default)]
+    component_scores: BTreeMap<u32, BTreeMap<String, f64>>,
+    #[serde(default)]
+    attendance: BTreeMap<Session, BTreeMap<u32, AttendanceStatus>>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Course> for CourseData {
+    fn from(course: Course) -> Self {
+        let students = course.students.into_iter().collect();
+        CourseData {
+            id: course.id,
+            name: course.name,
+            credits: course.credits,
+            instructor_id: course.instructor_id,
+            term_id: course.term_id,
+            students,
+            capacity: course.capacity,
+            waitlist: course.waitlist.into_iter().collect(),
+            withdrawals: course.withdrawals,
+            prerequisite: course.prerequisite,
+            time_slots: course.time_slots,
+            components: course.components,
+            component_scores: course.component_scores,
+            attendance: course.attendance,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CourseData> for Course {
+    type Error = AppError;
+
+    fn try_from(data: CourseData) -> AppResult<Self> {
+        let mut course = Course::new(data.id, data.name, data.credits)?;
+        course.instructor_id = data.instructor_id;
+        course.term_id = data.term_id;
+        course.set_prerequisite(data.prerequisite)?;
+        for slot in data.time_slots {
+            course.add_time_slot(slot)?;
+        }
+        for student_id in data.students {
+            course.enroll_student(student_id)?;
+        }
+        course.set_capacity(data.capacity)?;
+        for student_id in data.waitlist {
+            course.add_to_waitlist(student_id)?;
+        }
+        for (student_id, dates) in data.withdrawals {
+            for date in dates {
+                course.restore_withdrawal(student_id, date)?;
+            }
+        }
+        course.set_assessment_components(data.components)?;
+        for (student_id, scores) in data.component_scores {
+            for (component, score) in scores {
+                course.restore_component_score(student_id, &component, score)?;
+            }
+        }
+        for (session, records) in data.attendance {
+            for (student_id, status) in records {
+                course.restore_attendance(session, student_id, status)?;
+            }
+        }
+        Ok(course)
     }
 }
 
 impl std::fmt::Display for Course {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let instructor = self.instructor_id.map_or("未安排".to_string(), |id| format!("ID {}", id));
         write!(f, "课程[{}]: {} ({}学分, 教师: {}, 学生数: {})", 
-               self.id, self.name, self.credits, self.instructor, self.student_count())
+               self.id, self.name, self.credits, instructor, self.student_count())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_course_json_round_trip() {
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.enroll_student(1).unwrap();
+
+        let json = serde_json::to_string(&course).unwrap();
+        assert_eq!(serde_json::from_str::<Course>(&json).unwrap(), course);
+    }
+
+    #[test]
+    fn test_invalid_course_json_is_rejected() {
+        let bad_credits = r#"{"id":1,"name":"Rust编程","credits":0,"instructor_id":1}"#;
+        assert!(serde_json::from_str::<Course>(bad_credits).is_err());
+    }
+}
@@ -0,0 +1,980 @@
+//! 注册数据持久化模块
+//!
+//! 将 `EnrollmentManager` 中的课程、学生、成绩和选课关系保存为文本文件，
+//! 并能从文件重新构建出相同的管理器。
+//!
+//! 文件格式为按行记录，字段之间用制表符分隔（下例中以空格示意）：
+//!
+//! ```text
+//! # enrollment-data v1
+//! journal 42
+//! scale 优 90 A 4
+//! attendance_threshold 75
+//! term 1 2024秋季学期 2024-09-01 2025-01-15 2024-09-15 2024-11-15
+//! instructor 1 王老师
+//! student 1 张三 20
+//! contact 1 zhangsan@example.com 13800000000 北京市海淀区
+//! guardian 1 张父 父亲  13900000000 
+//! status 1 休学 2024-03-01
+//! grade 1 数学 95 A 1 101
+//! grade 1 研讨课  P 1 102
+//! grade 1 数据库 52 F 1 103 W
+//! amended 1 NP P 补交报告 王老师 2025-01-10
+//! course 101 Rust编程 3 1 1
+//! enrolled 101 1
+//! capacity 101 30
+//! prerequisite 101 all(course:100>=C, credits:10)
+//! slot 101 周一 08:00 09:40 A101
+//! waitlisted 101 2
+//! withdrawn 101 3 2024-10-08
+//! attendance 101 2024-09-02#1 1 迟到
+//! components 101 作业 20 期末 80
+//! component_score 101 1 期末 88
+//! programme 1 计算机科学与技术 120
+//! required 1 101
+//! elective 1 专业选修 6 201 202 203
+//! # crc32 1a2b3c4d
+//! ```
+//!
+//! 第一行是格式版本头，末尾是CRC32校验和行，校验失败时返回 `AppError::CorruptedData`。
+//! 以后数据模型变化时提高版本号，加载时按文件自身的版本解析。
+//!
+//! 记录说明：
+//! - 标准等级制不写出等级制记录（`scale 名称 最低分 等级 绩点`），没有等级制记录的文件
+//!   按标准等级制读取；出勤率警戒线（`attendance_threshold 百分比`）同样只在不是默认值时写出
+//! - 学期记录末尾是退补选和退出课程截止日期，两个都不限时省略，只有一个不限时为空
+//! - 成绩记录是 `grade 学生ID 科目 分数 等级 [学期ID [课程ID [W]]]`，有课程没有学期时
+//!   学期字段为空，已退出课程的成绩末尾带 `W`；不以分数记录的成绩分数字段为空，
+//!   等级字段是结果代码（`P`、`NP`、`I`、`AU`、`EX`）
+//! - 成绩修改记录（`amended 学生ID 原成绩 新成绩 原因 修改人 日期`）紧跟在所修改的
+//!   成绩记录之后，按修改顺序排列；学籍状态变更记录按时间顺序排列
+//! - 课程记录的教师字段是教师ID，没有教师时为空；课程记录末尾可以带所属学期ID
+//! - 候补名单按排队顺序保存；学生每次退出课程各有一条退出记录，按退出日期排列；考勤记录的上课日期带当天的节次（`2024-09-02#2`）
+//! - 由操作日志压缩而成的快照记录它包含的最后一条日志的序号（`journal 序号`），
+//!   见 `storage::journal` 模块
+
+use super::assessment::AssessmentComponent;
+use super::attendance::{check_attendance_threshold, DEFAULT_ATTENDANCE_THRESHOLD};
+use super::enrollment::EnrollmentManager;
+use super::instructor::Instructor;
+use super::programme::{ElectivePool, Programme};
+use super::schedule::TimeSlot;
+use super::term::Term;
+use crate::errors::{AppError, AppResult};
+use crate::storage::{MemoryStorage, Storage};
+use crate::student::amendment::{GradeAmendment, GradeChange};
+use crate::student::contact::{ContactInfo, Guardian};
+use crate::student::grade::Grade;
+use crate::student::grading_scale::{standard_scale, GradeBand, GradingScale};
+use crate::utils::date::Date;
+use crate::utils::file_handler::{
+    append_checksum, read_file_to_string, verify_checksum, write_string_to_file,
+};
+use crate::{Course, Student};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 文件头前缀，后面跟版本号，例如 `# enrollment-data v1`
+const FORMAT_HEADER: &str = "# enrollment-data v";
+
+/// 当前写出的格式版本
+pub const FORMAT_VERSION: u32 = 1;
+
+impl EnrollmentManager {
+    /// 将全部数据保存到文件
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> AppResult<()> {
+        write_string_to_file(path, &self.to_snapshot_string())
+    }
+
+    /// 从文件加载数据，重新构建管理器
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> AppResult<Self> {
+        let content = read_file_to_string(path)?;
+        Self::from_snapshot_str(&content)
+    }
+
+    /// 将全部数据编码为快照文本
+    pub fn to_snapshot_string(&self) -> String {
+        encode_storage(self.storage())
+    }
+
+    /// 从快照文本重新构建管理器
+    ///
+    /// 所有数据都会重新经过 `Student::new`、`Grade::new` 和 `Course::new` 的验证，
+    /// 记录之间的引用（注册学生、候补名单、教师、学期等）必须指向存在的记录。
+    pub fn from_snapshot_str(content: &str) -> AppResult<Self> {
+        let mut storage = MemoryStorage::new();
+        storage.merge(decode_snapshot(content)?)?;
+        Ok(EnrollmentManager::with_storage(Box::new(storage)))
+    }
+}
+
+/// 解码后的快照数据，各列表按ID排序
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotData {
+    pub students: Vec<Student>,
+    pub courses: Vec<Course>,
+    pub terms: Vec<Term>,
+    pub instructors: Vec<Instructor>,
+    pub programmes: Vec<Programme>,
+    /// 快照中保存的等级制，没有等级制记录时为 `None`
+    pub grading_scale: Option<GradingScale>,
+    /// 快照中保存的出勤率警戒线，没有警戒线记录时为 `None`
+    pub attendance_threshold: Option<f64>,
+    /// 快照已经包含的最后一条操作日志的序号，不是由日志压缩而成时为 `None`
+    pub journal_seq: Option<u64>,
+}
+
+/// 快照中要写出的管理器设置，`None` 表示不写出
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SnapshotSettings<'a> {
+    pub grading_scale: Option<&'a GradingScale>,
+    pub attendance_threshold: Option<f64>,
+}
+
+impl<'a> SnapshotSettings<'a> {
+    /// 存储中保存的全部设置
+    pub(crate) fn of(storage: &'a dyn Storage) -> Self {
+        SnapshotSettings {
+            grading_scale: Some(storage.grading_scale()),
+            attendance_threshold: Some(storage.attendance_threshold()),
+        }
+    }
+}
+
+/// 编码存储中的全部数据
+pub(crate) fn encode_storage(storage: &dyn Storage) -> String {
+    encode_snapshot(
+        &storage.list_students(), &storage.list_courses(), &storage.list_terms(),
+        &storage.list_instructors(), &storage.list_programmes(), SnapshotSettings::of(storage), None,
+    )
+}
+
+/// 将学生、课程、学期、教师、培养方案和管理器设置编码为带版本头的快照文本，
+/// 按ID排序以保证输出稳定；标准等级制和默认的出勤率警戒线不写出
+pub(crate) fn encode_snapshot(
+    students: &[&Student],
+    courses: &[&Course],
+    terms: &[&Term],
+    instructors: &[&Instructor],
+    programmes: &[&Programme],
+    settings: SnapshotSettings<'_>,
+    journal_seq: Option<u64>,
+) -> String {
+    let mut output = format!("{}{}\n", FORMAT_HEADER, FORMAT_VERSION);
+
+    if let Some(seq) = journal_seq {
+        output.push_str(&encode_record(&["journal", &seq.to_string()]));
+    }
+
+    if let Some(scale) = settings.grading_scale.filter(|&scale| scale != standard_scale()) {
+        for band in scale.bands() {
+            output.push_str(&encode_record(&[
+                "scale", &band.label, &band.min_score.to_string(), band.letter.as_str(), &band.gpa.to_string(),
+            ]));
+        }
+    }
+    if let Some(threshold) = settings.attendance_threshold.filter(|&t| t != DEFAULT_ATTENDANCE_THRESHOLD) {
+        output.push_str(&encode_record(&["attendance_threshold", &threshold.to_string()]));
+    }
+
+    let mut terms = terms.to_vec();
+    terms.sort_by_key(|t| t.id);
+    for term in terms {
+        let mut fields = vec![
+            "term".to_string(), term.id.to_string(), term.name.clone(),
+            term.start_date.to_string(), term.end_date.to_string(),
+        ];
+        if term.add_drop_deadline.is_some() || term.withdrawal_deadline.is_some() {
+            for deadline in [term.add_drop_deadline, term.withdrawal_deadline] {
+                fields.push(deadline.map(|d| d.to_string()).unwrap_or_default());
+            }
+        }
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        output.push_str(&encode_record(&fields));
+    }
+
+    let mut instructors = instructors.to_vec();
+    instructors.sort_by_key(|i| i.id);
+    for instructor in instructors {
+        output.push_str(&encode_record(&["instructor", &instructor.id.to_string(), &instructor.name]));
+    }
+
+    let mut students = students.to_vec();
+    students.sort_by_key(|s| s.id);
+    for student in students {
+        write_student(&mut output, student);
+    }
+
+    let mut courses = courses.to_vec();
+    courses.sort_by_key(|c| c.id);
+    for course in courses {
+        write_course(&mut output, course);
+    }
+
+    let mut programmes = programmes.to_vec();
+    programmes.sort_by_key(|p| p.id);
+    for programme in programmes {
+        write_programme(&mut output, programme);
+    }
+
+    append_checksum(&output)
+}
+
+/// 解码快照文本
+pub(crate) fn decode_snapshot(content: &str) -> AppResult<SnapshotData> {
+    let version = match content.lines().next() {
+        Some(header) => parse_header(header)?,
+        None => return Err(AppError::InvalidInput("数据文件为空".to_string())),
+    };
+    if version != FORMAT_VERSION {
+        return Err(AppError::InvalidInput(
+            format!("不支持的数据文件版本 {}", version)
+        ));
+    }
+
+    let body = verify_checksum(content)?;
+    let mut decoder = Decoder::default();
+
+    for (index, line) in body.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = decode_record(line);
+        decoder.apply_record(&fields)
+            .map_err(|e| AppError::InvalidInput(
+                format!("数据文件第{}行: {}", index + 1, e)
+            ))?;
+    }
+
+    decoder.restore_amendments()?;
+
+    let grading_scale = if decoder.scale_bands.is_empty() {
+        None
+    } else {
+        Some(GradingScale::new(decoder.scale_bands)?)
+    };
+
+    Ok(SnapshotData {
+        students: decoder.students.into_values().collect(),
+        courses: decoder.courses.into_values().collect(),
+        terms: decoder.terms.into_values().collect(),
+        instructors: decoder.instructors.into_values().collect(),
+        programmes: decoder.programmes.into_values().collect(),
+        grading_scale,
+        attendance_threshold: decoder.attendance_threshold,
+        journal_seq: decoder.journal_seq,
+    })
+}
+
+fn write_student(output: &mut String, student: &Student) {
+    let id = student.id.to_string();
+    output.push_str(&encode_record(&["student", &id, &student.name, &student.age.to_string()]));
+    if !student.contact.is_empty() {
+        let mut fields = vec!["contact", id.as_str()];
+        fields.extend(contact_fields(&student.contact));
+        output.push_str(&encode_record(&fields));
+    }
+    for guardian in &student.guardians {
+        let mut fields = vec!["guardian", id.as_str(), guardian.name(), guardian.relationship()];
+        fields.extend(contact_fields(guardian.contact()));
+        output.push_str(&encode_record(&fields));
+    }
+    for change in student.status_history() {
+        output.push_str(&encode_record(&["status", &id, change.to.as_str(), &change.date.to_string()]));
+    }
+    for grade in &student.grades {
+        let mut fields = vec!["grade", &id];
+        let grade_fields = grade_fields(grade);
+        fields.extend(grade_fields.iter().map(String::as_str));
+        output.push_str(&encode_record(&fields));
+        for amendment in grade.amendments() {
+            output.push_str(&encode_record(&[
+                "amended", &id, &amendment.old_value.to_string(), &amendment.new_value.to_string(),
+                &amendment.reason, &amendment.amended_by, &amendment.date.to_string(),
+            ]));
+        }
+    }
+}
+
+/// 联系方式的 `邮箱 电话 地址` 字段，没有填写时为空
+fn contact_fields(contact: &ContactInfo) -> [&str; 3] {
+    [
+        contact.email().unwrap_or_default(),
+        contact.phone().unwrap_or_default(),
+        contact.address().unwrap_or_default(),
+    ]
+}
+
+/// 解码 `邮箱 电话 地址` 字段
+fn decode_contact(fields: &[String]) -> AppResult<ContactInfo> {
+    ContactInfo::new(Some(fields[0].clone()), Some(fields[1].clone()), Some(fields[2].clone()))
+}
+
+/// 成绩的 `科目 分数 等级 [学期ID [课程ID]]` 字段，有课程没有学期时学期字段为空
+///
+/// 不以分数记录的成绩分数字段为空，等级字段是结果代码。
+fn grade_fields(grade: &Grade) -> Vec<String> {
+    let (score, letter) = match (grade.score(), grade.letter_grade(), grade.mark()) {
+        (Some(score), Some(letter), _) => (score.to_string(), letter.to_string()),
+        (_, _, Some(mark)) => (String::new(), mark.to_string()),
+        _ => (String::new(), String::new()),
+    };
+    let mut fields = vec![grade.subject().to_string(), score, letter];
+    let term_id = grade.term_id().map(|id| id.to_string()).unwrap_or_default();
+    match grade.course_id() {
+        Some(course_id) => {
+            fields.push(term_id);
+            fields.push(course_id.to_string());
+        }
+        None if grade.term_id().is_some() => fields.push(term_id),
+        None => {}
+    }
+    if grade.is_withdrawn() {
+        fields.push("W".to_string());
+    }
+    fields
+}
+
+fn write_course(output: &mut String, course: &Course) {
+    let mut fields = vec![
+        "course".to_string(),
+        course.id.to_string(),
+        course.name.clone(),
+        course.credits.to_string(),
+        course.instructor_id.map(|id| id.to_string()).unwrap_or_default(),
+    ];
+    if let Some(term_id) = course.term_id {
+        fields.push(term_id.to_string());
+    }
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    output.push_str(&encode_record(&fields));
+
+    let course_id = course.id.to_string();
+    for student_id in course.student_ids() {
+        output.push_str(&encode_record(&["enrolled", &course_id, &student_id.to_string()]));
+    }
+    if let Some(capacity) = course.capacity() {
+        output.push_str(&encode_record(&["capacity", &course_id, &capacity.to_string()]));
+    }
+    if let Some(prerequisite) = course.prerequisite() {
+        output.push_str(&encode_record(&["prerequisite", &course_id, &prerequisite.to_string()]));
+    }
+    for slot in course.time_slots() {
+        output.push_str(&encode_record(&[
+            "slot", &course_id, slot.day.as_str(), &slot.start.to_string(), &slot.end.to_string(), &slot.room,
+        ]));
+    }
+    for student_id in course.waitlist() {
+        output.push_str(&encode_record(&["waitlisted", &course_id, &student_id.to_string()]));
+    }
+    for student_id in course.withdrawn_student_ids() {
+        for date in course.withdrawal_dates(student_id) {
+            output.push_str(&encode_record(&["withdrawn", &course_id, &student_id.to_string(), &date.to_string()]));
+        }
+    }
+    for (session, student_id, status) in course.attendance_records() {
+        output.push_str(&encode_record(&[
+            "attendance", &course_id, &session.to_string(), &student_id.to_string(), status.as_str(),
+        ]));
+    }
+
+    let components = course.assessment_components();
+    if !components.is_empty() {
+        let mut fields = vec!["components".to_string(), course_id.clone()];
+        for component in components {
+            fields.push(component.name.clone());
+            fields.push(component.weight.to_string());
+        }
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        output.push_str(&encode_record(&fields));
+
+        for (student_id, name, score) in course.component_score_records() {
+            output.push_str(&encode_record(&[
+                "component_score", &course_id, &student_id.to_string(), name, &score.to_string(),
+            ]));
+        }
+    }
+}
+
+fn write_programme(output: &mut String, programme: &Programme) {
+    let id = programme.id.to_string();
+    output.push_str(&encode_record(&["programme", &id, &programme.name, &programme.min_credits.to_string()]));
+    for course_id in programme.required_courses() {
+        output.push_str(&encode_record(&["required", &id, &course_id.to_string()]));
+    }
+    for pool in programme.elective_pools() {
+        let mut fields = vec!["elective".to_string(), id.clone(), pool.name.clone(), pool.min_credits.to_string()];
+        fields.extend(pool.course_ids().iter().map(u32::to_string));
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        output.push_str(&encode_record(&fields));
+    }
+}
+
+fn parse_header(header: &str) -> AppResult<u32> {
+    let version = header.trim().strip_prefix(FORMAT_HEADER)
+        .ok_or_else(|| AppError::InvalidInput("缺少数据文件版本头".to_string()))?;
+    Ok(version.parse()?)
+}
+
+/// 逐条应用记录，构建快照数据
+#[derive(Default)]
+struct Decoder {
+    students: BTreeMap<u32, Student>,
+    courses: BTreeMap<u32, Course>,
+    terms: BTreeMap<u32, Term>,
+    instructors: BTreeMap<u32, Instructor>,
+    programmes: BTreeMap<u32, Programme>,
+    scale_bands: Vec<GradeBand>,
+    attendance_threshold: Option<f64>,
+    /// 按（学生ID，成绩序号）收集的修改记录，读完后整条验证
+    amendments: BTreeMap<(u32, usize), Vec<GradeAmendment>>,
+    journal_seq: Option<u64>,
+}
+
+impl Decoder {
+    fn apply_record(&mut self, fields: &[String]) -> AppResult<()> {
+        match fields[0].as_str() {
+            "journal" => {
+                expect_fields(fields, 2)?;
+                if self.journal_seq.replace(fields[1].parse()?).is_some() {
+                    return Err(AppError::ValidationError("日志序号记录重复".to_string()));
+                }
+            }
+            "scale" => {
+                expect_fields(fields, 5)?;
+                let gpa = fields[4].parse()
+                    .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的绩点", fields[4])))?;
+                self.scale_bands.push(GradeBand::new(&fields[1], parse_score(&fields[2])?, fields[3].parse()?, gpa));
+            }
+            "attendance_threshold" => {
+                expect_fields(fields, 2)?;
+                let threshold = parse_score(&fields[1])?;
+                check_attendance_threshold(threshold)?;
+                if self.attendance_threshold.replace(threshold).is_some() {
+                    return Err(AppError::ValidationError("出勤率警戒线记录重复".to_string()));
+                }
+            }
+            "term" => {
+                if fields.len() != 5 && fields.len() != 7 {
+                    expect_fields(fields, 5)?;
+                }
+                let mut term = Term::new(
+                    fields[1].parse()?, fields[2].clone(), fields[3].parse()?, fields[4].parse()?,
+                )?;
+                if fields.len() == 7 {
+                    term.set_deadlines(parse_optional_date(&fields[5])?, parse_optional_date(&fields[6])?)?;
+                }
+                if self.terms.insert(term.id, term).is_some() {
+                    return Err(AppError::ValidationError(format!("学期ID {} 重复", fields[1])));
+                }
+            }
+            "instructor" => {
+                expect_fields(fields, 3)?;
+                let instructor = Instructor::new(fields[1].parse()?, fields[2].clone())?;
+                if self.instructors.insert(instructor.id, instructor).is_some() {
+                    return Err(AppError::ValidationError(format!("教师ID {} 重复", fields[1])));
+                }
+            }
+            "student" => {
+                expect_fields(fields, 4)?;
+                let student = Student::new(fields[1].parse()?, fields[2].clone(), fields[3].parse()?)?;
+                if self.students.insert(student.id, student).is_some() {
+                    return Err(AppError::ValidationError(format!("学生ID {} 重复", fields[1])));
+                }
+            }
+            "contact" => {
+                expect_fields(fields, 5)?;
+                let contact = decode_contact(&fields[2..])?;
+                self.find_student(fields[1].parse()?)?.contact = contact;
+            }
+            "guardian" => {
+                expect_fields(fields, 7)?;
+                let guardian = Guardian::new(fields[2].clone(), fields[3].clone(), decode_contact(&fields[4..])?)?;
+                self.find_student(fields[1].parse()?)?.add_guardian(guardian)?;
+            }
+            "status" => {
+                expect_fields(fields, 4)?;
+                self.find_student(fields[1].parse()?)?.change_status(fields[2].parse()?, fields[3].parse()?)?;
+            }
+            "grade" => {
+                expect_field_range(fields, 5, 8)?;
+                let grade = decode_grade(&fields[2..])?;
+                self.find_student(fields[1].parse()?)?.grades.push(grade);
+            }
+            "amended" => {
+                expect_fields(fields, 7)?;
+                let change = GradeChange::new(fields[3].parse()?, &fields[4], &fields[5], fields[6].parse()?)?;
+                let amendment = GradeAmendment::new(fields[2].parse()?, change)?;
+                let student_id = fields[1].parse()?;
+                let index = self.find_student(student_id)?.grades.len().checked_sub(1)
+                    .ok_or_else(|| AppError::InvalidInput("修改记录之前没有成绩记录".to_string()))?;
+                self.amendments.entry((student_id, index)).or_default().push(amendment);
+            }
+            "course" => {
+                expect_field_range(fields, 5, 6)?;
+                let mut course = Course::new(fields[1].parse()?, fields[2].clone(), fields[3].parse()?)?;
+                if !fields[4].is_empty() {
+                    course.instructor_id = Some(fields[4].parse()?);
+                }
+                course.term_id = fields.get(5).map(|id| id.parse()).transpose()?;
+                if self.courses.insert(course.id, course).is_some() {
+                    return Err(AppError::ValidationError(format!("课程ID {} 重复", fields[1])));
+                }
+            }
+            "enrolled" => {
+                expect_fields(fields, 3)?;
+                let student_id = fields[2].parse()?;
+                self.find_course(fields[1].parse()?)?.enroll_student(student_id)?;
+            }
+            "capacity" => {
+                expect_fields(fields, 3)?;
+                let capacity = fields[2].parse()?;
+                self.find_course(fields[1].parse()?)?.set_capacity(Some(capacity))?;
+            }
+            "prerequisite" => {
+                expect_fields(fields, 3)?;
+                let prerequisite = fields[2].parse()?;
+                self.find_course(fields[1].parse()?)?.set_prerequisite(Some(prerequisite))?;
+            }
+            "slot" => {
+                expect_fields(fields, 6)?;
+                let slot = TimeSlot::new(fields[2].parse()?, fields[3].parse()?, fields[4].parse()?, &fields[5])?;
+                self.find_course(fields[1].parse()?)?.add_time_slot(slot)?;
+            }
+            "waitlisted" => {
+                expect_fields(fields, 3)?;
+                let student_id = fields[2].parse()?;
+                self.find_course(fields[1].parse()?)?.add_to_waitlist(student_id)?;
+            }
+            "withdrawn" => {
+                expect_fields(fields, 4)?;
+                let student_id = fields[2].parse()?;
+                self.find_course(fields[1].parse()?)?.restore_withdrawal(student_id, fields[3].parse()?)?;
+            }
+            "attendance" => {
+                expect_fields(fields, 5)?;
+                let course = self.find_course(fields[1].parse()?)?;
+                course.restore_attendance(fields[2].parse()?, fields[3].parse()?, fields[4].parse()?)?;
+            }
+            "components" => {
+                if fields.len() < 2 || !fields.len().is_multiple_of(2) {
+                    return Err(AppError::InvalidInput("考核项记录字段数不正确".to_string()));
+                }
+                let components = fields[2..].chunks(2)
+                    .map(|pair| AssessmentComponent::new(&pair[0], parse_score(&pair[1])?))
+                    .collect::<AppResult<Vec<_>>>()?;
+                self.find_course(fields[1].parse()?)?.set_assessment_components(components)?;
+            }
+            "component_score" => {
+                expect_fields(fields, 5)?;
+                let course = self.find_course(fields[1].parse()?)?;
+                course.restore_component_score(fields[2].parse()?, &fields[3], parse_score(&fields[4])?)?;
+            }
+            "programme" => {
+                expect_fields(fields, 4)?;
+                let programme = Programme::new(fields[1].parse()?, &fields[2], fields[3].parse()?)?;
+                if self.programmes.insert(programme.id, programme).is_some() {
+                    return Err(AppError::ValidationError(format!("培养方案ID {} 重复", fields[1])));
+                }
+            }
+            "required" => {
+                expect_fields(fields, 3)?;
+                let course_id = fields[2].parse()?;
+                self.find_programme(fields[1].parse()?)?.add_required_course(course_id)?;
+            }
+            "elective" => {
+                expect_min_fields(fields, 5)?;
+                let course_ids = fields[4..].iter()
+                    .map(|id| id.parse())
+                    .collect::<Result<Vec<u32>, _>>()?;
+                let pool = ElectivePool::new(&fields[2], course_ids, fields[3].parse()?)?;
+                self.find_programme(fields[1].parse()?)?.add_elective_pool(pool)?;
+            }
+            other => {
+                return Err(AppError::InvalidInput(format!("未知的记录类型 '{}'", other)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 把收集到的修改记录恢复到对应的成绩上
+    fn restore_amendments(&mut self) -> AppResult<()> {
+        for ((student_id, index), amendments) in std::mem::take(&mut self.amendments) {
+            self.find_student(student_id)?.grades[index].restore_amendments(amendments)
+                .map_err(|e| AppError::InvalidInput(format!("学生ID {} 的成绩修改记录: {}", student_id, e)))?;
+        }
+        Ok(())
+    }
+
+    fn find_student(&mut self, id: u32) -> AppResult<&mut Student> {
+        self.students.get_mut(&id)
+            .ok_or_else(|| AppError::NotFound(format!("学生ID {} 不存在", id)))
+    }
+
+    fn find_course(&mut self, id: u32) -> AppResult<&mut Course> {
+        self.courses.get_mut(&id)
+            .ok_or_else(|| AppError::NotFound(format!("课程ID {} 不存在", id)))
+    }
+
+    fn find_programme(&mut self, id: u32) -> AppResult<&mut Programme> {
+        self.programmes.get_mut(&id)
+            .ok_or_else(|| AppError::NotFound(format!("培养方案ID {} 不存在", id)))
+    }
+}
+
+/// 解码 `科目 分数 等级 [学期ID [课程ID [W]]]`
+fn decode_grade(fields: &[String]) -> AppResult<Grade> {
+    let mut grade = match fields[1].as_str() {
+        "" => Grade::with_mark(fields[0].clone(), fields[2].parse()?)?,
+        score => Grade::with_letter_grade(fields[0].clone(), parse_score(score)?, fields[2].parse()?)?,
+    };
+    if let Some(term_id) = fields.get(3).filter(|id| !id.is_empty()) {
+        grade = grade.in_term(term_id.parse()?);
+    }
+    if let Some(course_id) = fields.get(4) {
+        grade = grade.for_course(course_id.parse()?);
+    }
+    match fields.get(5).map(String::as_str) {
+        None => {}
+        Some("W") => grade.mark_withdrawn()?,
+        Some(other) => return Err(AppError::InvalidInput(format!("'{}' 不是有效的成绩标记", other))),
+    }
+    Ok(grade)
+}
+
+/// 空字段表示没有日期
+fn parse_optional_date(text: &str) -> AppResult<Option<Date>> {
+    if text.is_empty() {
+        Ok(None)
+    } else {
+        text.parse().map(Some)
+    }
+}
+
+fn expect_fields(fields: &[String], count: usize) -> AppResult<()> {
+    expect_field_range(fields, count, count)
+}
+
+fn expect_min_fields(fields: &[String], min: usize) -> AppResult<()> {
+    if fields.len() < min {
+        return Err(AppError::InvalidInput(format!(
+            "记录 '{}' 至少需要 {} 个字段，实际有 {} 个", fields[0], min, fields.len()
+        )));
+    }
+    Ok(())
+}
+
+fn expect_field_range(fields: &[String], min: usize, max: usize) -> AppResult<()> {
+    if fields.len() < min || fields.len() > max {
+        return Err(AppError::InvalidInput(format!(
+            "记录 '{}' 需要 {}-{} 个字段，实际有 {} 个", fields[0], min, max, fields.len()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_score(text: &str) -> AppResult<f64> {
+    text.parse()
+        .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的分数", text)))
+}
+
+/// 成绩在单个字段中的文本形式：分数，或不以分数记录的结果代码
+pub(crate) fn grade_value_text(grade: &Grade) -> String {
+    grade.value().to_string()
+}
+
+/// 将一条记录编码为一行文本（包含换行符）
+pub(crate) fn encode_record(fields: &[&str]) -> String {
+    let mut line = fields.iter()
+        .map(|field| escape_field(field))
+        .collect::<Vec<_>>()
+        .join("\t");
+    line.push('\n');
+    line
+}
+
+/// 将一行文本解码为字段列表
+pub(crate) fn decode_record(line: &str) -> Vec<String> {
+    line.split('\t').map(unescape_field).collect()
+}
+
+fn escape_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_field(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::attendance::AttendanceStatus;
+    use crate::student::amendment::GradeValue;
+    use crate::student::grade::{GradeMark, LetterGrade};
+    use crate::student::grading_scale::GradingScale;
+    use crate::student::status::StudentStatus;
+    use tempfile::tempdir;
+
+    fn sample_manager() -> EnrollmentManager {
+        let mut manager = EnrollmentManager::new();
+
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        student.add_grade("数学".to_string(), 95.5).unwrap();
+        student.add_grade("物理\t实验".to_string(), 78.0).unwrap();
+        manager.add_student(student).unwrap();
+        let contact = ContactInfo::new(Some("lisi@example.com".to_string()), None, Some("上海市\t徐汇区".to_string())).unwrap();
+        manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap().with_contact(contact)).unwrap();
+        let contact = ContactInfo::new(None, Some("13900000000".to_string()), None).unwrap();
+        manager.add_guardian(2, Guardian::new("李父".to_string(), "父亲".to_string(), contact).unwrap()).unwrap();
+
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.set_assessment_components(vec![
+            AssessmentComponent::new("平时", 40.0).unwrap(),
+            AssessmentComponent::new("期末", 60.0).unwrap(),
+        ]).unwrap();
+        manager.add_course(course).unwrap();
+        manager.add_instructor(Instructor::new(7, "王老师".to_string()).unwrap()).unwrap();
+        manager.assign_instructor(101, 7).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        manager.record_component_score(101, 1, "期末", 82.5).unwrap();
+        manager.record_attendance(101, "2024-09-02".parse().unwrap(), 1, AttendanceStatus::Late).unwrap();
+        manager.record_attendance(101, "2024-09-02".parse().unwrap(), 2, AttendanceStatus::Present).unwrap();
+        manager.record_attendance(101, "2024-09-09".parse().unwrap(), 1, AttendanceStatus::Excused).unwrap();
+        manager.add_course(Course::new(102, "数据库".to_string(), 2).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.add_course_grade(1, 102, "实验".to_string(), 91.0).unwrap();
+        let date = "2024-12-20".parse().unwrap();
+        let change = GradeChange::new(GradeValue::Score(93.0), "漏登\t附加题", "王老师", date).unwrap();
+        manager.amend_course_grade(1, 102, "实验", change).unwrap();
+        let change = GradeChange::new(GradeValue::Mark(GradeMark::Exempt), "免修申请通过", "教务处", date).unwrap();
+        manager.amend_course_grade(1, 102, "实验", change).unwrap();
+        manager.add_course_mark(2, 101, "期末".to_string(), GradeMark::Incomplete).unwrap();
+        manager.set_course_capacity(102, Some(1)).unwrap();
+        let slot = TimeSlot::new("周二".parse().unwrap(), "14:00".parse().unwrap(), "15:40".parse().unwrap(), "B 202").unwrap();
+        manager.add_course_time_slot(102, slot).unwrap();
+        manager.enroll_student_in_course(2, 102).unwrap();
+        manager.set_course_prerequisite(102, Some("any(course:101>=B, credits:3)".parse().unwrap())).unwrap();
+
+        let term = Term::new(1, "2024秋".to_string(), "2024-09-01".parse().unwrap(), "2025-01-15".parse().unwrap()).unwrap();
+        manager.add_term(term).unwrap();
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.add_grade_in_term(2, 1, "英语".to_string(), 66.0).unwrap();
+        manager.set_term_deadlines(1, None, Some("2024-11-15".parse().unwrap())).unwrap();
+        manager.withdraw_from_course(2, 101, "2024-10-08".parse().unwrap()).unwrap();
+        manager.change_student_status(1, StudentStatus::OnLeave, "2024-10-09".parse().unwrap()).unwrap();
+        let mut programme = Programme::new(1, "软件工程", 120).unwrap();
+        programme.add_required_course(101).unwrap();
+        programme.add_elective_pool(ElectivePool::new("数据\t方向", [102], 2).unwrap()).unwrap();
+        manager.add_programme(programme).unwrap();
+
+        manager
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("enrollment.dat");
+        let manager = sample_manager();
+
+        manager.save_to_file(&path).unwrap();
+        let loaded = EnrollmentManager::load_from_file(&path).unwrap();
+
+        assert_eq!(manager, loaded);
+        assert_eq!(loaded.get_course(101).unwrap().student_count(), 1);
+        assert_eq!(loaded.get_course(102).unwrap().waitlist(), vec![2]);
+        assert_eq!(loaded.get_instructor(7).unwrap().name, "王老师");
+        assert_eq!(loaded.get_course(101).unwrap().instructor_id, Some(7));
+        assert_eq!(loaded.get_course(102).unwrap().time_slots()[0].to_string(), "周二 14:00-15:40 B 202");
+        assert_eq!(loaded.get_course(102).unwrap().prerequisite().unwrap().to_string(), "any(course:101>=B, credits:3)");
+        assert_eq!(loaded.get_course(101).unwrap().student_attendance(1).late, 1);
+        assert_eq!(loaded.get_student(2).unwrap().contact.address(), Some("上海市\t徐汇区"));
+        assert_eq!(loaded.get_student(2).unwrap().guardians[0].contact().phone(), Some("13900000000"));
+        assert!(loaded.get_student(1).unwrap().contact.is_empty());
+        assert_eq!(loaded.get_student(1).unwrap().status(), StudentStatus::OnLeave);
+        assert_eq!(loaded.get_student(1).unwrap().status_since(), Some("2024-10-09".parse().unwrap()));
+        assert_eq!(loaded.get_course(101).unwrap().attendance_sessions().len(), 2);
+        let amendments = loaded.get_student(1).unwrap().grades_in_course(102)[0].amendments();
+        assert_eq!(amendments.len(), 2);
+        assert_eq!(amendments[0].reason, "漏登\t附加题");
+        assert_eq!(amendments[1].old_value, GradeValue::Score(93.0));
+        let programme = loaded.get_programme(1).unwrap();
+        assert_eq!(programme.required_courses(), vec![101]);
+        assert_eq!(programme.elective_pools()[0].name, "数据\t方向");
+        assert_eq!(programme.elective_pools()[0].course_ids(), vec![102]);
+    }
+
+    #[test]
+    fn test_snapshot_starts_with_version_header() {
+        let snapshot = sample_manager().to_snapshot_string();
+        assert!(snapshot.starts_with("# enrollment-data v1\n"));
+    }
+
+    #[test]
+    fn test_custom_scale_letters_survive_round_trip() {
+        let mut manager = EnrollmentManager::new();
+        let scale = GradingScale::from_config_str("A 85 A\nD 60 D 1.5\nF 0 F\n").unwrap();
+        manager.set_grading_scale(scale.clone()).unwrap();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_grade(1, "数学".to_string(), 86.0).unwrap();
+
+        let loaded = EnrollmentManager::from_snapshot_str(&manager.to_snapshot_string()).unwrap();
+        let grade = loaded.get_student(1).unwrap().get_grade_by_subject("数学").unwrap();
+        assert_eq!(grade.letter_grade(), Some(&LetterGrade::A));
+        assert_eq!(loaded.grading_scale(), &scale);
+        assert_eq!(loaded.to_snapshot_string(), manager.to_snapshot_string());
+
+        // 标准等级制不写出
+        assert!(!sample_manager().to_snapshot_string().contains("\nscale\t"));
+    }
+
+    #[test]
+    fn test_attendance_threshold_survives_round_trip() {
+        let mut manager = sample_manager();
+        assert!(!manager.to_snapshot_string().contains("\nattendance_threshold\t"));
+        manager.set_attendance_threshold(62.5).unwrap();
+        let loaded = EnrollmentManager::from_snapshot_str(&manager.to_snapshot_string()).unwrap();
+        assert_eq!(loaded.attendance_threshold(), 62.5);
+
+        let invalid = append_checksum("# enrollment-data v1\nattendance_threshold\t120\n");
+        assert!(EnrollmentManager::from_snapshot_str(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_rejects_dangling_references() {
+        let base = "# enrollment-data v1\nstudent\t1\t张三\t20\ncourse\t101\tRust编程\t3\t\n";
+        assert!(EnrollmentManager::from_snapshot_str(&append_checksum(base)).is_ok());
+
+        let dangling = [
+            "enrolled\t101\t1\ncapacity\t101\t1\nwaitlisted\t101\t9\n",
+            "withdrawn\t101\t9\t2024-10-08\n",
+            "grade\t1\t数学\t90\tA\t\t999\n",
+            "course\t102\t数据库\t2\t7\n",
+            "course\t102\t数据库\t2\t\t5\n",
+            "prerequisite\t101\tcourse:999\n",
+            "programme\t1\t软件工程\t120\nrequired\t1\t999\n",
+            // 选修课组要求的学分超过组内课程的学分
+            "programme\t1\t软件工程\t120\nelective\t1\t专业选修\t5\t101\n",
+        ];
+        for record in dangling {
+            let content = format!("# enrollment-data v{}\n{}{}", FORMAT_VERSION, &base[base.find('\n').unwrap() + 1..], record);
+            let content = append_checksum(&content);
+            assert!(
+                matches!(EnrollmentManager::from_snapshot_str(&content), Err(AppError::CorruptedData(_))),
+                "应当拒绝: {}", record
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_forged_amendment_chains() {
+        let base = "student\t1\t张三\t20\ngrade\t1\t数学\t65\tD\n";
+        let chains = [
+            ("amended\t1\t58\t62\t复核\t王老师\t2024-07-01\namended\t1\t62\t65\t复核\t王老师\t2024-07-02\n", true),
+            // 最后一次修改的结果不是当前成绩
+            ("amended\t1\t58\t62\t复核\t王老师\t2024-07-01\n", false),
+            // 中间缺少一次修改
+            ("amended\t1\t58\t62\t复核\t王老师\t2024-07-01\namended\t1\t60\t65\t复核\t王老师\t2024-07-02\n", false),
+            // 日期倒序
+            ("amended\t1\t58\t62\t复核\t王老师\t2024-07-02\namended\t1\t62\t65\t复核\t王老师\t2024-07-01\n", false),
+        ];
+        for (chain, valid) in chains {
+            let content = append_checksum(&format!("# enrollment-data v{}\n{}{}", FORMAT_VERSION, base, chain));
+            assert_eq!(EnrollmentManager::from_snapshot_str(&content).is_ok(), valid, "{}", chain);
+        }
+    }
+
+    #[test]
+    fn test_detects_corrupted_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("enrollment.dat");
+        sample_manager().save_to_file(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replace("95.5", "59.5")).unwrap();
+        assert!(matches!(
+            EnrollmentManager::load_from_file(&path),
+            Err(AppError::CorruptedData(_))
+        ));
+
+        let truncated: String = content.lines().take(3).map(|l| format!("{}\n", l)).collect();
+        std::fs::write(&path, truncated).unwrap();
+        assert!(matches!(
+            EnrollmentManager::load_from_file(&path),
+            Err(AppError::CorruptedData(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unknown_version_and_bad_records() {
+        assert!(EnrollmentManager::from_snapshot_str(&append_checksum("# enrollment-data v2\n")).is_err());
+        assert!(EnrollmentManager::from_snapshot_str("student\t1\t张三\t20\n").is_err());
+        // 没有校验和的文件视为被截断
+        assert!(matches!(
+            EnrollmentManager::from_snapshot_str("# enrollment-data v1\nstudent\t1\t张三\t20\n"),
+            Err(AppError::CorruptedData(_))
+        ));
+        // 成绩记录必须带等级字段
+        assert!(EnrollmentManager::from_snapshot_str(&append_checksum(
+            "# enrollment-data v1\nstudent\t1\t张三\t20\ngrade\t1\t数学\t95\n"
+        )).is_err());
+        assert!(EnrollmentManager::from_snapshot_str(
+            "# enrollment-data v1\ngrade\t1\t数学\t95\n"
+        ).is_err());
+        assert!(EnrollmentManager::from_snapshot_str(
+            "# enrollment-data v1\nstudent\t1\t张三\t200\n"
+        ).is_err());
+        // 选修课组至少要有一门课程
+        assert!(EnrollmentManager::from_snapshot_str(&append_checksum(
+            "# enrollment-data v1\ncourse\t101\tRust编程\t3\t\nprogramme\t1\t软件工程\t120\nelective\t1\t专业选修\t3\n"
+        )).is_err());
+        // 修改记录必须跟在成绩记录之后
+        assert!(EnrollmentManager::from_snapshot_str(&append_checksum(
+            "# enrollment-data v1\nstudent\t1\t张三\t20\namended\t1\t58\t60\t复核\t王老师\t2024-12-20\n"
+        )).is_err());
+    }
+
+    #[test]
+    fn test_term_references_survive_round_trip() {
+        let loaded = EnrollmentManager::from_snapshot_str(&sample_manager().to_snapshot_string()).unwrap();
+        assert_eq!(loaded.get_term(1).unwrap().name, "2024秋");
+        assert_eq!(loaded.get_term(1).unwrap().add_drop_deadline, None);
+        assert!(loaded.get_term(1).unwrap().withdrawal_deadline.is_some());
+        assert!(loaded.get_course(101).unwrap().is_withdrawn(2));
+        assert_eq!(loaded.get_student(2).unwrap().grades_in_course(101)[0].mark(), Some(GradeMark::Incomplete));
+        assert_eq!(loaded.get_course(101).unwrap().term_id, Some(1));
+        assert_eq!(loaded.get_student(2).unwrap().grades_in_term(1).len(), 1);
+    }
+
+    #[test]
+    fn test_field_escaping_round_trip() {
+        let line = encode_record(&["a\tb", "c\\d", "e\nf"]);
+        assert_eq!(decode_record(line.trim_end_matches('\n')), vec!["a\tb", "c\\d", "e\nf"]);
+    }
+}
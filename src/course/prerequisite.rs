@@ -0,0 +1,609 @@
+//! 先修要求模块
+//!
+//! 课程可以设置先修要求：修过指定课程（可以要求最低等级）、已获得的学分，
+//! 以及它们的"全部满足"和"满足其一"组合。注册课程时检查学生是否满足要求。
+//!
+//! 先修要求可以写成文本形式，例如：
+//!
+//! ```text
+//! all(course:101>=B, any(course:102, credits:30))
+//! ```
+//!
+//! 只有已经结束的课程才算修过，见 `EnrollmentManager::is_course_finished`。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::student::grade::{GradeMark, LetterGrade};
+use crate::student::Student;
+use crate::utils::date::Date;
+use std::collections::HashMap;
+
+/// 先修要求文本中组合条件允许的最大嵌套层数
+const MAX_NESTING_DEPTH: usize = 32;
+
+/// 先修要求
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Prerequisite {
+    /// 修过指定课程并及格；给出 `min_grade` 时成绩还不能低于该等级
+    Course {
+        course_id: u32,
+        min_grade: Option<LetterGrade>,
+    },
+    /// 已获得（及格课程的）学分不少于指定数量
+    Credits(u32),
+    /// 全部满足
+    All(Vec<Prerequisite>),
+    /// 至少满足一项
+    Any(Vec<Prerequisite>),
+}
+
+impl Prerequisite {
+    /// 修过指定课程并及格
+    pub fn course(course_id: u32) -> Self {
+        Prerequisite::Course { course_id, min_grade: None }
+    }
+
+    /// 修过指定课程且成绩不低于 `min_grade`
+    pub fn course_with_grade(course_id: u32, min_grade: LetterGrade) -> Self {
+        Prerequisite::Course { course_id, min_grade: Some(min_grade) }
+    }
+
+    /// 要求中引用的所有课程ID
+    pub fn referenced_courses(&self) -> Vec<u32> {
+        match self {
+            Prerequisite::Course { course_id, .. } => vec![*course_id],
+            Prerequisite::Credits(_) => Vec::new(),
+            Prerequisite::All(items) | Prerequisite::Any(items) => {
+                items.iter().flat_map(Prerequisite::referenced_courses).collect()
+            }
+        }
+    }
+
+    /// 检查组合要求不为空
+    pub fn validate(&self) -> AppResult<()> {
+        match self {
+            Prerequisite::All(items) | Prerequisite::Any(items) => {
+                if items.is_empty() {
+                    return Err(AppError::ValidationError("先修要求的组合条件不能为空".to_string()));
+                }
+                items.iter().try_for_each(Prerequisite::validate)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Prerequisite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Prerequisite::Course { course_id, min_grade: None } => write!(f, "course:{}", course_id),
+            Prerequisite::Course { course_id, min_grade: Some(grade) } => {
+                write!(f, "course:{}>={}", course_id, grade)
+            }
+            Prerequisite::Credits(credits) => write!(f, "credits:{}", credits),
+            Prerequisite::All(items) | Prerequisite::Any(items) => {
+                let keyword = if matches!(self, Prerequisite::All(_)) { "all" } else { "any" };
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "{}({})", keyword, items.join(", "))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Prerequisite {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let mut parser = Parser { input: s, position: 0, depth: 0 };
+        let prerequisite = parser.parse_expression()?;
+        parser.skip_whitespace();
+        if parser.position != s.len() {
+            return Err(parser.error("多余的内容"));
+        }
+        prerequisite.validate()?;
+        Ok(prerequisite)
+    }
+}
+
+/// 先修要求文本的递归下降解析器
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// 当前所在的组合条件层数
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.position = self.input.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 读取组合条件的关键字和左括号，两者之间可以有空白
+    fn eat_group(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let Some(rest) = self.rest().strip_prefix(keyword) else {
+            return false;
+        };
+        let Some(rest) = rest.trim_start().strip_prefix('(') else {
+            return false;
+        };
+        self.position = self.input.len() - rest.len();
+        true
+    }
+
+    fn error(&self, message: &str) -> AppError {
+        AppError::InvalidInput(format!(
+            "先修要求 '{}' 第{}个字符处{}", self.input, self.position + 1, message
+        ))
+    }
+
+    /// 读取到分隔符为止的一个词
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let end = rest.find(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '>'))
+            .unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn number(&mut self) -> AppResult<u32> {
+        let word = self.word();
+        word.parse().map_err(|_| AppError::InvalidInput(
+            format!("先修要求中的 '{}' 不是有效的数字", word)
+        ))
+    }
+
+    fn parse_expression(&mut self) -> AppResult<Prerequisite> {
+        if self.eat_group("all") {
+            return Ok(Prerequisite::All(self.parse_list()?));
+        }
+        if self.eat_group("any") {
+            return Ok(Prerequisite::Any(self.parse_list()?));
+        }
+        if self.eat("course:") {
+            let course_id = self.number()?;
+            let min_grade = if self.eat(">=") {
+                Some(self.word().parse()?)
+            } else {
+                None
+            };
+            return Ok(Prerequisite::Course { course_id, min_grade });
+        }
+        if self.eat("credits:") {
+            return Ok(Prerequisite::Credits(self.number()?));
+        }
+        Err(self.error("需要 all(、any(、course: 或 credits:"))
+    }
+
+    fn parse_list(&mut self) -> AppResult<Vec<Prerequisite>> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(self.error(&format!("组合条件嵌套超过{}层", MAX_NESTING_DEPTH)));
+        }
+        self.depth += 1;
+        let mut items = Vec::new();
+        if self.eat(")") {
+            self.depth -= 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_expression()?);
+            if self.eat(")") {
+                self.depth -= 1;
+                return Ok(items);
+            }
+            if !self.eat(",") {
+                return Err(self.error("需要 ',' 或 ')'"));
+            }
+        }
+    }
+}
+
+/// 学生已修课程的情况，用于检查先修要求
+pub(crate) struct AcademicRecord {
+    /// 已结束课程的课程ID -> 课程结果
+    pub results: HashMap<u32, CourseResult>,
+    /// 及格课程的学分之和
+    pub earned_credits: u32,
+}
+
+/// 一门已修课程的结果
+pub(crate) enum CourseResult {
+    Letter(LetterGrade),
+    /// 不以分数记录的结果；免修满足任何成绩要求，通过只满足没有最低等级的要求
+    Mark(GradeMark),
+}
+
+impl CourseResult {
+    /// 是否及格（获得学分）
+    pub fn is_passing(&self) -> bool {
+        match self {
+            CourseResult::Letter(letter) => *letter != LetterGrade::F,
+            CourseResult::Mark(mark) => mark.is_passing(),
+        }
+    }
+}
+
+impl std::fmt::Display for CourseResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CourseResult::Letter(letter) => write!(f, "{}", letter),
+            CourseResult::Mark(mark) => write!(f, "{}", mark),
+        }
+    }
+}
+
+impl Course {
+    pub fn prerequisite(&self) -> Option<&Prerequisite> {
+        self.prerequisite.as_ref()
+    }
+
+    /// 设置先修要求，不检查循环依赖（由 `EnrollmentManager` 负责）
+    pub fn set_prerequisite(&mut self, prerequisite: Option<Prerequisite>) -> AppResult<()> {
+        if let Some(prerequisite) = &prerequisite {
+            prerequisite.validate()?;
+        }
+        self.prerequisite = prerequisite;
+        Ok(())
+    }
+}
+
+impl EnrollmentManager {
+    /// 修改课程的先修要求，引用不存在的课程或形成循环依赖时拒绝
+    pub fn set_course_prerequisite(
+        &mut self,
+        course_id: u32,
+        prerequisite: Option<Prerequisite>,
+    ) -> AppResult<()> {
+        self.run_operation("set_course_prerequisite", |manager| {
+            if let Some(prerequisite) = &prerequisite {
+                manager.check_prerequisite(course_id, prerequisite)?;
+            }
+            manager.update_course(course_id, |course| course.set_prerequisite(prerequisite))
+        })
+    }
+
+    /// 检查学生是否满足课程的先修要求，不满足时的错误说明哪项要求没有达到
+    ///
+    /// 只有截至今天已经结束的课程才算修过。
+    pub fn check_prerequisites(&self, student_id: u32, course_id: u32) -> AppResult<()> {
+        let course = self.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("课程ID {} 不存在", course_id)
+            ))?;
+        let Some(prerequisite) = course.prerequisite() else {
+            return Ok(());
+        };
+
+        let record = self.academic_record(student_id, Some(course_id), Date::today())?;
+        match self.unmet_requirement(prerequisite, &record) {
+            None => Ok(()),
+            Some(reason) => Err(AppError::ValidationError(
+                format!("学生ID {} 不满足课程 {} 的先修要求: {}", student_id, course.name, reason)
+            )),
+        }
+    }
+
+    /// 添加或修改课程时检查先修要求引用的课程都存在，并且先修关系不形成循环
+    pub(crate) fn check_prerequisite(
+        &self,
+        course_id: u32,
+        prerequisite: &Prerequisite,
+    ) -> AppResult<()> {
+        let referenced = prerequisite.referenced_courses();
+        if let Some(missing) = referenced.iter().find(|&&id| id != course_id && self.get_course(id).is_none()) {
+            return Err(AppError::NotFound(
+                format!("先修要求引用的课程ID {} 不存在", missing)
+            ));
+        }
+
+        let mut path = vec![course_id];
+        if self.reaches(course_id, referenced, &mut path) {
+            let path: Vec<String> = path.iter().map(|id| id.to_string()).collect();
+            return Err(AppError::ValidationError(
+                format!("先修要求形成循环: {}", path.join(" -> "))
+            ));
+        }
+        Ok(())
+    }
+
+    /// 深度优先查找能否从 `next` 经先修关系回到 `target`，找到时 `path` 为循环路径
+    fn reaches(&self, target: u32, next: Vec<u32>, path: &mut Vec<u32>) -> bool {
+        for course_id in next {
+            if path[1..].contains(&course_id) {
+                continue;
+            }
+            path.push(course_id);
+            if course_id == target {
+                return true;
+            }
+            let referenced = self.get_course(course_id)
+                .and_then(|c| c.prerequisite())
+                .map(|p| p.referenced_courses())
+                .unwrap_or_default();
+            if self.reaches(target, referenced, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// 学生的课程结果截至 `as_of` 是否已经确定
+    ///
+    /// 课程所在学期已经结束，或者课程定义了考核项且各项都有分数时课程结束；
+    /// 不以分数记录的结果（未完成除外）本身就是最终结果。没有安排学期也没有考核项的课程
+    /// 无从判断，有成绩即算结束。
+    pub fn is_course_finished(&self, course: &Course, student: &Student, as_of: Date) -> bool {
+        if let Some(mark) = course.student_mark(student) {
+            return mark != GradeMark::Incomplete;
+        }
+        let term_ended = course.term_id
+            .and_then(|id| self.get_term(id))
+            .map(|term| term.end_date < as_of);
+        match term_ended {
+            Some(true) => true,
+            _ if !course.assessment_components().is_empty() => course.final_score(student.id).is_some(),
+            Some(false) => false,
+            None => true,
+        }
+    }
+
+    /// 学生在除 `excluding` 以外、截至 `as_of` 已结束的已注册课程上的成绩和已获得学分
+    ///
+    /// 分数按管理器当前的等级制换算为等级，与成绩记录上的等级一致。
+    pub(crate) fn academic_record(
+        &self,
+        student_id: u32,
+        excluding: Option<u32>,
+        as_of: Date,
+    ) -> AppResult<AcademicRecord> {
+        let student = self.get_student(student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不存在", student_id)
+            ))?;
+
+        let mut record = AcademicRecord { results: HashMap::new(), earned_credits: 0 };
+        for course in self.list_all_courses() {
+            if Some(course.id) == excluding || !self.is_course_finished(course, student, as_of) {
+                continue;
+            }
+            let result = match (course.student_mark(student), course.student_score(student)) {
+                (Some(mark), _) => CourseResult::Mark(mark),
+                (None, Some(score)) => CourseResult::Letter(self.grading_scale().letter_for(score)),
+                (None, None) => continue,
+            };
+            if result.is_passing() {
+                record.earned_credits += course.credits as u32;
+            }
+            record.results.insert(course.id, result);
+        }
+        Ok(record)
+    }
+
+    /// 第一个没有满足的要求的说明，全部满足时返回 `None`
+    fn unmet_requirement(&self, prerequisite: &Prerequisite, record: &AcademicRecord) -> Option<String> {
+        match prerequisite {
+            Prerequisite::Course { course_id, min_grade } => {
+                let name = self.get_course(*course_id)
+                    .map(|c| format!("{}({})", c.name, course_id))
+                    .unwrap_or_else(|| format!("ID {}", course_id));
+                match (record.results.get(course_id), min_grade) {
+                    (None, _) => Some(format!("需要先修课程 {}", name)),
+                    (Some(CourseResult::Letter(LetterGrade::F)), _) => Some(format!("先修课程 {} 没有及格", name)),
+                    (Some(CourseResult::Letter(letter)), Some(min)) if letter.to_gpa() < min.to_gpa() => Some(format!(
+                        "先修课程 {} 的成绩需要不低于 {}（当前为 {}）", name, min, letter
+                    )),
+                    (Some(CourseResult::Mark(mark)), _) if !mark.is_passing() => Some(format!(
+                        "先修课程 {} 没有通过（当前为 {}）", name, mark.label()
+                    )),
+                    (Some(CourseResult::Mark(GradeMark::Pass)), Some(min)) => Some(format!(
+                        "先修课程 {} 的成绩需要不低于 {}（当前为 {}）", name, min, GradeMark::Pass.label()
+                    )),
+                    _ => None,
+                }
+            }
+            Prerequisite::Credits(required) => (record.earned_credits < *required).then(|| {
+                format!("需要已获得 {} 学分（当前为 {}）", required, record.earned_credits)
+            }),
+            Prerequisite::All(items) => {
+                let unmet: Vec<String> = items.iter()
+                    .filter_map(|item| self.unmet_requirement(item, record))
+                    .collect();
+                (!unmet.is_empty()).then(|| unmet.join("；"))
+            }
+            Prerequisite::Any(items) => {
+                let unmet: Vec<String> = items.iter()
+                    .map(|item| self.unmet_requirement(item, record))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("以下条件至少满足一项: {}", unmet.join(" 或 ")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::assessment::AssessmentComponent;
+    use crate::course::test_support::manager_with;
+
+    fn manager() -> EnrollmentManager {
+        manager_with(&[(1, "张三")], &[(101, "程序设计", 4), (102, "离散数学", 3), (201, "数据结构", 3)])
+    }
+
+    fn pass(manager: &mut EnrollmentManager, course_id: u32, score: f64) {
+        manager.enroll_student_in_course(1, course_id).unwrap();
+        manager.add_course_grade(1, course_id, "期末".to_string(), score).unwrap();
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        let text = "all(course:101>=B+, any(course:102, credits:30))";
+        let prerequisite: Prerequisite = text.parse().unwrap();
+        assert_eq!(prerequisite.to_string(), text);
+        assert_eq!(prerequisite.referenced_courses(), vec![101, 102]);
+        assert_eq!(" credits:5 ".parse::<Prerequisite>().unwrap(), Prerequisite::Credits(5));
+        assert_eq!("all (course:101, any\t( credits:5 ))".parse::<Prerequisite>().unwrap().to_string(),
+                   "all(course:101, any(credits:5))");
+
+        for bad in ["", "all()", "course:abc", "course:101>=E", "any(credits:3", "credits:3 x", "allx(credits:3)"] {
+            assert!(bad.parse::<Prerequisite>().is_err(), "{}", bad);
+        }
+
+        let nested = |depth: usize| format!("{}credits:1{}", "all(".repeat(depth), ")".repeat(depth));
+        assert!(nested(MAX_NESTING_DEPTH).parse::<Prerequisite>().is_ok());
+        let error = nested(100_000).parse::<Prerequisite>().unwrap_err().to_string();
+        assert!(error.contains("嵌套超过"), "{}", error);
+    }
+
+    #[test]
+    fn test_enrollment_requires_course_and_grade() {
+        let mut manager = manager();
+        manager.set_course_prerequisite(201, Some("course:101>=B".parse().unwrap())).unwrap();
+
+        let error = manager.enroll_student_in_course(1, 201).unwrap_err().to_string();
+        assert!(error.contains("需要先修课程 程序设计(101)"), "{}", error);
+
+        pass(&mut manager, 101, 75.0);
+        let error = manager.enroll_student_in_course(1, 201).unwrap_err().to_string();
+        assert!(error.contains("不低于 B（当前为 C）"), "{}", error);
+
+        manager.add_course_grade(1, 101, "补考".to_string(), 95.0).unwrap();
+        assert!(manager.enroll_student_in_course(1, 201).is_ok());
+    }
+
+    #[test]
+    fn test_any_and_credit_thresholds() {
+        let mut manager = manager();
+        let rule = "any(credits:7, all(course:101, course:102))".parse().unwrap();
+        manager.set_course_prerequisite(201, Some(rule)).unwrap();
+
+        pass(&mut manager, 101, 90.0);
+        let error = manager.check_prerequisites(1, 201).unwrap_err().to_string();
+        assert!(error.contains("需要已获得 7 学分（当前为 4）"), "{}", error);
+        assert!(error.contains("需要先修课程 离散数学(102)"), "{}", error);
+
+        // 不及格的课程不计学分
+        pass(&mut manager, 102, 40.0);
+        assert!(manager.check_prerequisites(1, 201).is_err());
+        manager.add_course_grade(1, 102, "补考".to_string(), 100.0).unwrap();
+        assert!(manager.check_prerequisites(1, 201).is_ok());
+    }
+
+    #[test]
+    fn test_marks_satisfy_prerequisites() {
+        let mut manager = manager();
+        manager.set_course_prerequisite(201, Some("all(course:101, course:102>=C, credits:7)".parse().unwrap())).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+
+        manager.add_course_mark(1, 101, "总评".to_string(), GradeMark::Audit).unwrap();
+        manager.add_course_mark(1, 102, "总评".to_string(), GradeMark::Pass).unwrap();
+        let error = manager.check_prerequisites(1, 201).unwrap_err().to_string();
+        assert!(error.contains("先修课程 程序设计(101) 没有通过（当前为 旁听）"), "{}", error);
+        assert!(error.contains("成绩需要不低于 C（当前为 通过）"), "{}", error);
+        assert!(error.contains("需要已获得 7 学分（当前为 3）"), "{}", error);
+
+        // 最后一条结果决定课程结果，免修满足任何成绩要求
+        manager.add_course_mark(1, 101, "总评".to_string(), GradeMark::Exempt).unwrap();
+        manager.add_course_mark(1, 102, "总评".to_string(), GradeMark::Exempt).unwrap();
+        assert!(manager.check_prerequisites(1, 201).is_ok());
+    }
+
+    #[test]
+    fn test_incomplete_is_resolved_by_later_score() {
+        let mut manager = manager();
+        manager.set_course_prerequisite(201, Some("course:101>=B".parse().unwrap())).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.add_course_mark(1, 101, "总评".to_string(), GradeMark::Incomplete).unwrap();
+        let error = manager.check_prerequisites(1, 201).unwrap_err().to_string();
+        assert!(error.contains("需要先修课程 程序设计(101)"), "{}", error);
+
+        manager.add_course_grade(1, 101, "补考".to_string(), 85.0).unwrap();
+        let course = manager.get_course(101).unwrap();
+        let student = manager.get_student(1).unwrap();
+        assert_eq!(course.student_mark(student), None);
+        assert_eq!(course.student_score(student), Some(85.0));
+        assert!(manager.check_prerequisites(1, 201).is_ok());
+    }
+
+    #[test]
+    fn test_detects_cycles() {
+        let mut manager = manager();
+        manager.set_course_prerequisite(201, Some(Prerequisite::course(101))).unwrap();
+        manager.set_course_prerequisite(101, Some(Prerequisite::course(102))).unwrap();
+
+        let error = manager.set_course_prerequisite(102, Some(Prerequisite::course(201))).unwrap_err();
+        assert!(error.to_string().contains("102 -> 201 -> 101 -> 102"), "{}", error);
+        assert!(manager.set_course_prerequisite(102, Some(Prerequisite::course(102))).is_err());
+
+        let mut course = Course::new(301, "编译原理".to_string(), 3).unwrap();
+        course.set_prerequisite(Some(Prerequisite::course(301))).unwrap();
+        assert!(manager.add_course(course).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_courses() {
+        let mut manager = manager();
+        let error = manager.set_course_prerequisite(201, Some("any(course:101, course:999)".parse().unwrap())).unwrap_err();
+        assert!(matches!(error, AppError::NotFound(_)), "{}", error);
+        assert!(manager.get_course(201).unwrap().prerequisite().is_none());
+
+        let mut course = Course::new(301, "编译原理".to_string(), 3).unwrap();
+        course.set_prerequisite(Some(Prerequisite::course(999))).unwrap();
+        assert!(manager.add_course(course).is_err());
+        assert!(manager.get_course(301).is_none());
+    }
+
+    #[test]
+    fn test_only_finished_courses_count() {
+        let mut manager = manager();
+        let start = "2024-09-01".parse().unwrap();
+        let end = "2025-01-15".parse().unwrap();
+        manager.add_term(crate::course::term::Term::new(1, "2024秋".to_string(), start, end).unwrap()).unwrap();
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.update_course(102, |course| course.set_assessment_components(vec![
+            AssessmentComponent::new("期中", 40.0).unwrap(),
+            AssessmentComponent::new("期末", 60.0).unwrap(),
+        ])).unwrap();
+        manager.set_course_prerequisite(201, Some("all(course:101, course:102)".parse().unwrap())).unwrap();
+
+        pass(&mut manager, 101, 90.0);
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.record_component_score(102, 1, "期中", 85.0).unwrap();
+        let student = manager.get_student(1).unwrap();
+        let during_term = "2024-12-01".parse().unwrap();
+        let record = manager.academic_record(1, None, during_term).unwrap();
+        assert!(record.results.is_empty());
+        assert!(!manager.is_course_finished(manager.get_course(101).unwrap(), student, during_term));
+
+        // 学期结束后课程成绩确定，考核项都有分数的课程随时可以确定
+        manager.record_component_score(102, 1, "期末", 75.0).unwrap();
+        let record = manager.academic_record(1, None, "2025-02-01".parse().unwrap()).unwrap();
+        assert_eq!(record.earned_credits, 7);
+        assert!(manager.check_prerequisites(1, 201).is_ok());
+
+        manager.add_course_mark(1, 102, "总评".to_string(), GradeMark::Incomplete).unwrap();
+        let record = manager.academic_record(1, None, "2025-02-01".parse().unwrap()).unwrap();
+        assert_eq!(record.earned_credits, 4);
+    }
+}
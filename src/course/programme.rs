@@ -0,0 +1,510 @@
+//! 专业培养方案模块
+//!
+//! 培养方案规定必修课程、若干选修课组（组内修满指定学分）和毕业所需的最低总学分。
+//! 学位审核把学生在管理器中的课程成绩与培养方案逐项比较，列出已满足和尚未满足的要求。
+//! 课程是否修完的判断与先修要求相同：有成绩且及格才算完成。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// 选修课组：从组内课程中修满 `min_credits` 学分
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ElectivePoolData"))]
+pub struct ElectivePool {
+    pub name: String,
+    course_ids: BTreeSet<u32>,
+    pub min_credits: u32,
+}
+
+impl ElectivePool {
+    pub fn new(name: &str, course_ids: impl IntoIterator<Item = u32>, min_credits: u32) -> AppResult<Self> {
+        let name = validate_title(name, "选修课组名称")?;
+        let course_ids: BTreeSet<u32> = course_ids.into_iter().collect();
+        if course_ids.is_empty() {
+            return Err(AppError::ValidationError(format!("选修课组 {} 没有课程", name)));
+        }
+        if min_credits == 0 {
+            return Err(AppError::ValidationError(format!("选修课组 {} 的学分要求必须大于0", name)));
+        }
+        Ok(ElectivePool { name, course_ids, min_credits })
+    }
+
+    /// 组内课程ID，按ID排序
+    pub fn course_ids(&self) -> Vec<u32> {
+        self.course_ids.iter().copied().collect()
+    }
+
+    pub fn contains(&self, course_id: u32) -> bool {
+        self.course_ids.contains(&course_id)
+    }
+}
+
+/// 反序列化时的原始数据，经过 `ElectivePool::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ElectivePoolData {
+    name: String,
+    course_ids: Vec<u32>,
+    min_credits: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ElectivePoolData> for ElectivePool {
+    type Error = AppError;
+
+    fn try_from(data: ElectivePoolData) -> AppResult<Self> {
+        ElectivePool::new(&data.name, data.course_ids, data.min_credits)
+    }
+}
+
+/// 专业培养方案
+///
+/// 每门课程最多出现在一处：要么是必修课程，要么属于某一个选修课组。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ProgrammeData"))]
+pub struct Programme {
+    pub id: u32,
+    pub name: String,
+    /// 毕业所需的最低总学分，所有及格课程都计入
+    pub min_credits: u32,
+    required_courses: BTreeSet<u32>,
+    elective_pools: Vec<ElectivePool>,
+}
+
+impl Programme {
+    pub fn new(id: u32, name: &str, min_credits: u32) -> AppResult<Self> {
+        Ok(Programme {
+            id,
+            name: validate_title(name, "培养方案名称")?,
+            min_credits,
+            required_courses: BTreeSet::new(),
+            elective_pools: Vec::new(),
+        })
+    }
+
+    pub fn add_required_course(&mut self, course_id: u32) -> AppResult<()> {
+        self.check_unused(course_id)?;
+        self.required_courses.insert(course_id);
+        Ok(())
+    }
+
+    /// 添加选修课组，组名不能重复
+    pub fn add_elective_pool(&mut self, pool: ElectivePool) -> AppResult<()> {
+        if self.elective_pools.iter().any(|p| p.name == pool.name) {
+            return Err(AppError::ValidationError(
+                format!("培养方案 {} 已有选修课组 {}", self.name, pool.name)
+            ));
+        }
+        for &course_id in &pool.course_ids {
+            self.check_unused(course_id)?;
+        }
+        self.elective_pools.push(pool);
+        Ok(())
+    }
+
+    /// 必修课程ID，按ID排序
+    pub fn required_courses(&self) -> Vec<u32> {
+        self.required_courses.iter().copied().collect()
+    }
+
+    /// 选修课组，按添加顺序排列
+    pub fn elective_pools(&self) -> &[ElectivePool] {
+        &self.elective_pools
+    }
+
+    /// 培养方案中出现的所有课程ID，按ID排序
+    pub fn referenced_courses(&self) -> Vec<u32> {
+        let mut ids: BTreeSet<u32> = self.required_courses.clone();
+        for pool in &self.elective_pools {
+            ids.extend(&pool.course_ids);
+        }
+        ids.into_iter().collect()
+    }
+
+    /// 检查引用的课程都存在，并且每个选修课组的学分要求可以达到
+    pub(crate) fn check_courses<'a, F>(&self, course: F) -> AppResult<()>
+    where
+        F: Fn(u32) -> Option<&'a Course>,
+    {
+        for course_id in self.referenced_courses() {
+            if course(course_id).is_none() {
+                return Err(AppError::NotFound(
+                    format!("培养方案 {} 引用的课程ID {} 不存在", self.name, course_id)
+                ));
+            }
+        }
+        for pool in &self.elective_pools {
+            let available: u32 = pool.course_ids.iter()
+                .filter_map(|&id| course(id))
+                .map(|c| u32::from(c.credits))
+                .sum();
+            if available < pool.min_credits {
+                return Err(AppError::ValidationError(format!(
+                    "选修课组 {} 要求{}学分，但组内课程只有{}学分",
+                    pool.name, pool.min_credits, available
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_unused(&self, course_id: u32) -> AppResult<()> {
+        let location = if self.required_courses.contains(&course_id) {
+            "必修课程".to_string()
+        } else if let Some(pool) = self.elective_pools.iter().find(|p| p.contains(course_id)) {
+            format!("选修课组 {}", pool.name)
+        } else {
+            return Ok(());
+        };
+        Err(AppError::ValidationError(
+            format!("课程ID {} 已是培养方案 {} 的{}", course_id, self.name, location)
+        ))
+    }
+}
+
+impl fmt::Display for Programme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "培养方案[{}]: {}（至少{}学分）", self.id, self.name, self.min_credits)
+    }
+}
+
+/// 反序列化时的原始数据，重新经过 `Programme` 的验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ProgrammeData {
+    id: u32,
+    name: String,
+    min_credits: u32,
+    required_courses: Vec<u32>,
+    elective_pools: Vec<ElectivePool>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ProgrammeData> for Programme {
+    type Error = AppError;
+
+    fn try_from(data: ProgrammeData) -> AppResult<Self> {
+        let mut programme = Programme::new(data.id, &data.name, data.min_credits)?;
+        for course_id in data.required_courses {
+            programme.add_required_course(course_id)?;
+        }
+        for pool in data.elective_pools {
+            programme.add_elective_pool(pool)?;
+        }
+        Ok(programme)
+    }
+}
+
+/// 去掉首尾空白后不能为空
+fn validate_title(text: &str, what: &str) -> AppResult<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AppError::ValidationError(format!("{}不能为空", what)));
+    }
+    Ok(text.to_string())
+}
+
+/// 学生在一门课程上的进度
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CourseProgress {
+    /// 已及格，附带等级或结果代码
+    Completed(String),
+    /// 有成绩但没有及格
+    NotPassed(String),
+    /// 已注册，还没有成绩
+    InProgress,
+    NotTaken,
+}
+
+impl CourseProgress {
+    pub fn is_completed(&self) -> bool {
+        matches!(self, CourseProgress::Completed(_))
+    }
+}
+
+impl fmt::Display for CourseProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CourseProgress::Completed(grade) => write!(f, "已完成（{}）", grade),
+            CourseProgress::NotPassed(grade) => write!(f, "未通过（{}）", grade),
+            CourseProgress::InProgress => write!(f, "在读"),
+            CourseProgress::NotTaken => write!(f, "未修读"),
+        }
+    }
+}
+
+/// 审核报告中的一门课程
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CourseAudit {
+    pub course_id: u32,
+    pub course_name: String,
+    pub credits: u8,
+    pub progress: CourseProgress,
+}
+
+/// 审核报告中的一个选修课组
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolAudit {
+    pub name: String,
+    pub required_credits: u32,
+    /// 组内已完成课程的学分之和
+    pub earned_credits: u32,
+    /// 按课程ID排序
+    pub courses: Vec<CourseAudit>,
+}
+
+impl PoolAudit {
+    pub fn is_satisfied(&self) -> bool {
+        self.earned_credits >= self.required_credits
+    }
+}
+
+/// 一名学生相对于一个培养方案的学位审核报告
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DegreeAudit {
+    pub student_id: u32,
+    pub student_name: String,
+    pub programme_id: u32,
+    pub programme_name: String,
+    /// 必修课程，按课程ID排序
+    pub required: Vec<CourseAudit>,
+    pub pools: Vec<PoolAudit>,
+    /// 所有及格课程的学分之和，包括培养方案以外的课程
+    pub earned_credits: u32,
+    pub min_credits: u32,
+}
+
+impl DegreeAudit {
+    /// 所有要求都已满足
+    pub fn is_complete(&self) -> bool {
+        self.outstanding().is_empty()
+    }
+
+    /// 尚未满足的要求的说明，按必修课程、选修课组、总学分的顺序排列
+    pub fn outstanding(&self) -> Vec<String> {
+        let mut outstanding: Vec<String> = self.required.iter()
+            .filter(|c| !c.progress.is_completed())
+            .map(|c| format!("必修课程 {}({})：{}", c.course_name, c.course_id, c.progress))
+            .collect();
+        for pool in self.pools.iter().filter(|p| !p.is_satisfied()) {
+            outstanding.push(format!(
+                "选修课组 {}：已修{}学分，还需{}学分",
+                pool.name, pool.earned_credits, pool.required_credits - pool.earned_credits
+            ));
+        }
+        if self.earned_credits < self.min_credits {
+            outstanding.push(format!(
+                "总学分：已修{}学分，还需{}学分",
+                self.earned_credits, self.min_credits - self.earned_credits
+            ));
+        }
+        outstanding
+    }
+}
+
+impl EnrollmentManager {
+    pub(crate) fn require_programme(&self, programme_id: u32) -> AppResult<&Programme> {
+        self.get_programme(programme_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("培养方案ID {} 不存在", programme_id)
+            ))
+    }
+
+    /// 检查培养方案引用的课程都存在，并且每个选修课组的学分要求可以达到
+    pub(crate) fn check_programme(&self, programme: &Programme) -> AppResult<()> {
+        programme.check_courses(|id| self.get_course(id))
+    }
+
+    /// 学生截至今天相对于培养方案的学位审核
+    pub fn degree_audit(&self, student_id: u32, programme_id: u32) -> AppResult<DegreeAudit> {
+        self.degree_audit_as_of(student_id, programme_id, Date::today())
+    }
+
+    /// 学生截至 `as_of` 相对于培养方案的学位审核
+    ///
+    /// 只有已经结束的课程（见 `is_course_finished`）计为完成或未通过并计入学分；
+    /// 已注册但还没有结束的课程即使已有部分成绩也是在读。
+    pub fn degree_audit_as_of(&self, student_id: u32, programme_id: u32, as_of: Date) -> AppResult<DegreeAudit> {
+        let programme = self.require_programme(programme_id)?;
+        let record = self.academic_record(student_id, None, as_of)?;
+        let student_name = self.get_student(student_id).map(|s| s.name.clone()).unwrap_or_default();
+
+        let audit_course = |course_id: u32| -> AppResult<CourseAudit> {
+            let course = self.get_course(course_id)
+                .ok_or_else(|| AppError::NotFound(
+                    format!("课程ID {} 不存在", course_id)
+                ))?;
+            let progress = match record.results.get(&course_id) {
+                Some(result) if result.is_passing() => CourseProgress::Completed(result.to_string()),
+                Some(result) => CourseProgress::NotPassed(result.to_string()),
+                None if course.is_enrolled(student_id) => CourseProgress::InProgress,
+                None => CourseProgress::NotTaken,
+            };
+            Ok(CourseAudit {
+                course_id,
+                course_name: course.name.clone(),
+                credits: course.credits,
+                progress,
+            })
+        };
+
+        let required = programme.required_courses()
+            .into_iter()
+            .map(audit_course)
+            .collect::<AppResult<Vec<_>>>()?;
+        let mut pools = Vec::new();
+        for pool in programme.elective_pools() {
+            let courses = pool.course_ids()
+                .into_iter()
+                .map(audit_course)
+                .collect::<AppResult<Vec<_>>>()?;
+            pools.push(PoolAudit {
+                name: pool.name.clone(),
+                required_credits: pool.min_credits,
+                earned_credits: courses.iter()
+                    .filter(|c| c.progress.is_completed())
+                    .map(|c| u32::from(c.credits))
+                    .sum(),
+                courses,
+            });
+        }
+
+        Ok(DegreeAudit {
+            student_id,
+            student_name,
+            programme_id,
+            programme_name: programme.name.clone(),
+            required,
+            pools,
+            earned_credits: record.earned_credits,
+            min_credits: programme.min_credits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::student::grade::GradeMark;
+    use crate::course::assessment::AssessmentComponent;
+    use crate::course::term::Term;
+    use crate::course::test_support::{date, manager_with};
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三")], &[
+            (101, "程序设计", 4), (102, "数据结构", 4), (201, "编译原理", 3),
+            (202, "数据库", 3), (203, "计算机图形学", 2),
+        ]);
+
+        let mut programme = Programme::new(1, "计算机科学与技术", 14).unwrap();
+        programme.add_required_course(101).unwrap();
+        programme.add_required_course(102).unwrap();
+        programme.add_elective_pool(ElectivePool::new("专业选修", [201, 202, 203], 5).unwrap()).unwrap();
+        manager.add_programme(programme).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_programme_rejects_overlapping_courses() {
+        let mut programme = Programme::new(1, "软件工程", 120).unwrap();
+        programme.add_required_course(101).unwrap();
+        assert!(programme.add_required_course(101).is_err());
+        assert!(programme.add_elective_pool(ElectivePool::new("选修", [101, 102], 3).unwrap()).is_err());
+        programme.add_elective_pool(ElectivePool::new("选修", [102, 103], 3).unwrap()).unwrap();
+        assert!(programme.add_elective_pool(ElectivePool::new("选修", [104], 3).unwrap()).is_err());
+        assert!(programme.add_elective_pool(ElectivePool::new("通识", [103], 2).unwrap()).is_err());
+        assert_eq!(programme.referenced_courses(), vec![101, 102, 103]);
+
+        assert!(Programme::new(2, "  ", 120).is_err());
+        assert!(ElectivePool::new("选修", [], 3).is_err());
+        assert!(ElectivePool::new("选修", [101], 0).is_err());
+    }
+
+    #[test]
+    fn test_add_programme_checks_courses() {
+        let mut manager = manager();
+        assert!(manager.add_programme(Programme::new(1, "重复", 10).unwrap()).is_err());
+
+        let mut unknown = Programme::new(2, "软件工程", 10).unwrap();
+        unknown.add_required_course(999).unwrap();
+        assert!(manager.add_programme(unknown).is_err());
+
+        // 组内课程只有 3 + 2 学分
+        let mut unreachable = Programme::new(3, "图形学方向", 10).unwrap();
+        unreachable.add_elective_pool(ElectivePool::new("方向课", [201, 203], 6).unwrap()).unwrap();
+        assert!(manager.add_programme(unreachable).is_err());
+        assert_eq!(manager.list_programmes().len(), 1);
+    }
+
+    #[test]
+    fn test_degree_audit() {
+        let mut manager = manager();
+        for course_id in [101, 102, 201, 202] {
+            manager.enroll_student_in_course(1, course_id).unwrap();
+        }
+        manager.add_course_grade(1, 101, "期末".to_string(), 88.0).unwrap();
+        manager.add_course_grade(1, 102, "期末".to_string(), 45.0).unwrap();
+        manager.add_course_mark(1, 201, "期末".to_string(), GradeMark::Pass).unwrap();
+
+        let audit = manager.degree_audit(1, 1).unwrap();
+        assert_eq!(audit.required[0].progress, CourseProgress::Completed("B".to_string()));
+        assert_eq!(audit.required[1].progress, CourseProgress::NotPassed("F".to_string()));
+        assert_eq!(audit.pools[0].earned_credits, 3);
+        assert_eq!(audit.pools[0].courses[1].progress, CourseProgress::InProgress);
+        assert_eq!(audit.pools[0].courses[2].progress, CourseProgress::NotTaken);
+        assert_eq!(audit.earned_credits, 7);
+        assert_eq!(audit.outstanding(), vec![
+            "必修课程 数据结构(102)：未通过（F）".to_string(),
+            "选修课组 专业选修：已修3学分，还需2学分".to_string(),
+            "总学分：已修7学分，还需7学分".to_string(),
+        ]);
+
+        manager.add_course_grade(1, 102, "补考".to_string(), 95.0).unwrap();
+        manager.add_course_grade(1, 202, "期末".to_string(), 76.0).unwrap();
+        let audit = manager.degree_audit(1, 1).unwrap();
+        assert!(audit.is_complete());
+        assert_eq!(audit.earned_credits, 14);
+
+        assert!(manager.degree_audit(9, 1).is_err());
+        assert!(manager.degree_audit(1, 9).is_err());
+    }
+
+    #[test]
+    fn test_unfinished_courses_are_in_progress() {
+        let mut manager = manager();
+        let term = Term::new(1, "2024秋".to_string(), date("2024-09-01"), date("2025-01-15")).unwrap();
+        manager.add_term(term).unwrap();
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.update_course(102, |course| course.set_assessment_components(vec![
+            AssessmentComponent::new("测验", 20.0).unwrap(),
+            AssessmentComponent::new("期末", 80.0).unwrap(),
+        ])).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.add_course_grade(1, 101, "期中".to_string(), 88.0).unwrap();
+        manager.record_component_score(102, 1, "测验", 90.0).unwrap();
+
+        let audit = manager.degree_audit_as_of(1, 1, date("2024-12-01")).unwrap();
+        assert_eq!(audit.required[0].progress, CourseProgress::InProgress);
+        assert_eq!(audit.required[1].progress, CourseProgress::InProgress);
+        assert_eq!(audit.earned_credits, 0);
+
+        // 学期结束后课程完成；考核项都有分数的课程随时可以完成
+        manager.record_component_score(102, 1, "期末", 70.0).unwrap();
+        let audit = manager.degree_audit_as_of(1, 1, date("2025-02-01")).unwrap();
+        assert_eq!(audit.required[0].progress, CourseProgress::Completed("B".to_string()));
+        assert_eq!(audit.required[1].progress, CourseProgress::Completed("C".to_string()));
+        assert_eq!(audit.earned_credits, 8);
+    }
+}
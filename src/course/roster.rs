@@ -0,0 +1,148 @@
+//! 学生名单导入模块
+//!
+//! 从教务处提供的CSV名单（id, name, age, email）批量导入学生。
+//! 邮箱保存为学生的联系方式，与已有学生重复的行会被拒绝。
+//! 单行出错不会中断导入，所有被拒绝的行都会记录在导入报告中。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::contact::ContactInfo;
+use crate::utils::{csv, file_handler::read_file_to_string};
+use crate::Student;
+use std::path::Path;
+
+/// 名单CSV的表头
+const ROSTER_HEADER: [&str; 4] = ["id", "name", "age", "email"];
+
+/// 被拒绝的一行
+#[derive(Debug)]
+pub struct RejectedLine {
+    /// 行号，从1开始
+    pub line_number: usize,
+    pub content: String,
+    pub error: AppError,
+}
+
+/// 名单导入报告
+#[derive(Debug, Default)]
+pub struct RosterImportReport {
+    /// 成功导入的学生ID，按文件中的顺序排列
+    pub imported: Vec<u32>,
+    pub rejected: Vec<RejectedLine>,
+}
+
+impl RosterImportReport {
+    pub fn is_clean(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+impl EnrollmentManager {
+    /// 从CSV文件导入学生名单
+    ///
+    /// 只有文件本身无法读取时才返回错误，数据行的问题都记录在报告里。
+    pub fn import_roster_csv<P: AsRef<Path>>(&mut self, path: P) -> AppResult<RosterImportReport> {
+        self.run_operation("import_roster_csv", |manager| {
+            let content = read_file_to_string(path)?;
+            Ok(manager.import_roster_str(&content))
+        })
+    }
+
+    /// 从CSV文本导入学生名单，第一行可以是表头
+    pub fn import_roster_str(&mut self, content: &str) -> RosterImportReport {
+        self.run_operation("import_roster_str", |manager| {
+            let mut report = RosterImportReport::default();
+
+            for (index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() || (index == 0 && is_header(line)) {
+                    continue;
+                }
+
+                let result = parse_roster_line(line)
+                    .and_then(|student| {
+                        let id = student.id;
+                        manager.add_student(student)?;
+                        Ok(id)
+                    });
+
+                match result {
+                    Ok(id) => report.imported.push(id),
+                    Err(error) => report.rejected.push(RejectedLine {
+                        line_number: index + 1,
+                        content: line.to_string(),
+                        error,
+                    }),
+                }
+            }
+
+            report
+        })
+    }
+}
+
+fn is_header(line: &str) -> bool {
+    match csv::parse_line(line) {
+        Ok(fields) => fields.iter()
+            .map(|f| f.trim().to_lowercase())
+            .eq(ROSTER_HEADER.iter().map(|h| h.to_string())),
+        Err(_) => false,
+    }
+}
+
+/// 解析一行名单，经过 `Student::new` 和 `ContactInfo::new` 的全部验证
+fn parse_roster_line(line: &str) -> AppResult<Student> {
+    let fields = csv::parse_line(line)?;
+    if fields.len() != ROSTER_HEADER.len() {
+        return Err(AppError::InvalidInput(
+            format!("需要 {} 列，实际有 {} 列", ROSTER_HEADER.len(), fields.len())
+        ));
+    }
+
+    let id: u32 = fields[0].trim().parse()?;
+    let name = fields[1].trim().to_string();
+    let age: u8 = fields[2].trim().parse()?;
+    // 名单中的邮箱是必填的，格式由 `ContactInfo::new` 验证
+    if fields[3].trim().is_empty() {
+        return Err(AppError::ValidationError("邮箱不能为空".to_string()));
+    }
+    let contact = ContactInfo::new(Some(fields[3].clone()), None, None)?;
+
+    Ok(Student::new(id, name, age)?.with_contact(contact))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_reports_every_bad_line() {
+        let mut manager = EnrollmentManager::new();
+        let csv = "id,name,age,email\n\
+                   1,张三,20,zhangsan@example.com\n\
+                   abc,李四,20,lisi@example.com\n\
+                   3,王五,3,wangwu@example.com\n\
+                   \n\
+                   4,赵六,21,not-an-email\n\
+                   1,重复,22,dup@example.com\n\
+                   5,\"钱, 七\",22,qian@example.com\n\
+                   6,孙八,22,QIAN@example.com\n";
+
+        let report = manager.import_roster_str(csv);
+
+        assert_eq!(report.imported, vec![1, 5]);
+        let lines: Vec<usize> = report.rejected.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, vec![3, 4, 6, 7, 9]);
+        assert!(matches!(report.rejected[0].error, AppError::ParseError(_)));
+        assert!(matches!(report.rejected[1].error, AppError::ValidationError(_)));
+        assert_eq!(manager.get_student(5).unwrap().name, "钱, 七");
+        assert_eq!(manager.get_student(5).unwrap().email(), Some("qian@example.com"));
+    }
+
+    #[test]
+    fn test_import_without_header() {
+        let mut manager = EnrollmentManager::new();
+        let report = manager.import_roster_str("7,孙八,18,sun@example.com\n");
+        assert!(report.is_clean());
+        assert!(manager.get_student(7).is_some());
+    }
+}
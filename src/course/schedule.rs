@@ -0,0 +1,364 @@
+//! 课程时间表模块
+//!
+//! 课程可以有多个每周固定的上课时段。注册课程时检查与学生已注册课程的
+//! 时间冲突；给课程增加时段或改换学期时，同样检查课程已注册的学生。
+//! 可以生成学生某个学期的周课表。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::{TimeOfDay, Weekday};
+
+/// 每周固定的上课时段，时间区间左闭右开
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TimeSlotData"))]
+pub struct TimeSlot {
+    pub day: Weekday,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    pub room: String,
+}
+
+impl TimeSlot {
+    pub fn new(day: Weekday, start: TimeOfDay, end: TimeOfDay, room: &str) -> AppResult<Self> {
+        if start >= end {
+            return Err(AppError::ValidationError(
+                format!("上课时段的开始时间 {} 必须早于结束时间 {}", start, end)
+            ));
+        }
+        if room.trim().is_empty() {
+            return Err(AppError::ValidationError("教室不能为空".to_string()));
+        }
+        Ok(TimeSlot { day, start, end, room: room.trim().to_string() })
+    }
+
+    /// 两个时段是否在同一天且时间重叠；首尾相接不算重叠
+    pub fn overlaps(&self, other: &TimeSlot) -> bool {
+        self.day == other.day && self.start < other.end && other.start < self.end
+    }
+}
+
+/// 反序列化时的原始数据，经过 `TimeSlot::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TimeSlotData {
+    day: Weekday,
+    start: TimeOfDay,
+    end: TimeOfDay,
+    room: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TimeSlotData> for TimeSlot {
+    type Error = AppError;
+
+    fn try_from(data: TimeSlotData) -> AppResult<Self> {
+        TimeSlot::new(data.day, data.start, data.end, &data.room)
+    }
+}
+
+impl std::fmt::Display for TimeSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}-{} {}", self.day, self.start, self.end, self.room)
+    }
+}
+
+/// 与已注册课程的时间冲突
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleConflict {
+    pub course_id: u32,
+    pub course_name: String,
+    /// 要注册的课程的时段
+    pub slot: TimeSlot,
+    /// 已注册课程中与之重叠的时段
+    pub conflicting_slot: TimeSlot,
+}
+
+impl std::fmt::Display for ScheduleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} 与课程 {}({}) 的 {} 冲突",
+               self.slot, self.course_name, self.course_id, self.conflicting_slot)
+    }
+}
+
+/// 周课表中的一节课
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimetableEntry {
+    pub course_id: u32,
+    pub course_name: String,
+    pub slot: TimeSlot,
+}
+
+/// 学生的周课表，按星期和开始时间排序
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timetable {
+    pub student_id: u32,
+    pub student_name: String,
+    /// 课表所属的学期，`None` 表示只包括没有安排学期的课程
+    pub term_id: Option<u32>,
+    pub entries: Vec<TimetableEntry>,
+}
+
+impl Course {
+    pub fn time_slots(&self) -> &[TimeSlot] {
+        &self.time_slots
+    }
+
+    /// 增加上课时段，不能与本课程已有的时段重叠
+    pub fn add_time_slot(&mut self, slot: TimeSlot) -> AppResult<()> {
+        if let Some(existing) = self.time_slots.iter().find(|s| s.overlaps(&slot)) {
+            return Err(AppError::ValidationError(
+                format!("课程 {} 的时段 {} 与已有时段 {} 重叠", self.name, slot, existing)
+            ));
+        }
+        self.time_slots.push(slot);
+        Ok(())
+    }
+
+    pub fn clear_time_slots(&mut self) {
+        self.time_slots.clear();
+    }
+
+    /// 两门课程是否可能同时上课：学期不同的课程不会冲突
+    fn shares_term_with(&self, other: &Course) -> bool {
+        match (self.term_id, other.term_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+}
+
+impl EnrollmentManager {
+    /// 增加上课时段，新时段与已注册学生的其他课程冲突时拒绝
+    pub fn add_course_time_slot(&mut self, course_id: u32, slot: TimeSlot) -> AppResult<()> {
+        self.run_operation("add_course_time_slot", |manager| {
+            let mut changed = manager.require_course(course_id)?.clone();
+            changed.add_time_slot(slot.clone())?;
+            manager.check_roster_conflicts(&changed)?;
+            manager.update_course(course_id, |course| course.add_time_slot(slot))
+        })
+    }
+
+    /// 将课程安排到指定学期，在新学期与已注册学生的其他课程冲突时拒绝
+    pub fn assign_course_to_term(&mut self, course_id: u32, term_id: u32) -> AppResult<()> {
+        self.run_operation("assign_course_to_term", |manager| {
+            manager.require_term(term_id)?;
+            let mut changed = manager.require_course(course_id)?.clone();
+            changed.term_id = Some(term_id);
+            manager.check_roster_conflicts(&changed)?;
+            manager.update_course(course_id, |course| {
+                course.term_id = Some(term_id);
+                Ok(())
+            })
+        })
+    }
+
+    /// 课程与学生已注册课程之间的时间冲突
+    pub fn schedule_conflicts(&self, student_id: u32, course_id: u32) -> AppResult<Vec<ScheduleConflict>> {
+        let course = self.require_course(course_id)?;
+        Ok(self.conflicts_with_enrolled(student_id, course))
+    }
+
+    /// `course` 与学生注册的其他课程之间的时间冲突，`course` 可以是还没有写回的修改
+    fn conflicts_with_enrolled(&self, student_id: u32, course: &Course) -> Vec<ScheduleConflict> {
+        let course_id = course.id;
+        let mut enrolled: Vec<&Course> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.id != course_id && c.is_enrolled(student_id) && c.shares_term_with(course))
+            .collect();
+        enrolled.sort_by_key(|c| c.id);
+
+        let mut conflicts = Vec::new();
+        for other in enrolled {
+            for slot in &course.time_slots {
+                for other_slot in other.time_slots.iter().filter(|s| s.overlaps(slot)) {
+                    conflicts.push(ScheduleConflict {
+                        course_id: other.id,
+                        course_name: other.name.clone(),
+                        slot: slot.clone(),
+                        conflicting_slot: other_slot.clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// 学生注册课程时存在时间冲突则返回错误
+    pub(crate) fn check_schedule_conflicts(&self, student_id: u32, course_id: u32) -> AppResult<()> {
+        let conflicts = self.schedule_conflicts(student_id, course_id)?;
+        conflict_error(student_id, &conflicts)
+    }
+
+    /// 检查修改后的课程与每个已注册学生的其他课程都不冲突
+    fn check_roster_conflicts(&self, course: &Course) -> AppResult<()> {
+        for student_id in course.student_ids() {
+            conflict_error(student_id, &self.conflicts_with_enrolled(student_id, course))?;
+        }
+        Ok(())
+    }
+
+    /// 学生在某个学期的周课表
+    ///
+    /// 包括学生注册的、安排在 `term_id` 学期的课程，以及没有安排学期的课程；
+    /// `term_id` 为 `None` 时只包括没有安排学期的课程。
+    pub fn timetable(&self, student_id: u32, term_id: Option<u32>) -> AppResult<Timetable> {
+        let student = self.get_student(student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不存在", student_id)
+            ))?;
+        if let Some(term_id) = term_id {
+            self.require_term(term_id)?;
+        }
+
+        let mut entries: Vec<TimetableEntry> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.is_enrolled(student_id) && (c.term_id.is_none() || c.term_id == term_id))
+            .flat_map(|course| course.time_slots.iter().map(|slot| TimetableEntry {
+                course_id: course.id,
+                course_name: course.name.clone(),
+                slot: slot.clone(),
+            }))
+            .collect();
+        entries.sort_by_key(|e| (e.slot.day, e.slot.start, e.course_id));
+
+        Ok(Timetable {
+            student_id,
+            student_name: student.name.clone(),
+            term_id,
+            entries,
+        })
+    }
+}
+
+/// 存在时间冲突时返回说明所有冲突的错误
+fn conflict_error(student_id: u32, conflicts: &[ScheduleConflict]) -> AppResult<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+    Err(AppError::ValidationError(
+        format!("学生ID {} 的课程时间冲突: {}", student_id, details.join("；"))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::term::Term;
+    use crate::course::test_support::manager_with;
+
+    fn slot(day: Weekday, start: &str, end: &str, room: &str) -> TimeSlot {
+        TimeSlot::new(day, start.parse().unwrap(), end.parse().unwrap(), room).unwrap()
+    }
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三")], &[(101, "Rust编程", 3), (102, "数据库", 3), (103, "体育", 3)]);
+        manager.add_course_time_slot(101, slot(Weekday::Monday, "08:00", "09:40", "A101")).unwrap();
+        manager.add_course_time_slot(101, slot(Weekday::Wednesday, "10:00", "11:40", "A101")).unwrap();
+        manager.add_course_time_slot(102, slot(Weekday::Monday, "09:00", "10:40", "B202")).unwrap();
+        manager.add_course_time_slot(103, slot(Weekday::Monday, "09:40", "11:00", "操场")).unwrap();
+        manager
+    }
+
+    fn add_terms(manager: &mut EnrollmentManager) {
+        for (id, start, end) in [(1, "2024-09-01", "2025-01-15"), (2, "2025-02-20", "2025-07-01")] {
+            let term = Term::new(id, format!("学期{}", id), start.parse().unwrap(), end.parse().unwrap()).unwrap();
+            manager.add_term(term).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_slot_validation_and_overlap() {
+        let monday = slot(Weekday::Monday, "08:00", "09:40", "A101");
+        assert!(monday.overlaps(&slot(Weekday::Monday, "09:00", "10:00", "B")));
+        assert!(!monday.overlaps(&slot(Weekday::Monday, "09:40", "10:00", "B")));
+        assert!(!monday.overlaps(&slot(Weekday::Tuesday, "08:00", "09:40", "A101")));
+        assert!(TimeSlot::new(Weekday::Monday, "10:00".parse().unwrap(), "09:00".parse().unwrap(), "A").is_err());
+        assert!(TimeSlot::new(Weekday::Monday, "08:00".parse().unwrap(), "09:00".parse().unwrap(), " ").is_err());
+
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.add_time_slot(monday.clone()).unwrap();
+        assert!(course.add_time_slot(monday).is_err());
+    }
+
+    #[test]
+    fn test_enrollment_rejects_conflicts() {
+        let mut manager = manager();
+        manager.enroll_student_in_course(1, 101).unwrap();
+
+        let error = manager.enroll_student_in_course(1, 102).unwrap_err().to_string();
+        assert!(error.contains("周一 09:00-10:40 B202 与课程 Rust编程(101) 的 周一 08:00-09:40 A101 冲突"), "{}", error);
+        assert!(!manager.get_course(102).unwrap().is_enrolled(1));
+
+        // 首尾相接不算冲突
+        manager.enroll_student_in_course(1, 103).unwrap();
+    }
+
+    #[test]
+    fn test_courses_in_different_terms_do_not_conflict() {
+        let mut manager = manager();
+        add_terms(&mut manager);
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        assert!(manager.schedule_conflicts(1, 102).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_timetable_is_sorted() {
+        let mut manager = manager();
+        manager.enroll_student_in_course(1, 103).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+
+        let timetable = manager.timetable(1, None).unwrap();
+        let order: Vec<u32> = timetable.entries.iter().map(|e| e.course_id).collect();
+        assert_eq!(order, vec![101, 103, 101]);
+        assert!(manager.timetable(9, None).is_err());
+        assert!(manager.timetable(1, Some(9)).is_err());
+    }
+
+    #[test]
+    fn test_timetable_is_per_term() {
+        let mut manager = manager();
+        add_terms(&mut manager);
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.assign_course_to_term(103, 1).unwrap();
+        manager.add_course(Course::new(104, "班会".to_string(), 1).unwrap()).unwrap();
+        manager.add_course_time_slot(104, slot(Weekday::Thursday, "16:00", "17:00", "C301")).unwrap();
+        for course_id in [101, 102, 103, 104] {
+            manager.enroll_student_in_course(1, course_id).unwrap();
+        }
+
+        let courses = |term_id| -> Vec<u32> {
+            manager.timetable(1, term_id).unwrap().entries.iter().map(|e| e.course_id).collect()
+        };
+        assert_eq!(courses(Some(1)), vec![101, 103, 101, 104]);
+        assert_eq!(courses(Some(2)), vec![102, 104]);
+        assert_eq!(courses(None), vec![104]);
+    }
+
+    #[test]
+    fn test_schedule_changes_recheck_enrolled_students() {
+        let mut manager = manager();
+        add_terms(&mut manager);
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+
+        // 102 改到与 101 同一学期后时间冲突
+        let error = manager.assign_course_to_term(102, 1).unwrap_err().to_string();
+        assert!(error.contains("学生ID 1 的课程时间冲突"), "{}", error);
+        assert_eq!(manager.get_course(102).unwrap().term_id, Some(2));
+
+        manager.add_course_time_slot(101, slot(Weekday::Friday, "08:00", "09:00", "A101")).unwrap();
+        manager.assign_course_to_term(103, 1).unwrap();
+        manager.enroll_student_in_course(1, 103).unwrap();
+        let error = manager.add_course_time_slot(103, slot(Weekday::Friday, "08:30", "09:30", "操场")).unwrap_err();
+        assert!(error.to_string().contains("与课程 Rust编程(101) 的 周五 08:00-09:00 A101 冲突"), "{}", error);
+        assert_eq!(manager.get_course(103).unwrap().time_slots().len(), 1);
+    }
+}
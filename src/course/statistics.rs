@@ -1,16 +1,35 @@
 //! 课程统计模块
+//!
+//! 平均分、最高分、最低分和等级分布只统计分数成绩；不以分数记录的课程结果
+//! 单独计数。及格率的分母是有分数或有通过/不通过/免修结果的学生，免修算作及格；
+//! 未完成和旁听的学生不参与及格率。考勤数据来自 `attendance` 模块，
+//! 与成绩分开统计：还没有成绩的课程也有出勤率。
 
-use crate::Course;
+use super::attendance::DEFAULT_ATTENDANCE_THRESHOLD;
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::grade::{GradeMark, LetterGrade};
+use crate::student::grading_scale::{standard_scale, GradingScale};
+use crate::{Course, Student};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CourseStatistics {
-    pub average_grade: f64,
-    pub highest_grade: f64,
-    pub lowest_grade: f64,
-    pub passing_rate: f64,
+    /// 没有分数成绩时为 `None`
+    pub average_grade: Option<f64>,
+    pub highest_grade: Option<f64>,
+    pub lowest_grade: Option<f64>,
+    /// 百分比；没有学生有分数或通过/不通过/免修结果时为 `None`
+    pub passing_rate: Option<f64>,
     pub grade_distribution: GradeDistribution,
+    pub mark_distribution: MarkDistribution,
+    /// 全体注册学生的出勤率（百分比），还没有考勤记录时为 `None`
+    pub attendance_rate: Option<f64>,
+    /// 出勤率低于警戒线的学生ID，按从小到大排序
+    pub low_attendance_students: Vec<u32>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GradeDistribution {
     pub a_count: usize,
     pub b_count: usize,
@@ -19,40 +38,109 @@ pub struct GradeDistribution {
     pub f_count: usize,
 }
 
-impl Course {
-    pub fn calculate_statistics(&self) -> Option<CourseStatistics> {
-        if self.students.is_empty() {
-            return None;
+/// 不以分数记录的课程结果的人数
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarkDistribution {
+    pub pass_count: usize,
+    pub fail_count: usize,
+    pub incomplete_count: usize,
+    pub audit_count: usize,
+    pub exempt_count: usize,
+}
+
+impl MarkDistribution {
+    fn count(&mut self, mark: GradeMark) {
+        match mark {
+            GradeMark::Pass => self.pass_count += 1,
+            GradeMark::Fail => self.fail_count += 1,
+            GradeMark::Incomplete => self.incomplete_count += 1,
+            GradeMark::Audit => self.audit_count += 1,
+            GradeMark::Exempt => self.exempt_count += 1,
         }
-        
-        let averages: Vec<f64> = self.students.values()
-            .map(|s| s.average_grade())
+    }
+
+    pub fn total(&self) -> usize {
+        self.pass_count + self.fail_count + self.incomplete_count + self.audit_count + self.exempt_count
+    }
+}
+
+impl Course {
+    /// 按标准等级制和默认出勤率警戒线 `DEFAULT_ATTENDANCE_THRESHOLD` 计算课程统计
+    ///
+    /// 课程只保存学生ID，学生数据由调用方提供；`students` 中没有注册本课程的
+    /// 学生会被忽略，因此可以直接传入管理器中的全部学生。
+    pub fn calculate_statistics(&self, students: &[&Student]) -> CourseStatistics {
+        self.calculate_statistics_with_scale(students, standard_scale(), DEFAULT_ATTENDANCE_THRESHOLD)
+    }
+    
+    /// 按指定等级制计算课程统计，及格率和成绩分布都以该等级制为准，
+    /// 出勤率低于 `attendance_threshold`（百分比）的学生会被标记出来
+    ///
+    /// 每个学生取 `Course::student_score` 或 `Course::student_mark`，
+    /// 还没有课程成绩的学生不计入。
+    pub fn calculate_statistics_with_scale(
+        &self,
+        students: &[&Student],
+        scale: &GradingScale,
+        attendance_threshold: f64,
+    ) -> CourseStatistics {
+        let averages: Vec<f64> = students.iter()
+            .filter_map(|s| self.student_score(s))
             .collect();
-        
-        if averages.is_empty() {
-            return None;
+        let mut mark_distribution = MarkDistribution::default();
+        for mark in students.iter().filter_map(|s| self.student_mark(s)) {
+            mark_distribution.count(mark);
         }
         
-        let total_average = averages.iter().sum::<f64>() / averages.len() as f64;
-        let highest = averages.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        let lowest = averages.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let (total_average, highest, lowest) = if averages.is_empty() {
+            (None, None, None)
+        } else {
+            (
+                Some(averages.iter().sum::<f64>() / averages.len() as f64),
+                Some(averages.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b))),
+                Some(averages.iter().fold(f64::INFINITY, |a, &b| a.min(b))),
+            )
+        };
         
-        let passing_count = averages.iter().filter(|&&avg| avg >= 60.0).count();
-        let passing_rate = (passing_count as f64 / averages.len() as f64) * 100.0;
+        let passing_count = averages.iter().filter(|&&avg| scale.is_passing(avg)).count()
+            + mark_distribution.pass_count
+            + mark_distribution.exempt_count;
+        let rated_count = averages.len()
+            + mark_distribution.pass_count
+            + mark_distribution.fail_count
+            + mark_distribution.exempt_count;
+        let passing_rate = (rated_count > 0)
+            .then(|| (passing_count as f64 / rated_count as f64) * 100.0);
         
-        let grade_distribution = calculate_grade_distribution(&averages);
+        let grade_distribution = calculate_grade_distribution(&averages, scale);
         
-        Some(CourseStatistics {
+        CourseStatistics {
             average_grade: total_average,
             highest_grade: highest,
             lowest_grade: lowest,
             passing_rate,
             grade_distribution,
-        })
+            mark_distribution,
+            attendance_rate: self.attendance_summary().rate(),
+            low_attendance_students: self.students_below_attendance(attendance_threshold),
+        }
+    }
+}
+
+impl EnrollmentManager {
+    /// 按管理器当前的等级制和出勤率警戒线计算课程统计
+    pub fn course_statistics(&self, course_id: u32) -> AppResult<CourseStatistics> {
+        let course = self.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("课程ID {} 不存在", course_id)
+            ))?;
+        let students = self.list_course_students(course_id)?;
+        Ok(course.calculate_statistics_with_scale(&students, self.grading_scale(), self.attendance_threshold()))
     }
 }
 
-fn calculate_grade_distribution(averages: &[f64]) -> GradeDistribution {
+fn calculate_grade_distribution(averages: &[f64], scale: &GradingScale) -> GradeDistribution {
     let mut distribution = GradeDistribution {
         a_count: 0,
         b_count: 0,
@@ -62,11 +150,12 @@ fn calculate_grade_distribution(averages: &[f64]) -> GradeDistribution {
     };
     
     for &avg in averages {
-        match avg {
-            90.0..=100.0 => distribution.a_count += 1,
-            80.0..=89.9 => distribution.b_count += 1,
-            70.0..=79.9 => distribution.c_count += 1,
-            60.0..=69.9 => distribution.d_count += 1,
+        // 带加减号的等级按基本等级计数
+        match scale.letter_for(avg).base() {
+            LetterGrade::A => distribution.a_count += 1,
+            LetterGrade::B => distribution.b_count += 1,
+            LetterGrade::C => distribution.c_count += 1,
+            LetterGrade::D => distribution.d_count += 1,
             _ => distribution.f_count += 1,
         }
     }
@@ -76,16 +165,194 @@ fn calculate_grade_distribution(averages: &[f64]) -> GradeDistribution {
 
 impl std::fmt::Display for CourseStatistics {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let value = |v: Option<f64>| v.map_or("--".to_string(), |v| format!("{:.1}", v));
         writeln!(f, "课程统计:")?;
-        writeln!(f, "  平均分: {:.1}", self.average_grade)?;
-        writeln!(f, "  最高分: {:.1}", self.highest_grade)?;
-        writeln!(f, "  最低分: {:.1}", self.lowest_grade)?;
-        writeln!(f, "  及格率: {:.1}%", self.passing_rate)?;
+        writeln!(f, "  平均分: {}", value(self.average_grade))?;
+        writeln!(f, "  最高分: {}", value(self.highest_grade))?;
+        writeln!(f, "  最低分: {}", value(self.lowest_grade))?;
+        match self.passing_rate {
+            Some(rate) => writeln!(f, "  及格率: {:.1}%", rate)?,
+            None => writeln!(f, "  及格率: --")?,
+        }
         writeln!(f, "  成绩分布:")?;
         writeln!(f, "    A: {} 人", self.grade_distribution.a_count)?;
         writeln!(f, "    B: {} 人", self.grade_distribution.b_count)?;
         writeln!(f, "    C: {} 人", self.grade_distribution.c_count)?;
         writeln!(f, "    D: {} 人", self.grade_distribution.d_count)?;
-        write!(f, "    F: {} 人", self.grade_distribution.f_count)
+        write!(f, "    F: {} 人", self.grade_distribution.f_count)?;
+        let marks = &self.mark_distribution;
+        if marks.total() > 0 {
+            write!(
+                f,
+                "\n  非分数成绩: 通过 {} 人, 不通过 {} 人, 未完成 {} 人, 旁听 {} 人, 免修 {} 人",
+                marks.pass_count, marks.fail_count, marks.incomplete_count, marks.audit_count, marks.exempt_count
+            )?;
+        }
+        if let Some(rate) = self.attendance_rate {
+            write!(f, "\n  出勤率: {:.1}% (偏低 {} 人)", rate, self.low_attendance_students.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::student::grade::Grade;
+    use crate::utils::math::score_to_gpa;
+    
+    fn distribution_letter(score: f64) -> LetterGrade {
+        let d = calculate_grade_distribution(&[score], standard_scale());
+        let counts = [d.a_count, d.b_count, d.c_count, d.d_count, d.f_count];
+        assert_eq!(counts.iter().sum::<usize>(), 1, "分数 {} 必须恰好落在一档", score);
+        let letters = [LetterGrade::A, LetterGrade::B, LetterGrade::C, LetterGrade::D, LetterGrade::F];
+        letters[counts.iter().position(|&c| c == 1).unwrap()].clone()
+    }
+    
+    #[test]
+    fn test_statistics_see_grades_added_after_enrollment() {
+        let mut manager = EnrollmentManager::new();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.average_grade, None);
+        assert_eq!(stats.passing_rate, None);
+
+        manager.add_course_grade(1, 101, "期末".to_string(), 95.0).unwrap();
+        manager.add_course_grade(2, 101, "期末".to_string(), 55.0).unwrap();
+        // 不属于课程的成绩不影响课程统计
+        manager.add_grade(2, "音乐".to_string(), 100.0).unwrap();
+
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.average_grade, Some(75.0));
+        assert_eq!(stats.passing_rate, Some(50.0));
+        assert_eq!(stats.grade_distribution.f_count, 1);
+        assert_eq!(manager.get_student(1).unwrap().grades_in_course(101).len(), 1);
+        assert_eq!(stats.attendance_rate, None);
+        assert!(manager.course_statistics(999).is_err());
+    }
+    
+    #[test]
+    fn test_course_sees_current_student_data() {
+        let mut manager = EnrollmentManager::new();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+
+        let mut student = manager.get_student_mut(1).unwrap();
+        student.add_grade("数学".to_string(), 88.0).unwrap();
+        student.save().unwrap();
+        // 没有保存的修改不生效
+        let mut student = manager.get_student_mut(1).unwrap();
+        student.name = "改名".to_string();
+        drop(student);
+
+        let course = manager.get_course(101).unwrap();
+        let students = course.list_students(&manager);
+        assert_eq!(students.len(), 1);
+        assert_eq!(students[0].name, "张三");
+        assert_eq!(students[0].average_grade(), 88.0);
+        assert!(course.get_student(&manager, 2).is_none());
+
+        // 学生和课程的ID不能在修改中改变
+        assert!(manager.update_student(1, |s| { s.id = 2; Ok(()) }).is_err());
+        let mut student = manager.get_student_mut(2).unwrap();
+        student.id = 1;
+        assert!(student.save().is_err());
+        assert!(manager.update_course(101, |c| { c.id = 102; Ok(()) }).is_err());
+        assert!(manager.get_course(102).is_none());
+        assert_eq!(manager.get_student(2).unwrap().name, "李四");
+    }
+    
+    #[test]
+    fn test_attendance_in_statistics() {
+        use crate::course::attendance::AttendanceStatus;
+        
+        let mut manager = EnrollmentManager::new();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        for (day, second) in [("2024-09-02", AttendanceStatus::Present), ("2024-09-09", AttendanceStatus::Absent)] {
+            let date = day.parse().unwrap();
+            manager.record_attendance(101, date, 1, AttendanceStatus::Present).unwrap();
+            manager.record_attendance(101, date, 2, second).unwrap();
+        }
+        
+        // 还没有成绩时也统计出勤
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.average_grade, None);
+        assert_eq!(stats.attendance_rate, Some(75.0));
+        assert_eq!(stats.low_attendance_students, vec![2]);
+        assert!(stats.to_string().ends_with("出勤率: 75.0% (偏低 1 人)"));
+        
+        manager.set_attendance_threshold(50.0).unwrap();
+        assert!(manager.course_statistics(101).unwrap().low_attendance_students.is_empty());
+        let course = manager.get_course(101).unwrap();
+        let students = course.list_students(&manager);
+        assert_eq!(course.calculate_statistics(&students).low_attendance_students, vec![2]);
+        assert!(course.calculate_statistics_with_scale(&students, standard_scale(), 50.0).low_attendance_students.is_empty());
+    }
+    
+    #[test]
+    fn test_marks_in_statistics() {
+        let mut manager = EnrollmentManager::new();
+        for (id, name) in [(1, "张三"), (2, "李四"), (3, "王五")] {
+            manager.add_student(Student::new(id, name.to_string(), 20).unwrap()).unwrap();
+        }
+        manager.add_course(Course::new(101, "研讨课".to_string(), 1).unwrap()).unwrap();
+        for id in 1..=3 {
+            manager.enroll_student_in_course(id, 101).unwrap();
+        }
+        manager.add_course_mark(1, 101, "总评".to_string(), GradeMark::Pass).unwrap();
+        manager.add_course_mark(2, 101, "总评".to_string(), GradeMark::Fail).unwrap();
+        manager.add_course_mark(3, 101, "总评".to_string(), GradeMark::Audit).unwrap();
+
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.average_grade, None);
+        assert_eq!(stats.passing_rate, Some(50.0));
+        assert_eq!(stats.mark_distribution.total(), 3);
+        assert_eq!(stats.grade_distribution.f_count, 0);
+        assert!(stats.to_string().contains("平均分: --"));
+        assert!(stats.to_string().ends_with("通过 1 人, 不通过 1 人, 未完成 0 人, 旁听 1 人, 免修 0 人"));
+
+        // 最近一条成绩决定课程结果，分数成绩和通过/不通过一起计算及格率
+        manager.add_course_grade(3, 101, "报告".to_string(), 50.0).unwrap();
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.average_grade, Some(50.0));
+        assert!((stats.passing_rate.unwrap() - 100.0 / 3.0).abs() < 1e-9);
+
+        // 免修算作及格，也计入及格率的分母
+        manager.add_course_mark(3, 101, "总评".to_string(), GradeMark::Exempt).unwrap();
+        let stats = manager.course_statistics(101).unwrap();
+        assert_eq!(stats.mark_distribution.exempt_count, 1);
+        assert_eq!(stats.average_grade, None);
+        assert!((stats.passing_rate.unwrap() - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grade_distribution_boundaries() {
+        assert_eq!(distribution_letter(89.95), LetterGrade::B);
+        assert_eq!(distribution_letter(90.0), LetterGrade::A);
+        assert_eq!(distribution_letter(69.99), LetterGrade::D);
+        assert_eq!(distribution_letter(59.95), LetterGrade::F);
+    }
+    
+    /// 以0.005为步长扫描0-100分，三种映射必须给出一致的结果
+    #[test]
+    fn test_every_score_maps_to_exactly_one_band() {
+        for step in 0..=20_000 {
+            let score = step as f64 * 0.005;
+            let grade = Grade::new("数学".to_string(), score).unwrap();
+            let letter = distribution_letter(score);
+            
+            assert_eq!(grade.letter_grade(), Some(&letter), "分数 {}", score);
+            assert_eq!(score_to_gpa(score), letter.to_gpa(), "分数 {}", score);
+        }
     }
 }
\ No newline at end of file
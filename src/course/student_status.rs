@@ -0,0 +1,126 @@
+//! 学籍状态管理模块
+//!
+//! 学生离开在读状态时会从所有候补名单中移除，避免之后被自动补进课程；
+//! 已经注册的课程保留并在变更结果中列出，需要时通过退课或退出课程处理。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::AppResult;
+use crate::student::status::StudentStatus;
+use crate::utils::date::Date;
+use crate::Student;
+
+/// 学生离开在读状态对课程的影响，课程ID按从小到大排序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusChangeEffects {
+    /// 学生被移出候补名单的课程
+    pub left_waitlists: Vec<u32>,
+    /// 学生仍然注册着的课程，需要时通过退课或退出课程处理
+    pub still_enrolled: Vec<u32>,
+}
+
+impl EnrollmentManager {
+    /// 变更学生的学籍状态
+    ///
+    /// 学生离开在读状态时返回被移出的候补名单和仍然注册着的课程；变为在读时两者都为空。
+    pub fn change_student_status(
+        &mut self,
+        student_id: u32,
+        status: StudentStatus,
+        date: Date,
+    ) -> AppResult<StatusChangeEffects> {
+        self.run_operation("change_student_status", |manager| {
+            manager.update_student(student_id, |student| student.change_status(status, date))?;
+            if status.can_enroll() {
+                return Ok(StatusChangeEffects::default());
+            }
+
+            let mut effects = StatusChangeEffects::default();
+            for course in manager.list_all_courses() {
+                if course.waitlist_position(student_id).is_some() {
+                    effects.left_waitlists.push(course.id);
+                } else if course.is_enrolled(student_id) {
+                    effects.still_enrolled.push(course.id);
+                }
+            }
+            effects.left_waitlists.sort_unstable();
+            effects.still_enrolled.sort_unstable();
+            for &course_id in &effects.left_waitlists {
+                manager.leave_waitlist(student_id, course_id)?;
+            }
+            Ok(effects)
+        })
+    }
+
+    /// 处于指定学籍状态的学生，按ID排序
+    pub fn list_students_with_status(&self, status: StudentStatus) -> Vec<&Student> {
+        let mut students: Vec<&Student> = self.list_all_students()
+            .into_iter()
+            .filter(|s| s.status() == status)
+            .collect();
+        students.sort_by_key(|s| s.id);
+        students
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::test_support::{date, manager_with};
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四"), (3, "王五")], &[(101, "Rust编程", 3), (102, "数据库", 2)]);
+        manager.set_course_capacity(101, Some(1)).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_inactive_students_cannot_enroll() {
+        let mut manager = manager();
+        manager.change_student_status(3, StudentStatus::Suspended, date("2024-10-01")).unwrap();
+        assert!(manager.enroll_student_in_course(3, 102).is_err());
+        assert!(manager.get_course(102).unwrap().student_ids().is_empty());
+
+        manager.change_student_status(3, StudentStatus::Active, date("2024-11-01")).unwrap();
+        manager.enroll_student_in_course(3, 102).unwrap();
+
+        manager.change_student_status(3, StudentStatus::Graduated, date("2025-06-30")).unwrap();
+        assert!(manager.change_student_status(3, StudentStatus::Active, date("2025-07-01")).is_err());
+        assert_eq!(manager.get_student(3).unwrap().status_history().len(), 3);
+        assert!(manager.change_student_status(9, StudentStatus::OnLeave, date("2025-07-01")).is_err());
+    }
+
+    #[test]
+    fn test_leaving_active_clears_waitlists() {
+        let mut manager = manager();
+        let effects = manager.change_student_status(2, StudentStatus::OnLeave, date("2024-10-01")).unwrap();
+        assert_eq!(effects.left_waitlists, vec![101]);
+        assert!(effects.still_enrolled.is_empty());
+        // 张三退课后不会自动补上休学的李四
+        assert_eq!(manager.remove_student_from_course(1, 101).unwrap(), Vec::<u32>::new());
+        assert!(manager.get_course(101).unwrap().waitlist().is_empty());
+    }
+
+    #[test]
+    fn test_leaving_active_reports_enrolled_courses() {
+        let mut manager = manager();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        let effects = manager.change_student_status(1, StudentStatus::Graduated, date("2025-06-30")).unwrap();
+        assert_eq!(effects.still_enrolled, vec![101, 102]);
+        assert!(effects.left_waitlists.is_empty());
+        assert!(manager.get_course(101).unwrap().is_enrolled(1));
+    }
+
+    #[test]
+    fn test_list_students_with_status() {
+        let mut manager = manager();
+        manager.change_student_status(1, StudentStatus::DroppedOut, date("2024-10-01")).unwrap();
+        manager.change_student_status(3, StudentStatus::DroppedOut, date("2024-10-02")).unwrap();
+
+        let ids = |students: Vec<&Student>| students.iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids(manager.list_students_with_status(StudentStatus::DroppedOut)), vec![1, 3]);
+        assert_eq!(ids(manager.list_students_with_status(StudentStatus::Active)), vec![2]);
+        assert!(manager.list_students_with_status(StudentStatus::Graduated).is_empty());
+    }
+}
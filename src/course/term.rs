@@ -0,0 +1,102 @@
+//! 学期模块
+
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+
+/// 学期，课程和成绩可以归属到某个学期
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TermData"))]
+pub struct Term {
+    pub id: u32,
+    pub name: String,
+    pub start_date: Date,
+    pub end_date: Date,
+    /// 退补选截止日期（含），此前退课不留记录；`None` 表示不限
+    pub add_drop_deadline: Option<Date>,
+    /// 退出课程截止日期（含），退补选截止后到此日期前退出课程记为W；`None` 表示不限
+    pub withdrawal_deadline: Option<Date>,
+}
+
+impl Term {
+    pub fn new(id: u32, name: String, start_date: Date, end_date: Date) -> AppResult<Self> {
+        if name.trim().is_empty() {
+            return Err(AppError::ValidationError("学期名称不能为空".to_string()));
+        }
+        if start_date >= end_date {
+            return Err(AppError::ValidationError(
+                format!("学期 {} 的开始日期 {} 必须早于结束日期 {}", name, start_date, end_date)
+            ));
+        }
+        Ok(Term {
+            id,
+            name,
+            start_date,
+            end_date,
+            add_drop_deadline: None,
+            withdrawal_deadline: None,
+        })
+    }
+
+    pub fn contains(&self, date: Date) -> bool {
+        self.start_date <= date && date <= self.end_date
+    }
+
+    /// 设置退补选和退出课程的截止日期
+    ///
+    /// 截止日期必须在学期之内，退补选截止不能晚于退出课程截止。
+    pub fn set_deadlines(
+        &mut self,
+        add_drop_deadline: Option<Date>,
+        withdrawal_deadline: Option<Date>,
+    ) -> AppResult<()> {
+        for deadline in add_drop_deadline.iter().chain(withdrawal_deadline.iter()) {
+            if !self.contains(*deadline) {
+                return Err(AppError::ValidationError(
+                    format!("截止日期 {} 不在学期 {} 之内", deadline, self.name)
+                ));
+            }
+        }
+        if let (Some(add_drop), Some(withdrawal)) = (add_drop_deadline, withdrawal_deadline) {
+            if add_drop > withdrawal {
+                return Err(AppError::ValidationError(
+                    format!("退补选截止日期 {} 不能晚于退出课程截止日期 {}", add_drop, withdrawal)
+                ));
+            }
+        }
+        self.add_drop_deadline = add_drop_deadline;
+        self.withdrawal_deadline = withdrawal_deadline;
+        Ok(())
+    }
+}
+
+/// 反序列化时的原始数据，经过 `Term::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TermData {
+    id: u32,
+    name: String,
+    start_date: Date,
+    end_date: Date,
+    #[serde(default)]
+    add_drop_deadline: Option<Date>,
+    #[serde(default)]
+    withdrawal_deadline: Option<Date>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TermData> for Term {
+    type Error = AppError;
+
+    fn try_from(data: TermData) -> AppResult<Self> {
+        let mut term = Term::new(data.id, data.name, data.start_date, data.end_date)?;
+        term.set_deadlines(data.add_drop_deadline, data.withdrawal_deadline)?;
+        Ok(term)
+    }
+}
+
+impl std::fmt::Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "学期[{}]: {} ({} 至 {})", self.id, self.name, self.start_date, self.end_date)
+    }
+}
@@ -0,0 +1,119 @@
+//! 各功能模块测试共用的辅助函数
+
+use super::enrollment::EnrollmentManager;
+use super::instructor::Instructor;
+use super::programme::Programme;
+use super::term::Term;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::storage::{MemoryStorage, Storage};
+use crate::student::grading_scale::GradingScale;
+use crate::student::Student;
+use crate::utils::date::Date;
+use std::cell::Cell;
+use std::rc::Rc;
+
+pub(crate) fn date(text: &str) -> Date {
+    text.parse().unwrap()
+}
+
+/// 包含指定学生（ID，姓名）和课程（ID，名称，学分）的管理器，学生都是20岁
+pub(crate) fn manager_with(students: &[(u32, &str)], courses: &[(u32, &str, u8)]) -> EnrollmentManager {
+    let mut manager = EnrollmentManager::new();
+    for &(id, name) in students {
+        manager.add_student(Student::new(id, name.to_string(), 20).unwrap()).unwrap();
+    }
+    for &(id, name, credits) in courses {
+        manager.add_course(Course::new(id, name.to_string(), credits).unwrap()).unwrap();
+    }
+    manager
+}
+
+/// 可以让学生写入失败的内存存储，用于测试多次写入中途失败的情况
+#[derive(Debug, Default)]
+pub(crate) struct FailingStorage {
+    inner: MemoryStorage,
+    /// 为 `true` 时保存学生返回存储错误，管理器持有存储后仍可以通过它切换
+    pub fail_student_writes: Rc<Cell<bool>>,
+}
+
+impl Storage for FailingStorage {
+    fn get_student(&self, id: u32) -> Option<&Student> {
+        self.inner.get_student(id)
+    }
+
+    fn list_students(&self) -> Vec<&Student> {
+        self.inner.list_students()
+    }
+
+    fn save_student(&mut self, student: Student) -> AppResult<()> {
+        if self.fail_student_writes.get() {
+            return Err(AppError::StorageError("写入学生失败".to_string()));
+        }
+        self.inner.save_student(student)
+    }
+
+    fn get_course(&self, id: u32) -> Option<&Course> {
+        self.inner.get_course(id)
+    }
+
+    fn list_courses(&self) -> Vec<&Course> {
+        self.inner.list_courses()
+    }
+
+    fn save_course(&mut self, course: Course) -> AppResult<()> {
+        self.inner.save_course(course)
+    }
+
+    fn get_term(&self, id: u32) -> Option<&Term> {
+        self.inner.get_term(id)
+    }
+
+    fn list_terms(&self) -> Vec<&Term> {
+        self.inner.list_terms()
+    }
+
+    fn save_term(&mut self, term: Term) -> AppResult<()> {
+        self.inner.save_term(term)
+    }
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor> {
+        self.inner.get_instructor(id)
+    }
+
+    fn list_instructors(&self) -> Vec<&Instructor> {
+        self.inner.list_instructors()
+    }
+
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        self.inner.save_instructor(instructor)
+    }
+
+    fn get_programme(&self, id: u32) -> Option<&Programme> {
+        self.inner.get_programme(id)
+    }
+
+    fn list_programmes(&self) -> Vec<&Programme> {
+        self.inner.list_programmes()
+    }
+
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()> {
+        self.inner.save_programme(programme)
+    }
+
+    fn grading_scale(&self) -> &GradingScale {
+        self.inner.grading_scale()
+    }
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        self.inner.save_grading_scale(scale)
+    }
+
+    fn attendance_threshold(&self) -> f64 {
+        self.inner.attendance_threshold()
+    }
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        self.inner.save_attendance_threshold(threshold)
+    }
+}
@@ -0,0 +1,355 @@
+//! 成绩单模块
+//!
+//! 根据学生注册的课程生成成绩单，并按课程学分计算加权绩点。
+//! 课程安排了学期时，还可以按学期分组生成学期成绩单。
+
+use super::enrollment::EnrollmentManager;
+use crate::errors::{AppError, AppResult};
+use crate::student::grade::{Grade, GradeMark, LetterGrade};
+
+/// 按学分加权的绩点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightedGpa {
+    pub gpa: f64,
+    /// 计入绩点的学分总数（只统计已有分数成绩的课程）
+    pub total_credits: u32,
+}
+
+/// 成绩单中的一门课程
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptEntry {
+    pub course_id: u32,
+    pub course_name: String,
+    pub credits: u8,
+    pub term_id: Option<u32>,
+    /// 课程成绩，还没有成绩或课程结果不以分数记录时为 `None`
+    pub score: Option<f64>,
+    pub letter_grade: Option<LetterGrade>,
+    /// 分数所在等级档的绩点，加权绩点按它计算
+    pub grade_points: Option<f64>,
+    /// 不以分数记录的课程结果（通过、旁听等），不计入绩点
+    pub mark: Option<GradeMark>,
+    /// 学生在退补选截止后退出了课程，成绩单上记为W，不计入绩点
+    pub withdrawn: bool,
+}
+
+/// 学生成绩单
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript {
+    pub student_id: u32,
+    pub student_name: String,
+    /// 按课程ID排序
+    pub entries: Vec<TranscriptEntry>,
+    pub gpa: WeightedGpa,
+}
+
+/// 学期成绩单中的一个学期
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermRecord {
+    /// 未安排学期的课程和成绩归入 `None`
+    pub term_id: Option<u32>,
+    pub term_name: String,
+    pub entries: Vec<TranscriptEntry>,
+    /// 学生在该学期录入的、不属于任何课程的科目成绩
+    pub grades: Vec<Grade>,
+    /// 该学期注册课程的学分总数（包括还没有成绩的课程，不包括已退出的课程）
+    pub enrolled_credits: u32,
+    pub term_gpa: WeightedGpa,
+    /// 截至该学期（含）的累计绩点
+    pub cumulative_gpa: WeightedGpa,
+}
+
+/// 按学期分组的成绩单，学期按开始日期排序，未安排学期的部分排在最后
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermTranscript {
+    pub student_id: u32,
+    pub student_name: String,
+    pub terms: Vec<TermRecord>,
+    pub cumulative_gpa: WeightedGpa,
+}
+
+/// 未安排学期的课程在学期成绩单中使用的名称
+pub const UNASSIGNED_TERM_NAME: &str = "未分配学期";
+
+impl EnrollmentManager {
+    /// 计算学生在所注册课程上的学分加权绩点
+    pub fn student_gpa(&self, student_id: u32) -> AppResult<WeightedGpa> {
+        Ok(self.transcript(student_id)?.gpa)
+    }
+
+    /// 生成学生的成绩单，等级和绩点按管理器当前等级制中分数所在的一档评定，
+    /// 与成绩记录上的等级一致（见 `set_grading_scale`）
+    ///
+    /// 已退出的课程也列在成绩单上，每次退出一条，归入退出时所在的学期，没有分数和等级；
+    /// 退出后在其他学期重新注册的课程另列一条。
+    pub fn transcript(&self, student_id: u32) -> AppResult<Transcript> {
+        let student = self.get_student(student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不存在", student_id)
+            ))?;
+
+        let mut courses: Vec<_> = self.list_all_courses()
+            .into_iter()
+            .filter(|c| c.is_enrolled(student_id) || c.is_withdrawn(student_id))
+            .collect();
+        courses.sort_by_key(|c| c.id);
+
+        let scale = self.grading_scale();
+        let mut entries = Vec::new();
+        for course in courses {
+            let entry = |term_id| TranscriptEntry {
+                course_id: course.id,
+                course_name: course.name.clone(),
+                credits: course.credits,
+                term_id,
+                score: None,
+                letter_grade: None,
+                grade_points: None,
+                mark: None,
+                withdrawn: false,
+            };
+            for &date in course.withdrawal_dates(student_id) {
+                entries.push(TranscriptEntry {
+                    withdrawn: true,
+                    ..entry(self.withdrawal_term(course, date))
+                });
+            }
+            if course.is_enrolled(student_id) {
+                let score = course.student_score(student);
+                let band = score.map(|s| scale.band_for(s));
+                entries.push(TranscriptEntry {
+                    score,
+                    letter_grade: band.map(|b| b.letter.clone()),
+                    grade_points: band.map(|b| b.gpa),
+                    mark: course.student_mark(student),
+                    ..entry(course.term_id)
+                });
+            }
+        }
+
+        let gpa = weighted_gpa(&entries);
+
+        Ok(Transcript {
+            student_id,
+            student_name: student.name.clone(),
+            entries,
+            gpa,
+        })
+    }
+
+    /// 学生在指定学期的学分加权绩点
+    pub fn term_gpa(&self, student_id: u32, term_id: u32) -> AppResult<WeightedGpa> {
+        if self.get_term(term_id).is_none() {
+            return Err(AppError::NotFound(format!("学期ID {} 不存在", term_id)));
+        }
+        let transcript = self.transcript(student_id)?;
+        let entries: Vec<TranscriptEntry> = transcript.entries.into_iter()
+            .filter(|e| e.term_id == Some(term_id))
+            .collect();
+        Ok(weighted_gpa(&entries))
+    }
+
+    /// 生成按学期分组的成绩单
+    ///
+    /// 每个学期给出学期绩点、注册学分和截至该学期的累计绩点；
+    /// 没有课程也没有成绩的学期不出现在成绩单中。
+    pub fn term_transcript(&self, student_id: u32) -> AppResult<TermTranscript> {
+        let transcript = self.transcript(student_id)?;
+        let student = self.get_student(student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不存在", student_id)
+            ))?;
+
+        let mut groups: Vec<(Option<u32>, String)> = self.list_terms()
+            .into_iter()
+            .map(|t| (Some(t.id), t.name.clone()))
+            .collect();
+        groups.push((None, UNASSIGNED_TERM_NAME.to_string()));
+
+        let mut terms = Vec::new();
+        let mut completed: Vec<TranscriptEntry> = Vec::new();
+        for (term_id, term_name) in groups {
+            let entries: Vec<TranscriptEntry> = transcript.entries.iter()
+                .filter(|e| e.term_id == term_id)
+                .cloned()
+                .collect();
+            let grades: Vec<Grade> = student.grades.iter()
+                .filter(|g| g.term_id() == term_id && g.course_id().is_none())
+                .cloned()
+                .collect();
+            if entries.is_empty() && grades.is_empty() {
+                continue;
+            }
+
+            completed.extend(entries.iter().cloned());
+            terms.push(TermRecord {
+                term_id,
+                term_name,
+                enrolled_credits: entries.iter()
+                    .filter(|e| !e.withdrawn)
+                    .map(|e| e.credits as u32)
+                    .sum(),
+                term_gpa: weighted_gpa(&entries),
+                cumulative_gpa: weighted_gpa(&completed),
+                entries,
+                grades,
+            });
+        }
+
+        Ok(TermTranscript {
+            student_id,
+            student_name: transcript.student_name,
+            terms,
+            cumulative_gpa: transcript.gpa,
+        })
+    }
+}
+
+/// 按课程的等级绩点和学分计算加权绩点，没有绩点的课程不计入
+fn weighted_gpa(entries: &[TranscriptEntry]) -> WeightedGpa {
+    let graded: Vec<(f64, u32)> = entries.iter()
+        .filter_map(|e| e.grade_points.map(|points| (points, e.credits as u32)))
+        .collect();
+    let total_credits: u32 = graded.iter().map(|&(_, credits)| credits).sum();
+    if total_credits == 0 {
+        return WeightedGpa { gpa: 0.0, total_credits: 0 };
+    }
+    let total_points: f64 = graded.iter().map(|&(points, credits)| points * credits as f64).sum();
+    WeightedGpa { gpa: total_points / total_credits as f64, total_credits }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Course, Student};
+
+    fn manager_with_grades() -> EnrollmentManager {
+        let mut manager = EnrollmentManager::new();
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 4).unwrap()).unwrap();
+        manager.add_course(Course::new(102, "体育".to_string(), 1).unwrap()).unwrap();
+        manager.add_course(Course::new(103, "数据库".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.enroll_student_in_course(1, 103).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_credit_weighted_gpa() {
+        let mut manager = manager_with_grades();
+        manager.add_course_grade(1, 101, "期末".to_string(), 95.0).unwrap();
+        manager.add_course_grade(1, 102, "期末".to_string(), 50.0).unwrap();
+
+        let gpa = manager.student_gpa(1).unwrap();
+        // (4.0 * 4 + 0.0 * 1) / 5，没有成绩的数据库课程不计入
+        assert_eq!(gpa, WeightedGpa { gpa: 3.2, total_credits: 5 });
+
+        let transcript = manager.transcript(1).unwrap();
+        assert_eq!(transcript.entries.len(), 3);
+        assert_eq!(transcript.entries[0].letter_grade, Some(LetterGrade::A));
+        assert_eq!(transcript.entries[2].score, None);
+    }
+
+    #[test]
+    fn test_term_and_cumulative_gpa() {
+        use crate::course::term::Term;
+
+        let mut manager = manager_with_grades();
+        let fall = Term::new(1, "2024秋".to_string(), "2024-09-01".parse().unwrap(), "2025-01-15".parse().unwrap()).unwrap();
+        let spring = Term::new(2, "2025春".to_string(), "2025-02-20".parse().unwrap(), "2025-07-01".parse().unwrap()).unwrap();
+        // 故意先添加较晚的学期，成绩单仍按开始日期排序
+        manager.add_term(spring).unwrap();
+        manager.add_term(fall).unwrap();
+        manager.assign_course_to_term(101, 1).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.assign_course_to_term(103, 2).unwrap();
+        assert!(manager.assign_course_to_term(101, 9).is_err());
+        manager.add_grade_in_term(1, 2, "英语".to_string(), 88.0).unwrap();
+        manager.add_grade(1, "音乐".to_string(), 75.0).unwrap();
+
+        manager.add_course_grade(1, 101, "期末".to_string(), 95.0).unwrap();
+        manager.add_course_grade(1, 102, "期末".to_string(), 85.0).unwrap();
+
+        assert_eq!(manager.term_gpa(1, 1).unwrap(), WeightedGpa { gpa: 4.0, total_credits: 4 });
+        assert_eq!(manager.term_gpa(1, 2).unwrap(), WeightedGpa { gpa: 3.0, total_credits: 1 });
+        assert!(manager.term_gpa(1, 9).is_err());
+
+        let transcript = manager.term_transcript(1).unwrap();
+        let names: Vec<&str> = transcript.terms.iter().map(|t| t.term_name.as_str()).collect();
+        assert_eq!(names, vec!["2024秋", "2025春", UNASSIGNED_TERM_NAME]);
+
+        let spring = &transcript.terms[1];
+        assert_eq!(spring.enrolled_credits, 4);
+        assert_eq!(spring.grades.len(), 1);
+        // (4.0 * 4 + 3.0 * 1) / 5
+        assert_eq!(spring.cumulative_gpa, WeightedGpa { gpa: 3.8, total_credits: 5 });
+        assert!(transcript.terms[2].entries.is_empty());
+        assert_eq!(transcript.cumulative_gpa, spring.cumulative_gpa);
+    }
+
+    #[test]
+    fn test_marked_courses_are_outside_gpa() {
+        let mut manager = manager_with_grades();
+        manager.add_course_grade(1, 101, "期末".to_string(), 95.0).unwrap();
+        manager.add_course_mark(1, 102, "总评".to_string(), GradeMark::Pass).unwrap();
+        manager.add_course_mark(1, 103, "总评".to_string(), GradeMark::Fail).unwrap();
+
+        let transcript = manager.transcript(1).unwrap();
+        assert_eq!(transcript.gpa, WeightedGpa { gpa: 4.0, total_credits: 4 });
+        assert_eq!(transcript.entries[1].mark, Some(GradeMark::Pass));
+        assert_eq!(transcript.entries[2].mark, Some(GradeMark::Fail));
+        assert_eq!(transcript.entries[2].score, None);
+    }
+
+    #[test]
+    fn test_gpa_uses_band_points() {
+        use crate::student::grading_scale::GradingScale;
+
+        let mut manager = manager_with_grades();
+        let scale = GradingScale::from_config_str("优 85 A 4.0\n合格 60 C 2.5\n不合格 0 F 0\n").unwrap();
+        manager.set_grading_scale(scale).unwrap();
+        manager.add_course_grade(1, 101, "期末".to_string(), 70.0).unwrap();
+        manager.add_course_grade(1, 102, "期末".to_string(), 90.0).unwrap();
+
+        let transcript = manager.transcript(1).unwrap();
+        assert_eq!(transcript.entries[0].letter_grade, Some(LetterGrade::C));
+        assert_eq!(transcript.entries[0].grade_points, Some(2.5));
+        // (2.5 * 4 + 4.0 * 1) / 5
+        assert_eq!(transcript.gpa, WeightedGpa { gpa: 2.8, total_credits: 5 });
+
+        // 学生自带的成绩加入管理器时按管理器的等级制评定
+        let mut student = Student::new(2, "李四".to_string(), 19).unwrap();
+        student.add_grade("数学".to_string(), 88.0).unwrap();
+        assert_eq!(student.grades[0].letter_grade(), Some(&LetterGrade::B));
+        manager.add_student(student).unwrap();
+        let grade = manager.get_student(2).unwrap().get_grade_by_subject("数学").unwrap();
+        assert_eq!(grade.letter_grade(), Some(&LetterGrade::A));
+    }
+
+    #[test]
+    fn test_scale_change_regrades_recorded_grades() {
+        use crate::student::grading_scale::GradingScale;
+
+        let mut manager = manager_with_grades();
+        manager.add_course_grade(1, 101, "期末".to_string(), 70.0).unwrap();
+        manager.add_course_mark(1, 102, "总评".to_string(), GradeMark::Pass).unwrap();
+        let stored = |manager: &EnrollmentManager| {
+            manager.get_student(1).unwrap().grades_in_course(101)[0].letter_grade().cloned()
+        };
+        assert_eq!(stored(&manager), Some(LetterGrade::C));
+
+        let scale = GradingScale::from_config_str("优 85 A 4.0\n合格 60 B 3.0\n不合格 0 F 0\n").unwrap();
+        manager.set_grading_scale(scale).unwrap();
+        // 成绩记录上的等级与成绩单一致
+        assert_eq!(stored(&manager), Some(LetterGrade::B));
+        let transcript = manager.transcript(1).unwrap();
+        assert_eq!(transcript.entries[0].letter_grade, Some(LetterGrade::B));
+        assert_eq!(transcript.entries[1].mark, Some(GradeMark::Pass));
+    }
+
+    #[test]
+    fn test_gpa_for_unknown_student() {
+        assert!(manager_with_grades().student_gpa(9).is_err());
+    }
+}
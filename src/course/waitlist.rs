@@ -0,0 +1,274 @@
+//! 课程容量和候补名单模块
+//!
+//! 课程可以设置座位数。课程已满时新的注册请求按先来先到排入候补名单，
+//! 有学生退出或容量增加时，候补名单最前面的学生自动转为正式注册。
+//! 补位只通过 `EnrollmentManager` 进行，候补学生要重新通过注册时的全部检查
+//! （学籍状态、退出记录、先修要求和上课时间冲突），不通过的学生留在候补名单中，
+//! 由后面的学生补位。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+
+/// 注册请求的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnrollmentOutcome {
+    Enrolled,
+    /// 课程已满，排在候补名单第 `position` 位（从1开始）
+    Waitlisted { position: usize },
+}
+
+impl std::fmt::Display for EnrollmentOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EnrollmentOutcome::Enrolled => write!(f, "注册成功"),
+            EnrollmentOutcome::Waitlisted { position } => write!(f, "课程已满，排在候补名单第{}位", position),
+        }
+    }
+}
+
+impl Course {
+    /// 课程容量，`None` 表示不限人数
+    pub fn capacity(&self) -> Option<u32> {
+        self.capacity
+    }
+
+    /// 设置课程容量，返回因容量增加而转为正式注册的学生ID
+    ///
+    /// 容量可以小于当前注册人数，已注册的学生不受影响，只是不再接受新的注册。
+    /// 补位不检查学生能否注册，只用于恢复保存的数据；
+    /// 其他情况使用 `EnrollmentManager::set_course_capacity`。
+    pub(crate) fn set_capacity(&mut self, capacity: Option<u32>) -> AppResult<Vec<u32>> {
+        self.set_capacity_limit(capacity)?;
+        let mut promoted = Vec::new();
+        while let Some(student_id) = self.promote_from_waitlist() {
+            promoted.push(student_id);
+        }
+        Ok(promoted)
+    }
+
+    /// 设置课程容量，不从候补名单补位
+    pub(crate) fn set_capacity_limit(&mut self, capacity: Option<u32>) -> AppResult<()> {
+        if capacity == Some(0) {
+            return Err(AppError::ValidationError("课程容量必须大于0".to_string()));
+        }
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    /// 已经没有空余座位
+    pub fn is_full(&self) -> bool {
+        self.capacity.is_some_and(|capacity| self.students.len() >= capacity as usize)
+    }
+
+    /// 剩余座位数，不限人数时为 `None`
+    pub fn available_seats(&self) -> Option<u32> {
+        self.capacity.map(|capacity| capacity.saturating_sub(self.students.len() as u32))
+    }
+
+    /// 候补名单中的学生ID，按排队顺序
+    pub fn waitlist(&self) -> Vec<u32> {
+        self.waitlist.iter().copied().collect()
+    }
+
+    /// 学生在候补名单中的位置（从1开始），不在名单中时为 `None`
+    pub fn waitlist_position(&self, student_id: u32) -> Option<usize> {
+        self.waitlist.iter().position(|&id| id == student_id).map(|index| index + 1)
+    }
+
+    /// 把学生从候补名单中移除
+    pub fn leave_waitlist(&mut self, student_id: u32) -> AppResult<()> {
+        let index = self.waitlist.iter().position(|&id| id == student_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生ID {} 不在课程 {} 的候补名单中", student_id, self.name)
+            ))?;
+        self.waitlist.remove(index);
+        Ok(())
+    }
+
+    /// 恢复保存过的候补名单，课程必须已满
+    pub(crate) fn add_to_waitlist(&mut self, student_id: u32) -> AppResult<()> {
+        if !self.is_full() {
+            return Err(AppError::ValidationError(
+                format!("课程 {} 还有空位，学生ID {} 不应在候补名单中", self.name, student_id)
+            ));
+        }
+        self.enroll_student(student_id).map(|_| ())
+    }
+
+    /// 有空位时把候补名单最前面的学生转为正式注册
+    pub(crate) fn promote_from_waitlist(&mut self) -> Option<u32> {
+        let student_id = *self.waitlist.front()?;
+        self.promote_student(student_id).ok()?;
+        Some(student_id)
+    }
+
+    /// 有空位时把候补名单中的指定学生转为正式注册
+    pub(crate) fn promote_student(&mut self, student_id: u32) -> AppResult<()> {
+        if self.is_full() {
+            return Err(AppError::ValidationError(format!("课程 {} 已满", self.name)));
+        }
+        self.leave_waitlist(student_id)?;
+        self.students.insert(student_id);
+        Ok(())
+    }
+}
+
+impl EnrollmentManager {
+    /// 设置课程容量，返回因此转为正式注册的学生ID
+    pub fn set_course_capacity(&mut self, course_id: u32, capacity: Option<u32>) -> AppResult<Vec<u32>> {
+        self.run_operation("set_course_capacity", |manager| {
+            manager.update_course(course_id, |course| course.set_capacity_limit(capacity))?;
+            manager.promote_waitlisted(course_id)
+        })
+    }
+
+    /// 让学生退出课程，返回自动补上空位的候补学生ID
+    pub fn remove_student_from_course(&mut self, student_id: u32, course_id: u32) -> AppResult<Vec<u32>> {
+        self.run_operation("remove_student_from_course", |manager| {
+            manager.update_course(course_id, |course| course.unenroll_student(student_id))?;
+            manager.promote_waitlisted(course_id)
+        })
+    }
+
+    /// 按排队顺序用候补学生补上课程的空位，返回转为正式注册的学生ID
+    ///
+    /// 候补学生要重新通过注册时的检查；不通过的学生留在候补名单中，由后面的学生补位。
+    pub(crate) fn promote_waitlisted(&mut self, course_id: u32) -> AppResult<Vec<u32>> {
+        let mut promoted = Vec::new();
+        for student_id in self.require_course(course_id)?.waitlist() {
+            let course = self.require_course(course_id)?;
+            if course.is_full() {
+                break;
+            }
+            if self.check_can_enroll(student_id, course).is_err() {
+                continue;
+            }
+            self.update_course(course_id, |course| course.promote_student(student_id))?;
+            promoted.push(student_id);
+        }
+        Ok(promoted)
+    }
+
+    /// 学生在课程候补名单中的位置（从1开始）
+    pub fn waitlist_position(&self, student_id: u32, course_id: u32) -> AppResult<Option<usize>> {
+        let course = self.get_course(course_id)
+            .ok_or_else(|| AppError::NotFound(
+                format!("课程ID {} 不存在", course_id)
+            ))?;
+        Ok(course.waitlist_position(student_id))
+    }
+
+    pub fn leave_waitlist(&mut self, student_id: u32, course_id: u32) -> AppResult<()> {
+        self.run_operation("leave_waitlist", |manager| {
+            manager.update_course(course_id, |course| course.leave_waitlist(student_id))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::schedule::TimeSlot;
+    use crate::course::test_support::manager_with;
+    use crate::utils::date::Weekday;
+    use crate::Student;
+
+    fn full_course() -> Course {
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.set_capacity(Some(2)).unwrap();
+        for id in 1..=4 {
+            course.enroll_student(id).unwrap();
+        }
+        course
+    }
+
+    #[test]
+    fn test_full_course_waitlists_in_order() {
+        let mut course = full_course();
+        assert!(course.is_full());
+        assert_eq!(course.student_count(), 2);
+        assert_eq!(course.waitlist(), vec![3, 4]);
+        assert_eq!(course.waitlist_position(4), Some(2));
+        assert_eq!(course.waitlist_position(1), None);
+        let outcome = course.enroll_student(5).unwrap();
+        assert_eq!(outcome, EnrollmentOutcome::Waitlisted { position: 3 });
+        assert_eq!(outcome.to_string(), "课程已满，排在候补名单第3位");
+        assert!(course.enroll_student(3).is_err());
+        assert!(course.enroll_student(1).is_err());
+        assert!(course.set_capacity(Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_removal_promotes_next_student() {
+        let mut course = full_course();
+        course.unenroll_student(1).unwrap();
+        assert_eq!(course.promote_from_waitlist(), Some(3));
+        assert!(course.is_enrolled(3));
+        assert_eq!(course.waitlist_position(4), Some(1));
+
+        course.leave_waitlist(4).unwrap();
+        assert!(course.leave_waitlist(4).is_err());
+        course.unenroll_student(2).unwrap();
+        assert_eq!(course.promote_from_waitlist(), None);
+        assert_eq!(course.available_seats(), Some(1));
+    }
+
+    #[test]
+    fn test_raising_capacity_promotes() {
+        let mut course = full_course();
+        assert_eq!(course.set_capacity(Some(3)).unwrap(), vec![3]);
+        assert_eq!(course.set_capacity(None).unwrap(), vec![4]);
+        assert!(course.waitlist().is_empty());
+
+        // 容量低于已注册人数时，已注册的学生保留
+        course.set_capacity(Some(1)).unwrap();
+        assert_eq!(course.student_count(), 4);
+        assert_eq!(course.available_seats(), Some(0));
+    }
+
+    #[test]
+    fn test_manager_promotion_and_position() {
+        let mut manager = EnrollmentManager::new();
+        for (id, name) in [(1, "张三"), (2, "李四"), (3, "王五")] {
+            manager.add_student(Student::new(id, name.to_string(), 20).unwrap()).unwrap();
+        }
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.set_course_capacity(101, Some(1)).unwrap();
+
+        assert_eq!(manager.enroll_student_in_course(1, 101).unwrap(), EnrollmentOutcome::Enrolled);
+        assert_eq!(
+            manager.enroll_student_in_course(2, 101).unwrap(),
+            EnrollmentOutcome::Waitlisted { position: 1 }
+        );
+        manager.enroll_student_in_course(3, 101).unwrap();
+        assert_eq!(manager.waitlist_position(3, 101).unwrap(), Some(2));
+
+        assert_eq!(manager.remove_student_from_course(1, 101).unwrap(), vec![2]);
+        assert_eq!(manager.list_course_students(101).unwrap()[0].id, 2);
+        assert_eq!(manager.waitlist_position(3, 101).unwrap(), Some(1));
+        assert!(manager.waitlist_position(3, 999).is_err());
+    }
+
+    #[test]
+    fn test_promotion_rechecks_enrollment() {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四"), (3, "王五")], &[(101, "Rust编程", 3), (102, "数据库", 2)]);
+        manager.set_course_capacity(101, Some(1)).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+        manager.enroll_student_in_course(3, 101).unwrap();
+
+        // 李四排队后注册了时间冲突的课程，补位时被跳过但留在候补名单中
+        let slot = TimeSlot::new(Weekday::Monday, "08:00".parse().unwrap(), "09:40".parse().unwrap(), "A101").unwrap();
+        manager.add_course_time_slot(101, slot.clone()).unwrap();
+        manager.add_course_time_slot(102, slot).unwrap();
+        manager.enroll_student_in_course(2, 102).unwrap();
+
+        assert_eq!(manager.remove_student_from_course(1, 101).unwrap(), vec![3]);
+        assert!(!manager.get_course(101).unwrap().is_enrolled(2));
+        assert_eq!(manager.waitlist_position(2, 101).unwrap(), Some(1));
+
+        manager.remove_student_from_course(2, 102).unwrap();
+        assert_eq!(manager.set_course_capacity(101, Some(2)).unwrap(), vec![2]);
+    }
+}
@@ -0,0 +1,348 @@
+//! 退课和退出课程模块
+//!
+//! 退补选截止日期（含）之前退课会抹去注册记录和该课程的成绩；
+//! 之后到退出课程截止日期（含）之前可以退出课程，成绩单上保留一条W记录，
+//! 不计入绩点。退出前的成绩、考核项分数和考勤记录都保留下来，成绩标记为
+//! 退出，不再计入平均分和课程成绩。截止日期在课程所属学期上配置，课程没有
+//! 安排学期或学期没有设置截止日期时不受限制。
+//!
+//! 退出课程的学生在同一学期不能重新注册；课程安排到其他学期后可以重新注册，
+//! 此时上一次修读的考核项分数和考勤记录被清除。每次退出都记录下来，
+//! 在成绩单上各留一条W记录。
+
+use super::enrollment::EnrollmentManager;
+use super::Course;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+
+impl Course {
+    /// 学生是否在退补选截止后退出过本课程
+    pub fn is_withdrawn(&self, student_id: u32) -> bool {
+        self.withdrawals.contains_key(&student_id)
+    }
+
+    /// 学生最近一次退出本课程的日期
+    pub fn withdrawal_date(&self, student_id: u32) -> Option<Date> {
+        self.withdrawal_dates(student_id).last().copied()
+    }
+
+    /// 学生每次退出本课程的日期，按时间顺序排列
+    pub fn withdrawal_dates(&self, student_id: u32) -> &[Date] {
+        self.withdrawals.get(&student_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// 退出过本课程的学生ID，按从小到大排序
+    pub fn withdrawn_student_ids(&self) -> Vec<u32> {
+        self.withdrawals.keys().copied().collect()
+    }
+
+    /// 学生退出课程并保留W记录，空位由管理器从候补名单补上
+    ///
+    /// 学生的考核项分数和考勤记录保留；以前修读时的退出记录也保留，每次退出各记一条。
+    pub(crate) fn withdraw_student(&mut self, student_id: u32, date: Date) -> AppResult<()> {
+        if !self.students.contains(&student_id) {
+            return Err(AppError::NotFound(
+                format!("学生ID {} 未找到", student_id)
+            ));
+        }
+        self.restore_withdrawal(student_id, date)?;
+        self.students.remove(&student_id);
+        Ok(())
+    }
+
+    /// 恢复保存过的一次退出记录，同一学生的退出记录必须按时间顺序恢复
+    ///
+    /// 学生可以同时在注册名单或候补名单中：课程安排到新学期后重新注册的学生保留以前的退出记录。
+    pub(crate) fn restore_withdrawal(&mut self, student_id: u32, date: Date) -> AppResult<()> {
+        if let Some(last) = self.withdrawal_date(student_id).filter(|&last| date <= last) {
+            return Err(AppError::ValidationError(
+                format!("学生ID {} 在课程 {} 中的退出日期 {} 不晚于上一次退出（{}）", student_id, self.name, date, last)
+            ));
+        }
+        self.withdrawals.entry(student_id).or_default().push(date);
+        Ok(())
+    }
+}
+
+impl EnrollmentManager {
+    /// 设置学期的退补选和退出课程截止日期，`None` 表示不限
+    pub fn set_term_deadlines(
+        &mut self,
+        term_id: u32,
+        add_drop_deadline: Option<Date>,
+        withdrawal_deadline: Option<Date>,
+    ) -> AppResult<()> {
+        self.run_operation("set_term_deadlines", |manager| {
+            manager.update_term(term_id, |term| term.set_deadlines(add_drop_deadline, withdrawal_deadline))
+        })
+    }
+
+    /// 在退补选截止日期（含）之前退课，返回自动补上空位的候补学生ID
+    ///
+    /// 注册记录、考核项分数和记在该课程名下的成绩都会被删除，成绩单上不留痕迹。
+    pub fn drop_course(&mut self, student_id: u32, course_id: u32, date: Date) -> AppResult<Vec<u32>> {
+        self.run_operation("drop_course", |manager| {
+            let (add_drop_deadline, _) = manager.enrolled_course_deadlines(student_id, course_id)?;
+            if let Some(deadline) = add_drop_deadline.filter(|&deadline| date > deadline) {
+                return Err(AppError::ValidationError(
+                    format!("课程ID {} 的退补选已于 {} 截止，只能退出课程", course_id, deadline)
+                ));
+            }
+
+            manager.update_course_and_student(
+                course_id,
+                student_id,
+                |course| course.unenroll_student(student_id),
+                |student| {
+                    student.grades.retain(|g| g.course_id() != Some(course_id) || g.is_withdrawn());
+                    Ok(())
+                },
+            )?;
+            manager.promote_waitlisted(course_id)
+        })
+    }
+
+    /// 在退补选截止后、退出课程截止日期（含）之前退出课程，返回自动补上空位的候补学生ID
+    ///
+    /// 成绩单上保留W记录，不计入绩点；学生在该课程的成绩标记为退出，考核项分数和考勤记录保留。
+    /// 仍在退补选期内时应当使用 `drop_course`。
+    pub fn withdraw_from_course(&mut self, student_id: u32, course_id: u32, date: Date) -> AppResult<Vec<u32>> {
+        self.run_operation("withdraw_from_course", |manager| {
+            let (add_drop_deadline, withdrawal_deadline) = manager.enrolled_course_deadlines(student_id, course_id)?;
+            if let Some(deadline) = add_drop_deadline.filter(|&deadline| date <= deadline) {
+                return Err(AppError::ValidationError(
+                    format!("课程ID {} 仍在退补选期内（{} 截止），请直接退课", course_id, deadline)
+                ));
+            }
+            if let Some(deadline) = withdrawal_deadline.filter(|&deadline| date > deadline) {
+                return Err(AppError::ValidationError(
+                    format!("课程ID {} 的退出课程已于 {} 截止", course_id, deadline)
+                ));
+            }
+
+            manager.update_course_and_student(
+                course_id,
+                student_id,
+                |course| course.withdraw_student(student_id, date),
+                |student| {
+                    student.grades.iter_mut()
+                        .filter(|g| g.course_id() == Some(course_id))
+                        .try_for_each(|g| g.mark_withdrawn())
+                },
+            )?;
+            manager.promote_waitlisted(course_id)
+        })
+    }
+
+    /// 学生在 `date` 退出课程时所在的学期：课程学期包含退出日期时为课程学期，
+    /// 否则为包含退出日期的学期；都找不到时按课程学期计算
+    pub(crate) fn withdrawal_term(&self, course: &Course, date: Date) -> Option<u32> {
+        let course_term = course.term_id.and_then(|id| self.get_term(id));
+        if course_term.is_some_and(|term| term.contains(date)) {
+            return course.term_id;
+        }
+        self.list_terms()
+            .into_iter()
+            .find(|term| term.contains(date))
+            .map(|term| term.id)
+            .or(course.term_id)
+    }
+
+    /// 学生在课程当前学期退出过课程时不能重新注册
+    pub(crate) fn check_not_withdrawn(&self, student_id: u32, course: &Course) -> AppResult<()> {
+        let Some(date) = course.withdrawal_date(student_id) else {
+            return Ok(());
+        };
+        if self.withdrawal_term(course, date) == course.term_id {
+            return Err(AppError::ValidationError(
+                format!("学生ID {} 已于 {} 退出课程 {}，本学期不能重新注册", student_id, date, course.name)
+            ));
+        }
+        Ok(())
+    }
+
+    /// 学生已注册课程的 (退补选截止日期, 退出课程截止日期)
+    fn enrolled_course_deadlines(&self, student_id: u32, course_id: u32) -> AppResult<(Option<Date>, Option<Date>)> {
+        if self.get_student(student_id).is_none() {
+            return Err(AppError::NotFound(format!("学生ID {} 不存在", student_id)));
+        }
+        let course = self.enrolled_course(student_id, course_id)?;
+        Ok(match course.term_id.and_then(|term_id| self.get_term(term_id)) {
+            Some(term) => (term.add_drop_deadline, term.withdrawal_deadline),
+            None => (None, None),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::assessment::AssessmentComponent;
+    use crate::course::attendance::AttendanceStatus;
+    use crate::course::term::Term;
+    use crate::course::test_support::{date, manager_with, FailingStorage};
+    use crate::student::Student;
+
+    fn manager() -> EnrollmentManager {
+        let mut manager = manager_with(&[(1, "张三"), (2, "李四")], &[(101, "Rust编程", 3), (102, "数据库", 2)]);
+        let term = Term::new(1, "2024秋".to_string(), date("2024-09-01"), date("2025-01-15")).unwrap();
+        manager.add_term(term).unwrap();
+        manager.set_term_deadlines(1, Some(date("2024-09-15")), Some(date("2024-11-15"))).unwrap();
+        for id in [101, 102] {
+            manager.assign_course_to_term(id, 1).unwrap();
+            manager.enroll_student_in_course(1, id).unwrap();
+        }
+        manager.add_course_grade(1, 101, "期中".to_string(), 90.0).unwrap();
+        manager.add_course_grade(1, 102, "期中".to_string(), 40.0).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_deadline_validation() {
+        let mut manager = manager();
+        // 截止日期必须在学期之内，且退补选不能晚于退出课程
+        assert!(manager.set_term_deadlines(1, Some(date("2025-02-01")), None).is_err());
+        assert!(manager.set_term_deadlines(1, Some(date("2024-11-01")), Some(date("2024-10-01"))).is_err());
+        assert!(manager.set_term_deadlines(9, None, None).is_err());
+        assert_eq!(manager.get_term(1).unwrap().add_drop_deadline, Some(date("2024-09-15")));
+    }
+
+    #[test]
+    fn test_drop_erases_enrollment_and_grades() {
+        let mut manager = manager();
+        manager.set_course_capacity(101, Some(1)).unwrap();
+        manager.enroll_student_in_course(2, 101).unwrap();
+
+        assert_eq!(manager.drop_course(1, 101, date("2024-09-15")).unwrap(), vec![2]);
+        assert!(manager.get_student(1).unwrap().grades_in_course(101).is_empty());
+        assert!(manager.transcript(1).unwrap().entries.iter().all(|e| e.course_id != 101));
+        assert!(manager.drop_course(1, 101, date("2024-09-10")).is_err());
+        assert!(manager.drop_course(1, 102, date("2024-09-16")).is_err());
+        assert!(manager.drop_course(9, 102, date("2024-09-10")).is_err());
+    }
+
+    #[test]
+    fn test_withdrawal_keeps_w_record_outside_gpa() {
+        let mut manager = manager();
+        assert!(manager.withdraw_from_course(1, 102, date("2024-09-15")).is_err());
+        assert!(manager.withdraw_from_course(1, 102, date("2024-11-16")).is_err());
+        manager.withdraw_from_course(1, 102, date("2024-11-15")).unwrap();
+
+        let course = manager.get_course(102).unwrap();
+        assert!(!course.is_enrolled(1));
+        assert_eq!(course.withdrawal_date(1), Some(date("2024-11-15")));
+        assert!(manager.enroll_student_in_course(1, 102).is_err());
+
+        let transcript = manager.transcript(1).unwrap();
+        let withdrawn = transcript.entries.iter().find(|e| e.course_id == 102).unwrap();
+        assert!(withdrawn.withdrawn);
+        assert_eq!(withdrawn.score, None);
+        // 只剩 Rust编程 的A计入绩点
+        assert_eq!(transcript.gpa.total_credits, 3);
+        assert_eq!(transcript.gpa.gpa, 4.0);
+        assert_eq!(manager.term_transcript(1).unwrap().terms[0].enrolled_credits, 3);
+    }
+
+    #[test]
+    fn test_withdrawal_keeps_records_outside_averages() {
+        let mut manager = manager();
+        manager.update_course(102, |course| course.set_assessment_components(vec![
+            AssessmentComponent::new("期末", 100.0).unwrap(),
+        ])).unwrap();
+        manager.record_component_score(102, 1, "期末", 55.0).unwrap();
+        manager.record_attendance(102, date("2024-10-08").into(), 1, AttendanceStatus::Absent).unwrap();
+        assert_eq!(manager.get_student(1).unwrap().average_grade(), 65.0);
+
+        manager.withdraw_from_course(1, 102, date("2024-11-01")).unwrap();
+        let course = manager.get_course(102).unwrap();
+        assert_eq!(course.component_score(1, "期末"), Some(55.0));
+        assert_eq!(course.attendance_status(date("2024-10-08").into(), 1), Some(AttendanceStatus::Absent));
+        let student = manager.get_student(1).unwrap();
+        assert!(student.grades_in_course(102).iter().all(|g| g.is_withdrawn()));
+        assert_eq!(student.average_grade(), 90.0);
+
+        // 分数、考勤和退出标记都能保存下来
+        let restored = EnrollmentManager::from_snapshot_str(&manager.to_snapshot_string()).unwrap();
+        assert_eq!(restored, manager);
+        // 退出后不能再记录
+        assert!(manager.record_component_score(102, 1, "期末", 60.0).is_err());
+    }
+
+    #[test]
+    fn test_reenrolment_is_blocked_only_in_the_same_term() {
+        let mut manager = manager();
+        manager.withdraw_from_course(1, 102, date("2024-11-01")).unwrap();
+        let error = manager.enroll_student_in_course(1, 102).unwrap_err().to_string();
+        assert!(error.contains("本学期不能重新注册"), "{}", error);
+
+        let spring = Term::new(2, "2025春".to_string(), date("2025-02-20"), date("2025-07-01")).unwrap();
+        manager.add_term(spring).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.add_course_grade(1, 102, "期末".to_string(), 80.0).unwrap();
+
+        // 上一次修读记为W并归入退出时的学期，新的修读只计算新成绩
+        let transcript = manager.transcript(1).unwrap();
+        let entries: Vec<_> = transcript.entries.iter()
+            .filter(|e| e.course_id == 102)
+            .map(|e| (e.term_id, e.withdrawn, e.score))
+            .collect();
+        assert_eq!(entries, vec![(Some(1), true, None), (Some(2), false, Some(80.0))]);
+        assert_eq!(manager.term_transcript(1).unwrap().terms.len(), 2);
+
+        // 退课只删除这一次修读的成绩
+        manager.drop_course(1, 102, date("2025-02-21")).unwrap();
+        assert_eq!(manager.get_student(1).unwrap().grades_in_course(102).len(), 1);
+    }
+
+    #[test]
+    fn test_every_withdrawal_is_kept() {
+        let mut manager = manager();
+        manager.withdraw_from_course(1, 102, date("2024-11-01")).unwrap();
+        let spring = Term::new(2, "2025春".to_string(), date("2025-02-20"), date("2025-07-01")).unwrap();
+        manager.add_term(spring).unwrap();
+        manager.assign_course_to_term(102, 2).unwrap();
+        manager.enroll_student_in_course(1, 102).unwrap();
+        manager.withdraw_from_course(1, 102, date("2025-04-01")).unwrap();
+
+        let course = manager.get_course(102).unwrap();
+        assert_eq!(course.withdrawal_dates(1), [date("2024-11-01"), date("2025-04-01")]);
+        assert_eq!(course.withdrawal_date(1), Some(date("2025-04-01")));
+        let terms: Vec<_> = manager.transcript(1).unwrap().entries.iter()
+            .filter(|e| e.course_id == 102)
+            .map(|e| (e.term_id, e.withdrawn))
+            .collect();
+        assert_eq!(terms, vec![(Some(1), true), (Some(2), true)]);
+
+        let restored = EnrollmentManager::from_snapshot_str(&manager.to_snapshot_string()).unwrap();
+        assert_eq!(restored.get_course(102).unwrap().withdrawal_dates(1).len(), 2);
+        assert_eq!(restored.transcript(1).unwrap(), manager.transcript(1).unwrap());
+    }
+
+    #[test]
+    fn test_failed_student_write_restores_course() {
+        let storage = FailingStorage::default();
+        let fail_student_writes = storage.fail_student_writes.clone();
+        let mut manager = EnrollmentManager::with_storage(Box::new(storage));
+        manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.add_course_grade(1, 101, "期中".to_string(), 90.0).unwrap();
+        let expected = manager.to_snapshot_string();
+
+        fail_student_writes.set(true);
+        assert!(manager.withdraw_from_course(1, 101, date("2024-11-01")).is_err());
+        assert!(manager.drop_course(1, 101, date("2024-09-10")).is_err());
+        assert_eq!(manager.to_snapshot_string(), expected);
+        assert!(manager.get_course(101).unwrap().is_enrolled(1));
+    }
+
+    #[test]
+    fn test_courses_without_deadlines() {
+        let mut manager = manager();
+        manager.set_term_deadlines(1, None, None).unwrap();
+        manager.withdraw_from_course(1, 101, date("2025-01-10")).unwrap();
+        manager.drop_course(1, 102, date("2025-01-10")).unwrap();
+        assert_eq!(manager.transcript(1).unwrap().entries.len(), 1);
+    }
+}
@@ -11,6 +11,8 @@ pub enum AppError {
     ValidationError(String),
     IoError(std::io::Error),
     ParseError(std::num::ParseIntError),
+    StorageError(String),
+    CorruptedData(String),
 }
 
 impl fmt::Display for AppError {
@@ -21,6 +23,8 @@ impl fmt::Display for AppError {
             AppError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
             AppError::IoError(err) => write!(f, "IO错误: {}", err),
             AppError::ParseError(err) => write!(f, "解析错误: {}", err),
+            AppError::StorageError(msg) => write!(f, "存储错误: {}", msg),
+            AppError::CorruptedData(msg) => write!(f, "数据损坏: {}", msg),
         }
     }
 }
@@ -39,4 +43,11 @@ impl From<std::num::ParseIntError> for AppError {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        AppError::StorageError(error.to_string())
+    }
+}
+
 pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
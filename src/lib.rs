@@ -6,6 +6,7 @@ pub mod student;
 pub mod course;
 pub mod utils;
 pub mod errors;
+pub mod storage;
 
 // 重新导出常用类型
 pub use student::Student;
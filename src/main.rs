@@ -41,7 +41,7 @@ impl Student {
 
 fn main() {
     println!("🦀 Rust学习之旅 - 第10步：集合类型");
-    println!("=".repeat(50));
+    println!("{}", "=".repeat(50));
     
     // 1. Vector动态数组
     demonstrate_vectors();
@@ -60,6 +60,8 @@ fn main() {
 }
 
 /// 演示Vector的使用
+// 示例有意逐步展示 push 等基础写法
+#[allow(clippy::vec_init_then_push)]
 fn demonstrate_vectors() {
     println!("\n� 1. Vector动态数组");
     
@@ -117,6 +119,7 @@ fn demonstrate_vectors() {
 }
 
 /// 演示HashMap的使用
+#[allow(clippy::useless_vec)]
 fn demonstrate_hashmaps() {
     println!("\n�️ 2. HashMap哈希映射");
     
@@ -234,6 +237,7 @@ fn demonstrate_strings() {
 }
 
 /// 演示迭代器和闭包
+#[allow(clippy::iter_count)]
 fn demonstrate_iterators() {
     println!("\n🔄 4. 迭代器和闭包");
     
@@ -271,5 +275,86 @@ fn demonstrate_iterators() {
     let threshold = 5;
     let above_threshold: Vec<&i32> = numbers
         .iter()
-        .filter(|
+        .filter(|&&x| x > threshold)  // 闭包使用了外部的threshold
+        .collect();
+    println!("大于{}的数: {:?}", threshold, above_threshold);
+    
+    // 可变闭包
+    let mut total = 0;
+    let mut accumulate = |x: i32| total += x;
+    for &n in &numbers {
+        accumulate(n);
+    }
+    println!("累加结果: {}", total);
+    
+    // 链式操作
+    let result: i32 = numbers
+        .iter()
+        .skip(2)
+        .take(5)
+        .map(|&x| x * 10)
+        .sum();
+    println!("跳过2个、取5个、乘10后求和: {}", result);
+    
+    // 求积
+    let product: i32 = numbers.iter().take(5).product();
+    println!("前5个数的乘积: {}", product);
+}
 
+/// 演示综合应用：学生管理系统
+fn demonstrate_student_system() {
+    println!("\n🎓 5. 综合应用：学生管理系统");
+    
+    let mut students: HashMap<u32, Student> = HashMap::new();
+    
+    let roster = vec![
+        (1, "张三", 20, vec![85.0, 92.0, 78.0]),
+        (2, "李四", 21, vec![95.0, 88.0, 91.0]),
+        (3, "王五", 19, vec![72.0, 65.0, 80.0]),
+    ];
+    
+    for (id, name, age, grades) in roster {
+        let mut student = Student::new(id, name.to_string(), age);
+        for grade in grades {
+            student.add_grade(grade);
+        }
+        students.insert(id, student);
+    }
+    
+    // 按学号顺序显示
+    let mut ids: Vec<&u32> = students.keys().collect();
+    ids.sort();
+    println!("学生列表:");
+    for id in ids {
+        let student = &students[id];
+        println!("  [{}] {} ({}岁) 成绩: {:?} 平均分: {:.1}",
+                 student.id, student.name, student.age, student.grades, student.average_grade());
+    }
+    
+    // 找出平均分最高的学生
+    let best = students
+        .values()
+        .max_by(|a, b| a.average_grade().total_cmp(&b.average_grade()));
+    if let Some(student) = best {
+        println!("平均分最高: {} ({:.1})", student.name, student.average_grade());
+    }
+    
+    // 按平均分分组
+    let mut groups: HashMap<&str, Vec<&str>> = HashMap::new();
+    for student in students.values() {
+        let level = if student.average_grade() >= 85.0 { "优秀" } else { "良好及以下" };
+        groups.entry(level).or_default().push(&student.name);
+    }
+    for names in groups.values_mut() {
+        names.sort();
+    }
+    println!("按等级分组: {:?}", groups);
+    
+    // 全体平均分
+    let all_grades: Vec<f64> = students
+        .values()
+        .flat_map(|s| s.grades.iter().copied())
+        .collect();
+    let overall = all_grades.iter().sum::<f64>() / all_grades.len() as f64;
+    println!("全体平均分: {:.1}", overall);
+}
@@ -0,0 +1,185 @@
+//! 文本文件存储后端
+
+use super::memory::MemoryStorage;
+use super::Storage;
+use crate::course::instructor::Instructor;
+use crate::course::persistence::{decode_snapshot, encode_storage};
+use crate::course::programme::Programme;
+use crate::course::term::Term;
+use crate::errors::AppResult;
+use crate::student::grading_scale::GradingScale;
+use crate::utils::file_handler::{
+    file_exists, read_file_to_string, write_string_to_file, DirectoryLock,
+};
+use crate::{Course, Student};
+use std::path::{Path, PathBuf};
+
+/// 所有数据保存在一个快照文件中（格式见 `persistence` 模块）
+///
+/// 打开时把文件读入内存，之后每次写操作都原子地重写整个文件。
+/// 存储打开期间持有所在目录的锁，防止多个进程同时写入。
+#[derive(Debug)]
+pub struct FileStorage {
+    path: PathBuf,
+    cache: MemoryStorage,
+    _lock: DirectoryLock,
+}
+
+impl FileStorage {
+    /// 打开数据文件，文件不存在时从空数据开始
+    ///
+    /// 所在目录已被其他进程锁定时返回错误。
+    pub fn open<P: AsRef<Path>>(path: P) -> AppResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let lock = DirectoryLock::try_acquire(&directory)?;
+        let mut cache = MemoryStorage::new();
+
+        if file_exists(&path) {
+            cache.merge(decode_snapshot(&read_file_to_string(&path)?)?)?;
+        }
+
+        Ok(FileStorage { path, cache, _lock: lock })
+    }
+
+    fn flush(&self) -> AppResult<()> {
+        write_string_to_file(&self.path, &encode_storage(&self.cache))
+    }
+}
+
+impl Storage for FileStorage {
+    fn get_student(&self, id: u32) -> Option<&Student> {
+        self.cache.get_student(id)
+    }
+
+    fn list_students(&self) -> Vec<&Student> {
+        self.cache.list_students()
+    }
+
+    fn save_student(&mut self, student: Student) -> AppResult<()> {
+        let id = student.id;
+        let previous = self.cache.insert_student(student);
+        if let Err(e) = self.flush() {
+            // 写入失败时恢复缓存，保持与文件一致
+            match previous {
+                Some(previous) => self.cache.insert_student(previous),
+                None => self.cache.remove_student(id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_course(&self, id: u32) -> Option<&Course> {
+        self.cache.get_course(id)
+    }
+
+    fn list_courses(&self) -> Vec<&Course> {
+        self.cache.list_courses()
+    }
+
+    fn save_course(&mut self, course: Course) -> AppResult<()> {
+        let id = course.id;
+        let previous = self.cache.insert_course(course);
+        if let Err(e) = self.flush() {
+            match previous {
+                Some(previous) => self.cache.insert_course(previous),
+                None => self.cache.remove_course(id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_term(&self, id: u32) -> Option<&Term> {
+        self.cache.get_term(id)
+    }
+
+    fn list_terms(&self) -> Vec<&Term> {
+        self.cache.list_terms()
+    }
+
+    fn save_term(&mut self, term: Term) -> AppResult<()> {
+        let id = term.id;
+        let previous = self.cache.insert_term(term);
+        if let Err(e) = self.flush() {
+            match previous {
+                Some(previous) => self.cache.insert_term(previous),
+                None => self.cache.remove_term(id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor> {
+        self.cache.get_instructor(id)
+    }
+
+    fn list_instructors(&self) -> Vec<&Instructor> {
+        self.cache.list_instructors()
+    }
+
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        let id = instructor.id;
+        let previous = self.cache.insert_instructor(instructor);
+        if let Err(e) = self.flush() {
+            match previous {
+                Some(previous) => self.cache.insert_instructor(previous),
+                None => self.cache.remove_instructor(id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_programme(&self, id: u32) -> Option<&Programme> {
+        self.cache.get_programme(id)
+    }
+
+    fn list_programmes(&self) -> Vec<&Programme> {
+        self.cache.list_programmes()
+    }
+
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()> {
+        let id = programme.id;
+        let previous = self.cache.insert_programme(programme);
+        if let Err(e) = self.flush() {
+            match previous {
+                Some(previous) => self.cache.insert_programme(previous),
+                None => self.cache.remove_programme(id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn grading_scale(&self) -> &GradingScale {
+        self.cache.grading_scale()
+    }
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        let previous = self.cache.replace_grading_scale(scale);
+        if let Err(e) = self.flush() {
+            self.cache.replace_grading_scale(previous);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn attendance_threshold(&self) -> f64 {
+        self.cache.attendance_threshold()
+    }
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        let previous = self.cache.replace_attendance_threshold(threshold);
+        if let Err(e) = self.flush() {
+            self.cache.replace_attendance_threshold(previous);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,483 @@
+//! 操作日志存储后端
+//!
+//! 数据保存在一个快照文件（格式见 `persistence` 模块）和一个只追加的日志文件中。
+//! 每次写操作先把写入后的完整对象追加到日志，再更新内存中的缓存，所以通过
+//! `EnrollmentManager` 进行的任何修改都会在日志中留下完整的记录，便于审计；
+//! 打开时加载快照并按顺序重放日志。日志达到一定条数时自动压缩为快照，
+//! 之后只需重放快照之后的条目。
+//!
+//! 日志每行一条记录 `序号 操作 类型 数据`，字段编码与快照相同：
+//! - 序号从1开始递增，压缩后继续累加
+//! - 操作是写入所属的 `EnrollmentManager` 操作名（如 `enroll_student_in_course`），
+//!   一个操作写入的所有记录都带有同一个操作名；直接通过 `Storage` 写入时为空
+//! - 类型是 `student`、`course`、`term`、`instructor`、`programme`、`scale`（等级制）
+//!   或 `threshold`（出勤率警戒线）
+//! - 数据是只包含这一个对象的快照文本，带版本头和校验和
+//!
+//! 压缩得到的快照记录它包含的最后一条日志的序号，重放时跳过不大于它的条目，
+//! 所以在写完快照、清空日志之前崩溃也不会重复应用日志。追加时写入失败会把
+//! 日志截断回原来的长度；进程在追加中途退出留下的不完整末行在打开时丢弃。
+
+use super::memory::MemoryStorage;
+use super::Storage;
+use crate::course::attendance::DEFAULT_ATTENDANCE_THRESHOLD;
+use crate::course::instructor::Instructor;
+use crate::course::persistence::{
+    decode_record, decode_snapshot, encode_record, encode_snapshot, SnapshotData, SnapshotSettings,
+};
+use crate::course::programme::Programme;
+use crate::course::term::Term;
+use crate::errors::{AppError, AppResult};
+use crate::student::grading_scale::GradingScale;
+use crate::utils::file_handler::{
+    append_line_to_file, file_exists, read_file_to_string, truncate_file, write_string_to_file,
+    DirectoryLock,
+};
+use crate::{Course, Student};
+use std::path::{Path, PathBuf};
+
+/// 日志文件头
+const JOURNAL_HEADER: &str = "# enrollment-journal v1";
+
+/// 默认在日志达到多少条记录时自动压缩
+pub const DEFAULT_COMPACTION_THRESHOLD: usize = 1000;
+
+/// 快照加日志的存储后端
+///
+/// 存储打开期间持有日志所在目录的锁，防止多个进程同时写入。
+#[derive(Debug)]
+pub struct JournalStorage {
+    journal_path: PathBuf,
+    snapshot_path: PathBuf,
+    cache: MemoryStorage,
+    /// 最后一条日志的序号，还没有日志时为快照记录的序号
+    last_seq: u64,
+    /// 快照之后日志中的条目数
+    entry_count: usize,
+    /// 日志文件的长度（字节），追加失败时截断回这个长度
+    journal_len: u64,
+    compaction_threshold: usize,
+    /// 正在进行的管理器操作，最外层的在前
+    operations: Vec<String>,
+    _lock: DirectoryLock,
+}
+
+impl JournalStorage {
+    /// 打开日志，先加载快照再重放日志；两个文件都不存在时从空数据开始
+    ///
+    /// 日志所在目录已被其他进程锁定时返回错误。
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(journal_path: P, snapshot_path: Q) -> AppResult<Self> {
+        let journal_path = journal_path.as_ref().to_path_buf();
+        let snapshot_path = snapshot_path.as_ref().to_path_buf();
+        let directory = match journal_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let lock = DirectoryLock::try_acquire(&directory)?;
+
+        let mut cache = MemoryStorage::new();
+        let mut snapshot_seq = 0;
+        if file_exists(&snapshot_path) {
+            let data = decode_snapshot(&read_file_to_string(&snapshot_path)?)?;
+            snapshot_seq = data.journal_seq.unwrap_or(0);
+            cache.merge(data)?;
+        }
+
+        let mut storage = JournalStorage {
+            journal_path,
+            snapshot_path,
+            cache,
+            last_seq: snapshot_seq,
+            entry_count: 0,
+            journal_len: 0,
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+            operations: Vec::new(),
+            _lock: lock,
+        };
+
+        let content = if file_exists(&storage.journal_path) {
+            read_file_to_string(&storage.journal_path)?
+        } else {
+            String::new()
+        };
+        // 追加中途退出时末行没有换行符，这样的记录没有生效
+        let complete = &content[..content.rfind('\n').map_or(0, |end| end + 1)];
+        let mut lines = complete.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        match lines.next().map(|(_, header)| header.trim()) {
+            None => storage.reset_journal()?,
+            Some(JOURNAL_HEADER) => {
+                for (index, line) in lines {
+                    storage.replay_entry(line, snapshot_seq).map_err(|e| AppError::CorruptedData(
+                        format!("日志第{}行: {}", index + 1, e)
+                    ))?;
+                }
+                storage.cache.check_references()?;
+                if complete.len() < content.len() {
+                    truncate_file(&storage.journal_path, complete.len() as u64)?;
+                }
+                storage.journal_len = complete.len() as u64;
+            }
+            Some(_) => return Err(AppError::InvalidInput("缺少日志文件头".to_string())),
+        }
+        Ok(storage)
+    }
+
+    /// 设置自动压缩的阈值
+    pub fn with_compaction_threshold(mut self, threshold: usize) -> Self {
+        self.compaction_threshold = threshold.max(1);
+        self
+    }
+
+    /// 快照之后日志中的条目数
+    pub fn entry_count(&self) -> usize {
+        self.entry_count
+    }
+
+    /// 把当前数据写成快照并清空日志
+    ///
+    /// 快照记录最后一条日志的序号；写完快照后清空日志之前崩溃，
+    /// 下次打开时这些日志会被跳过。
+    pub fn compact(&mut self) -> AppResult<()> {
+        let snapshot = encode_snapshot(
+            &self.cache.list_students(), &self.cache.list_courses(), &self.cache.list_terms(),
+            &self.cache.list_instructors(), &self.cache.list_programmes(),
+            SnapshotSettings::of(&self.cache), Some(self.last_seq),
+        );
+        write_string_to_file(&self.snapshot_path, &snapshot)?;
+        self.reset_journal()?;
+        self.entry_count = 0;
+        Ok(())
+    }
+
+    fn reset_journal(&mut self) -> AppResult<()> {
+        let header = format!("{}\n", JOURNAL_HEADER);
+        write_string_to_file(&self.journal_path, &header)?;
+        self.journal_len = header.len() as u64;
+        Ok(())
+    }
+
+    /// 重放一条日志，已经包含在快照中的条目跳过
+    fn replay_entry(&mut self, line: &str, snapshot_seq: u64) -> AppResult<()> {
+        let fields = decode_record(line);
+        if fields.len() != 4 {
+            return Err(AppError::InvalidInput(format!("日志记录应有4个字段，实际为{}个", fields.len())));
+        }
+        let seq: u64 = fields[0].parse()?;
+        if seq <= self.last_seq {
+            if seq <= snapshot_seq {
+                return Ok(());
+            }
+            return Err(AppError::ValidationError(format!("日志序号 {} 没有递增", seq)));
+        }
+        apply_entry(&mut self.cache, &fields[2], decode_snapshot(&fields[3])?)?;
+        self.last_seq = seq;
+        self.entry_count += 1;
+        Ok(())
+    }
+
+    /// 追加一条记在当前操作名下的日志，写入失败时截断掉可能已经写入的部分
+    fn append(&mut self, kind: &str, data: &str) -> AppResult<()> {
+        let seq = self.last_seq + 1;
+        let operation = self.operations.first().map_or("", String::as_str);
+        let mut line = encode_record(&[&seq.to_string(), operation, kind, data]);
+        line.pop();
+        if let Err(e) = append_line_to_file(&self.journal_path, &line) {
+            let _ = truncate_file(&self.journal_path, self.journal_len);
+            return Err(e);
+        }
+        self.journal_len += line.len() as u64 + 1;
+        self.last_seq = seq;
+        self.entry_count += 1;
+        Ok(())
+    }
+
+    /// 日志达到阈值时压缩
+    fn compact_if_needed(&mut self) {
+        if self.entry_count >= self.compaction_threshold {
+            // 修改已经写入日志，压缩失败不影响它；报告错误，下一次写入时再尝试
+            if let Err(e) = self.compact() {
+                eprintln!("警告: 压缩操作日志 {} 失败: {}", self.journal_path.display(), e);
+            }
+        }
+    }
+}
+
+/// 把一条日志中的对象写入缓存，数据中必须恰好有一个该类型的对象
+fn apply_entry(cache: &mut MemoryStorage, kind: &str, data: SnapshotData) -> AppResult<()> {
+    let SnapshotData {
+        mut students, mut courses, mut terms, mut instructors, mut programmes, grading_scale, attendance_threshold, ..
+    } = data;
+    let count = students.len() + courses.len() + terms.len() + instructors.len() + programmes.len();
+    let applied = match (kind, count) {
+        ("student", 1) => students.pop().map(|student| { cache.insert_student(student); }),
+        ("course", 1) => courses.pop().map(|course| { cache.insert_course(course); }),
+        ("term", 1) => terms.pop().map(|term| { cache.insert_term(term); }),
+        ("instructor", 1) => instructors.pop().map(|instructor| { cache.insert_instructor(instructor); }),
+        ("programme", 1) => programmes.pop().map(|programme| { cache.insert_programme(programme); }),
+        // 标准等级制和默认的出勤率警戒线在快照中不写出
+        ("scale", 0) => {
+            cache.replace_grading_scale(grading_scale.unwrap_or_default());
+            Some(())
+        }
+        ("threshold", 0) => {
+            cache.replace_attendance_threshold(attendance_threshold.unwrap_or(DEFAULT_ATTENDANCE_THRESHOLD));
+            Some(())
+        }
+        _ => None,
+    };
+    applied.ok_or_else(|| AppError::InvalidInput(format!("'{}' 日志记录的数据不正确", kind)))
+}
+
+impl Storage for JournalStorage {
+    fn get_student(&self, id: u32) -> Option<&Student> {
+        self.cache.get_student(id)
+    }
+
+    fn list_students(&self) -> Vec<&Student> {
+        self.cache.list_students()
+    }
+
+    fn save_student(&mut self, student: Student) -> AppResult<()> {
+        self.append("student", &encode_snapshot(&[&student], &[], &[], &[], &[], SnapshotSettings::default(), None))?;
+        self.cache.insert_student(student);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn get_course(&self, id: u32) -> Option<&Course> {
+        self.cache.get_course(id)
+    }
+
+    fn list_courses(&self) -> Vec<&Course> {
+        self.cache.list_courses()
+    }
+
+    fn save_course(&mut self, course: Course) -> AppResult<()> {
+        self.append("course", &encode_snapshot(&[], &[&course], &[], &[], &[], SnapshotSettings::default(), None))?;
+        self.cache.insert_course(course);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn get_term(&self, id: u32) -> Option<&Term> {
+        self.cache.get_term(id)
+    }
+
+    fn list_terms(&self) -> Vec<&Term> {
+        self.cache.list_terms()
+    }
+
+    fn save_term(&mut self, term: Term) -> AppResult<()> {
+        self.append("term", &encode_snapshot(&[], &[], &[&term], &[], &[], SnapshotSettings::default(), None))?;
+        self.cache.insert_term(term);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor> {
+        self.cache.get_instructor(id)
+    }
+
+    fn list_instructors(&self) -> Vec<&Instructor> {
+        self.cache.list_instructors()
+    }
+
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        self.append("instructor", &encode_snapshot(&[], &[], &[], &[&instructor], &[], SnapshotSettings::default(), None))?;
+        self.cache.insert_instructor(instructor);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn get_programme(&self, id: u32) -> Option<&Programme> {
+        self.cache.get_programme(id)
+    }
+
+    fn list_programmes(&self) -> Vec<&Programme> {
+        self.cache.list_programmes()
+    }
+
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()> {
+        self.append("programme", &encode_snapshot(&[], &[], &[], &[], &[&programme], SnapshotSettings::default(), None))?;
+        self.cache.insert_programme(programme);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn grading_scale(&self) -> &GradingScale {
+        self.cache.grading_scale()
+    }
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        let settings = SnapshotSettings { grading_scale: Some(&scale), ..SnapshotSettings::default() };
+        self.append("scale", &encode_snapshot(&[], &[], &[], &[], &[], settings, None))?;
+        self.cache.replace_grading_scale(scale);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn attendance_threshold(&self) -> f64 {
+        self.cache.attendance_threshold()
+    }
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        let settings = SnapshotSettings { attendance_threshold: Some(threshold), ..SnapshotSettings::default() };
+        self.append("threshold", &encode_snapshot(&[], &[], &[], &[], &[], settings, None))?;
+        self.cache.replace_attendance_threshold(threshold);
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn begin_operation(&mut self, name: &str) {
+        self.operations.push(name.to_string());
+    }
+
+    fn end_operation(&mut self) {
+        self.operations.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::attendance::AttendanceStatus;
+    use crate::course::enrollment::EnrollmentManager;
+    use crate::course::programme::Programme;
+    use crate::student::contact::{ContactInfo, Guardian};
+    use crate::student::status::StudentStatus;
+    use tempfile::tempdir;
+
+    fn open(dir: &Path) -> JournalStorage {
+        JournalStorage::open(dir.join("journal.log"), dir.join("snapshot.dat")).unwrap()
+    }
+
+    fn journal_lines(dir: &Path) -> usize {
+        read_file_to_string(dir.join("journal.log")).unwrap().lines().count() - 1
+    }
+
+    fn populate(manager: &mut EnrollmentManager) {
+        manager.add_instructor(Instructor::new(1, "王老师".to_string()).unwrap()).unwrap();
+        manager.add_course(Course::new(101, "Rust编程".to_string(), 3).unwrap()).unwrap();
+        manager.assign_instructor(101, 1).unwrap();
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        student.add_grade("数学".to_string(), 88.0).unwrap();
+        manager.add_student(student).unwrap();
+        manager.enroll_student_in_course(1, 101).unwrap();
+        manager.add_grade(1, "物理".to_string(), 75.5).unwrap();
+    }
+
+    #[test]
+    fn test_every_change_is_journaled() {
+        let dir = tempdir().unwrap();
+        let mut manager = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        populate(&mut manager);
+        assert!(manager.add_grade(99, "数学".to_string(), 60.0).is_err());
+        assert_eq!(journal_lines(dir.path()), 6);
+
+        let contact = ContactInfo::new(Some("zhangsan@example.com".to_string()), None, None).unwrap();
+        manager.update_contact(1, contact).unwrap();
+        let phone = ContactInfo::new(None, Some("13800000000".to_string()), None).unwrap();
+        manager.add_guardian(1, Guardian::new("张父".to_string(), "父亲".to_string(), phone).unwrap()).unwrap();
+        manager.record_attendance(101, "2024-09-02".parse().unwrap(), 1, AttendanceStatus::Late).unwrap();
+        manager.change_student_status(1, StudentStatus::OnLeave, "2024-10-08".parse().unwrap()).unwrap();
+        let mut programme = Programme::new(1, "软件工程", 120).unwrap();
+        programme.add_required_course(101).unwrap();
+        manager.add_programme(programme).unwrap();
+        manager.set_grading_scale(GradingScale::five_level()).unwrap();
+        let expected = manager.to_snapshot_string();
+        drop(manager);
+
+        let reopened = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        assert_eq!(reopened.to_snapshot_string(), expected);
+        assert_eq!(reopened.get_student(1).unwrap().email(), Some("zhangsan@example.com"));
+        assert_eq!(reopened.get_student(1).unwrap().guardians.len(), 1);
+    }
+
+    #[test]
+    fn test_records_name_their_operation() {
+        let dir = tempdir().unwrap();
+        let mut manager = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        populate(&mut manager);
+        manager.add_course_grade(1, 101, "期中".to_string(), 90.0).unwrap();
+        drop(manager);
+
+        let journal = read_file_to_string(dir.path().join("journal.log")).unwrap();
+        let records: Vec<_> = journal.lines().skip(1).map(decode_record).collect();
+        let operations: Vec<_> = records.iter().map(|fields| (fields[1].as_str(), fields[2].as_str())).collect();
+        assert_eq!(operations, [
+            ("add_instructor", "instructor"),
+            ("add_course", "course"),
+            ("assign_instructor", "course"),
+            ("add_student", "student"),
+            ("enroll_student_in_course", "course"),
+            ("add_grade", "student"),
+            // 嵌套的 `update_student` 记在外层操作名下
+            ("add_course_grade", "student"),
+        ]);
+    }
+
+    #[test]
+    fn test_compaction_writes_snapshot() {
+        let dir = tempdir().unwrap();
+        let storage = open(dir.path()).with_compaction_threshold(3);
+        let mut manager = EnrollmentManager::with_storage(Box::new(storage));
+        populate(&mut manager);
+        let expected = manager.to_snapshot_string();
+        drop(manager);
+
+        assert!(file_exists(dir.path().join("snapshot.dat")));
+        assert_eq!(journal_lines(dir.path()), 0);
+        let reopened = open(dir.path());
+        assert_eq!(reopened.entry_count(), 0);
+        assert_eq!(EnrollmentManager::with_storage(Box::new(reopened)).to_snapshot_string(), expected);
+    }
+
+    #[test]
+    fn test_entries_in_snapshot_are_skipped() {
+        let dir = tempdir().unwrap();
+        let mut manager = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        populate(&mut manager);
+        let expected = manager.to_snapshot_string();
+        drop(manager);
+
+        // 模拟写完快照后、清空日志前崩溃
+        let journal = read_file_to_string(dir.path().join("journal.log")).unwrap();
+        open(dir.path()).compact().unwrap();
+        std::fs::write(dir.path().join("journal.log"), journal).unwrap();
+
+        let reopened = open(dir.path());
+        assert_eq!(reopened.entry_count(), 0);
+        let mut manager = EnrollmentManager::with_storage(Box::new(reopened));
+        assert_eq!(manager.to_snapshot_string(), expected);
+        manager.add_grade(1, "化学".to_string(), 66.0).unwrap();
+        let expected = manager.to_snapshot_string();
+        drop(manager);
+        assert_eq!(EnrollmentManager::with_storage(Box::new(open(dir.path()))).to_snapshot_string(), expected);
+    }
+
+    #[test]
+    fn test_incomplete_last_line_is_dropped() {
+        let dir = tempdir().unwrap();
+        let mut manager = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        populate(&mut manager);
+        let expected = manager.to_snapshot_string();
+        drop(manager);
+
+        let path = dir.path().join("journal.log");
+        let journal = read_file_to_string(&path).unwrap();
+        let last_line = journal.trim_end().rsplit('\n').next().unwrap();
+        std::fs::write(&path, format!("{}7{}", journal, &last_line[1..last_line.len() / 2])).unwrap();
+
+        let mut manager = EnrollmentManager::with_storage(Box::new(open(dir.path())));
+        assert_eq!(manager.to_snapshot_string(), expected);
+        assert_eq!(read_file_to_string(&path).unwrap(), journal);
+        manager.add_grade(1, "化学".to_string(), 66.0).unwrap();
+        drop(manager);
+        assert_eq!(journal_lines(dir.path()), 7);
+
+        // 完整但损坏的记录不会被跳过
+        std::fs::write(&path, format!("{}8\tadd_grade\tstudent\t损坏\n", journal)).unwrap();
+        assert!(JournalStorage::open(&path, dir.path().join("snapshot.dat")).is_err());
+    }
+}
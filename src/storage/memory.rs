@@ -0,0 +1,267 @@
+//! 内存存储后端
+
+use super::Storage;
+use crate::course::attendance::DEFAULT_ATTENDANCE_THRESHOLD;
+use crate::course::instructor::Instructor;
+use crate::course::persistence::SnapshotData;
+use crate::course::programme::Programme;
+use crate::course::term::Term;
+use crate::errors::{AppError, AppResult};
+use crate::student::grading_scale::GradingScale;
+use crate::{Course, Student};
+use std::collections::HashMap;
+
+/// 数据只保存在内存中，程序退出后丢失
+#[derive(Debug)]
+pub struct MemoryStorage {
+    students: HashMap<u32, Student>,
+    courses: HashMap<u32, Course>,
+    terms: HashMap<u32, Term>,
+    instructors: HashMap<u32, Instructor>,
+    programmes: HashMap<u32, Programme>,
+    grading_scale: GradingScale,
+    attendance_threshold: f64,
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        MemoryStorage {
+            students: HashMap::new(),
+            courses: HashMap::new(),
+            terms: HashMap::new(),
+            instructors: HashMap::new(),
+            programmes: HashMap::new(),
+            grading_scale: GradingScale::default(),
+            attendance_threshold: DEFAULT_ATTENDANCE_THRESHOLD,
+        }
+    }
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 合并快照数据，ID相同的记录被覆盖
+    ///
+    /// 合并后检查记录之间的引用，引用了不存在的学生、课程、学期或教师时返回错误。
+    pub(crate) fn merge(&mut self, data: SnapshotData) -> AppResult<()> {
+        self.insert_snapshot(data);
+        self.check_references()
+    }
+
+    /// 合并快照数据但不检查引用，用于分多次读入的数据，读完后再调用 `check_references`
+    pub(crate) fn insert_snapshot(&mut self, data: SnapshotData) {
+        for student in data.students {
+            self.insert_student(student);
+        }
+        for course in data.courses {
+            self.insert_course(course);
+        }
+        for term in data.terms {
+            self.insert_term(term);
+        }
+        for instructor in data.instructors {
+            self.insert_instructor(instructor);
+        }
+        for programme in data.programmes {
+            self.insert_programme(programme);
+        }
+        if let Some(scale) = data.grading_scale {
+            self.replace_grading_scale(scale);
+        }
+        if let Some(threshold) = data.attendance_threshold {
+            self.replace_attendance_threshold(threshold);
+        }
+    }
+
+    /// 检查记录之间的引用都指向存在的记录，并且培养方案的选修课组学分要求可以达到
+    pub(crate) fn check_references(&self) -> AppResult<()> {
+        let mut course_ids: Vec<&u32> = self.courses.keys().collect();
+        course_ids.sort();
+        for course in course_ids.into_iter().map(|id| &self.courses[id]) {
+            let students = course.student_ids().into_iter()
+                .chain(course.waitlist())
+                .chain(course.withdrawn_student_ids());
+            for student_id in students {
+                if !self.students.contains_key(&student_id) {
+                    return Err(dangling(format!("课程 {} 引用的学生ID {}", course.id, student_id)));
+                }
+            }
+            if let Some(instructor_id) = course.instructor_id.filter(|id| !self.instructors.contains_key(id)) {
+                return Err(dangling(format!("课程 {} 的教师ID {}", course.id, instructor_id)));
+            }
+            if let Some(term_id) = course.term_id.filter(|id| !self.terms.contains_key(id)) {
+                return Err(dangling(format!("课程 {} 的学期ID {}", course.id, term_id)));
+            }
+            let prerequisites = course.prerequisite().map(|p| p.referenced_courses()).unwrap_or_default();
+            if let Some(missing) = prerequisites.into_iter().find(|id| !self.courses.contains_key(id)) {
+                return Err(dangling(format!("课程 {} 的先修课程ID {}", course.id, missing)));
+            }
+        }
+
+        for student in self.students.values() {
+            for grade in &student.grades {
+                if let Some(term_id) = grade.term_id().filter(|id| !self.terms.contains_key(id)) {
+                    return Err(dangling(format!("学生 {} 的成绩 {} 的学期ID {}", student.id, grade.subject(), term_id)));
+                }
+                if let Some(course_id) = grade.course_id().filter(|id| !self.courses.contains_key(id)) {
+                    return Err(dangling(format!("学生 {} 的成绩 {} 的课程ID {}", student.id, grade.subject(), course_id)));
+                }
+            }
+        }
+
+        for programme in self.programmes.values() {
+            if let Some(missing) = programme.referenced_courses().into_iter().find(|id| !self.courses.contains_key(id)) {
+                return Err(dangling(format!("培养方案 {} 引用的课程ID {}", programme.id, missing)));
+            }
+            programme.check_courses(|id| self.courses.get(&id))
+                .map_err(|e| AppError::CorruptedData(format!("培养方案 {}: {}", programme.id, e)))?;
+        }
+        Ok(())
+    }
+
+    /// 插入学生，返回被覆盖的旧记录
+    pub(crate) fn insert_student(&mut self, student: Student) -> Option<Student> {
+        self.students.insert(student.id, student)
+    }
+
+    pub(crate) fn remove_student(&mut self, id: u32) -> Option<Student> {
+        self.students.remove(&id)
+    }
+
+    /// 插入课程，返回被覆盖的旧记录
+    pub(crate) fn insert_course(&mut self, course: Course) -> Option<Course> {
+        self.courses.insert(course.id, course)
+    }
+
+    pub(crate) fn remove_course(&mut self, id: u32) -> Option<Course> {
+        self.courses.remove(&id)
+    }
+
+    /// 插入学期，返回被覆盖的旧记录
+    pub(crate) fn insert_term(&mut self, term: Term) -> Option<Term> {
+        self.terms.insert(term.id, term)
+    }
+
+    pub(crate) fn remove_term(&mut self, id: u32) -> Option<Term> {
+        self.terms.remove(&id)
+    }
+
+    /// 插入教师，返回被覆盖的旧记录
+    pub(crate) fn insert_instructor(&mut self, instructor: Instructor) -> Option<Instructor> {
+        self.instructors.insert(instructor.id, instructor)
+    }
+
+    pub(crate) fn remove_instructor(&mut self, id: u32) -> Option<Instructor> {
+        self.instructors.remove(&id)
+    }
+
+    /// 插入培养方案，返回被覆盖的旧记录
+    pub(crate) fn insert_programme(&mut self, programme: Programme) -> Option<Programme> {
+        self.programmes.insert(programme.id, programme)
+    }
+
+    pub(crate) fn remove_programme(&mut self, id: u32) -> Option<Programme> {
+        self.programmes.remove(&id)
+    }
+
+    /// 替换等级制，返回原来的等级制
+    pub(crate) fn replace_grading_scale(&mut self, scale: GradingScale) -> GradingScale {
+        std::mem::replace(&mut self.grading_scale, scale)
+    }
+
+    /// 替换出勤率警戒线，返回原来的警戒线
+    pub(crate) fn replace_attendance_threshold(&mut self, threshold: f64) -> f64 {
+        std::mem::replace(&mut self.attendance_threshold, threshold)
+    }
+}
+
+fn dangling(reference: String) -> AppError {
+    AppError::CorruptedData(format!("{} 不存在", reference))
+}
+
+impl Storage for MemoryStorage {
+    fn get_student(&self, id: u32) -> Option<&Student> {
+        self.students.get(&id)
+    }
+
+    fn list_students(&self) -> Vec<&Student> {
+        self.students.values().collect()
+    }
+
+    fn save_student(&mut self, student: Student) -> AppResult<()> {
+        self.insert_student(student);
+        Ok(())
+    }
+
+    fn get_course(&self, id: u32) -> Option<&Course> {
+        self.courses.get(&id)
+    }
+
+    fn list_courses(&self) -> Vec<&Course> {
+        self.courses.values().collect()
+    }
+
+    fn save_course(&mut self, course: Course) -> AppResult<()> {
+        self.insert_course(course);
+        Ok(())
+    }
+
+    fn get_term(&self, id: u32) -> Option<&Term> {
+        self.terms.get(&id)
+    }
+
+    fn list_terms(&self) -> Vec<&Term> {
+        self.terms.values().collect()
+    }
+
+    fn save_term(&mut self, term: Term) -> AppResult<()> {
+        self.insert_term(term);
+        Ok(())
+    }
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor> {
+        self.instructors.get(&id)
+    }
+
+    fn list_instructors(&self) -> Vec<&Instructor> {
+        self.instructors.values().collect()
+    }
+
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        self.insert_instructor(instructor);
+        Ok(())
+    }
+
+    fn get_programme(&self, id: u32) -> Option<&Programme> {
+        self.programmes.get(&id)
+    }
+
+    fn list_programmes(&self) -> Vec<&Programme> {
+        self.programmes.values().collect()
+    }
+
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()> {
+        self.insert_programme(programme);
+        Ok(())
+    }
+
+    fn grading_scale(&self) -> &GradingScale {
+        &self.grading_scale
+    }
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        self.replace_grading_scale(scale);
+        Ok(())
+    }
+
+    fn attendance_threshold(&self) -> f64 {
+        self.attendance_threshold
+    }
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        self.replace_attendance_threshold(threshold);
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+//! 存储后端模块
+//!
+//! `EnrollmentManager` 通过 `Storage` 特征读写学生、课程、学期、教师、培养方案、
+//! 等级制和出勤率警戒线，可以运行在内存、文本文件、快照加操作日志或SQLite数据库（需要启用
+//! `sqlite` 特性）之上。
+
+pub mod file;
+pub mod journal;
+pub mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use file::FileStorage;
+pub use journal::JournalStorage;
+pub use memory::MemoryStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+use crate::course::instructor::Instructor;
+use crate::course::programme::Programme;
+use crate::course::term::Term;
+use crate::errors::AppResult;
+use crate::student::grading_scale::GradingScale;
+use crate::{Course, Student};
+use std::fmt::Debug;
+
+/// 学生、课程、学期、教师、培养方案、等级制和出勤率警戒线的存储后端
+///
+/// 读操作返回引用，所以持久化后端需要在内存中保留一份缓存；
+/// 写操作在数据真正落盘后才返回成功。
+pub trait Storage: Debug {
+    fn get_student(&self, id: u32) -> Option<&Student>;
+
+    fn list_students(&self) -> Vec<&Student>;
+
+    /// 保存学生，ID已存在时覆盖
+    fn save_student(&mut self, student: Student) -> AppResult<()>;
+
+    fn get_course(&self, id: u32) -> Option<&Course>;
+
+    fn list_courses(&self) -> Vec<&Course>;
+
+    /// 保存课程，ID已存在时覆盖
+    fn save_course(&mut self, course: Course) -> AppResult<()>;
+
+    fn get_term(&self, id: u32) -> Option<&Term>;
+
+    fn list_terms(&self) -> Vec<&Term>;
+
+    /// 保存学期，ID已存在时覆盖
+    fn save_term(&mut self, term: Term) -> AppResult<()>;
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor>;
+
+    fn list_instructors(&self) -> Vec<&Instructor>;
+
+    /// 保存教师，ID已存在时覆盖
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()>;
+
+    fn get_programme(&self, id: u32) -> Option<&Programme>;
+
+    fn list_programmes(&self) -> Vec<&Programme>;
+
+    /// 保存培养方案，ID已存在时覆盖
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()>;
+
+    /// 管理器使用的等级制，没有保存过时为标准等级制
+    fn grading_scale(&self) -> &GradingScale;
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()>;
+
+    /// 管理器使用的出勤率警戒线（百分比），没有保存过时为 `DEFAULT_ATTENDANCE_THRESHOLD`
+    fn attendance_threshold(&self) -> f64;
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()>;
+
+    /// 开始一个管理器操作，到对应的 `end_operation` 为止的写入都属于这个操作
+    ///
+    /// 操作可以嵌套，写入属于最外层的操作。需要记录操作的后端（如操作日志）
+    /// 实现这一对方法，默认什么也不做。
+    fn begin_operation(&mut self, _name: &str) {}
+
+    /// 结束最近开始的操作
+    fn end_operation(&mut self) {}
+}
+
+/// 每个存储后端都要通过的同一套测试
+#[cfg(test)]
+macro_rules! storage_test_suite {
+    ($name:ident, $open:expr, persistent) => {
+        storage_test_suite!($name, $open, {
+            #[test]
+            fn test_data_survives_reopen() {
+                let dir = tempdir().unwrap();
+                let mut first = manager(dir.path());
+                populate(&mut first);
+                let expected = first.to_snapshot_string();
+                drop(first);
+
+                let reopened = manager(dir.path());
+                assert_eq!(reopened.list_all_students().len(), 2);
+                assert_eq!(reopened.attendance_threshold(), 65.0);
+                assert_eq!(reopened.to_snapshot_string(), expected);
+            }
+
+            #[test]
+            fn test_failed_changes_are_not_persisted() {
+                let dir = tempdir().unwrap();
+                let mut first = manager(dir.path());
+                populate(&mut first);
+                let expected = first.to_snapshot_string();
+                attempt_invalid_changes(&mut first);
+                drop(first);
+
+                assert_eq!(manager(dir.path()).to_snapshot_string(), expected);
+            }
+        });
+    };
+    ($name:ident, $open:expr) => {
+        storage_test_suite!($name, $open, {});
+    };
+    ($name:ident, $open:expr, { $($extra:item)* }) => {
+        mod $name {
+            use super::*;
+            use crate::course::assessment::AssessmentComponent;
+            use crate::course::attendance::AttendanceStatus;
+            use crate::course::enrollment::EnrollmentManager;
+            use crate::course::programme::ElectivePool;
+            use crate::course::schedule::TimeSlot;
+            use crate::student::amendment::{GradeChange, GradeValue};
+            use crate::student::contact::{ContactInfo, Guardian};
+            use crate::student::grade::GradeMark;
+            use crate::student::status::StudentStatus;
+            use tempfile::tempdir;
+
+            fn manager(dir: &std::path::Path) -> EnrollmentManager {
+                let open: fn(&std::path::Path) -> Box<dyn Storage> = $open;
+                EnrollmentManager::with_storage(open(dir))
+            }
+
+            fn populate(manager: &mut EnrollmentManager) {
+                manager.add_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+                manager.add_student(Student::new(2, "李四".to_string(), 19).unwrap()).unwrap();
+                manager.add_instructor(Instructor::new(1, "王老师".to_string()).unwrap()).unwrap();
+                let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+                course.instructor_id = Some(1);
+                manager.add_course(course).unwrap();
+                manager.enroll_student_in_course(1, 101).unwrap();
+                manager.add_grade(1, "数学".to_string(), 91.5).unwrap();
+                let mut programme = Programme::new(1, "软件工程", 120).unwrap();
+                programme.add_required_course(101).unwrap();
+                manager.add_programme(programme).unwrap();
+                manager.set_grading_scale(GradingScale::five_level()).unwrap();
+                manager.set_attendance_threshold(65.0).unwrap();
+
+                // 每种对象的子记录都要经过存储后端保存和读回
+                let term = Term::new(1, "2024秋".to_string(), "2024-09-01".parse().unwrap(), "2025-01-15".parse().unwrap()).unwrap();
+                manager.add_term(term).unwrap();
+                manager.set_term_deadlines(1, Some("2024-09-15".parse().unwrap()), Some("2024-11-15".parse().unwrap())).unwrap();
+                let contact = ContactInfo::new(Some("zhangsan@example.com".to_string()), None, Some("北京市".to_string())).unwrap();
+                manager.update_contact(1, contact).unwrap();
+                let contact = ContactInfo::new(None, Some("13900000000".to_string()), None).unwrap();
+                manager.add_guardian(2, Guardian::new("李父".to_string(), "父亲".to_string(), contact).unwrap()).unwrap();
+
+                let mut course = Course::new(102, "数据库".to_string(), 2).unwrap();
+                course.set_assessment_components(vec![
+                    AssessmentComponent::new("平时", 40.0).unwrap(),
+                    AssessmentComponent::new("期末", 60.0).unwrap(),
+                ]).unwrap();
+                manager.add_course(course).unwrap();
+                manager.assign_course_to_term(102, 1).unwrap();
+                let slot = TimeSlot::new("周二".parse().unwrap(), "14:00".parse().unwrap(), "15:40".parse().unwrap(), "B202").unwrap();
+                manager.add_course_time_slot(102, slot).unwrap();
+                manager.enroll_student_in_course(2, 102).unwrap();
+                manager.set_course_capacity(102, Some(1)).unwrap();
+                manager.enroll_student_in_course(1, 102).unwrap();
+                manager.set_course_prerequisite(102, Some("course:101".parse().unwrap())).unwrap();
+                manager.record_component_score(102, 2, "期末", 82.5).unwrap();
+                manager.record_attendance(102, "2024-09-03".parse().unwrap(), 2, AttendanceStatus::Late).unwrap();
+                manager.add_course_grade(2, 102, "实验".to_string(), 88.0).unwrap();
+                let change = GradeChange::new(GradeValue::Score(90.0), "漏登附加题", "王老师", "2024-12-20".parse().unwrap()).unwrap();
+                manager.amend_course_grade(2, 102, "实验", change).unwrap();
+                manager.add_course_mark(2, 102, "期中".to_string(), GradeMark::Incomplete).unwrap();
+
+                manager.add_course(Course::new(103, "编译原理".to_string(), 2).unwrap()).unwrap();
+                manager.assign_course_to_term(103, 1).unwrap();
+                manager.enroll_student_in_course(2, 103).unwrap();
+                manager.withdraw_from_course(2, 103, "2024-10-08".parse().unwrap()).unwrap();
+                // 在下一学期重修后再次退出，两次退出都要保存
+                let term = Term::new(2, "2025春".to_string(), "2025-02-20".parse().unwrap(), "2025-07-01".parse().unwrap()).unwrap();
+                manager.add_term(term).unwrap();
+                manager.assign_course_to_term(103, 2).unwrap();
+                manager.enroll_student_in_course(2, 103).unwrap();
+                manager.withdraw_from_course(2, 103, "2025-03-10".parse().unwrap()).unwrap();
+                let mut programme = Programme::new(2, "数据科学", 100).unwrap();
+                programme.add_elective_pool(ElectivePool::new("数据方向", [102, 103], 2).unwrap()).unwrap();
+                manager.add_programme(programme).unwrap();
+                manager.change_student_status(2, StudentStatus::OnLeave, "2024-10-09".parse().unwrap()).unwrap();
+            }
+
+            /// 每种对象各尝试几个应当失败的修改
+            fn attempt_invalid_changes(manager: &mut EnrollmentManager) {
+                assert!(manager.set_attendance_threshold(120.0).is_err());
+                assert!(manager.add_student(Student::new(1, "重复".to_string(), 20).unwrap()).is_err());
+                let contact = ContactInfo::new(Some("ZhangSan@example.com".to_string()), None, None).unwrap();
+                assert!(manager.add_student(Student::new(3, "王五".to_string(), 20).unwrap().with_contact(contact)).is_err());
+                assert!(manager.change_student_status(2, StudentStatus::OnLeave, "2024-10-01".parse().unwrap()).is_err());
+
+                assert!(manager.add_course(Course::new(101, "重复".to_string(), 3).unwrap()).is_err());
+                let mut course = Course::new(104, "操作系统".to_string(), 3).unwrap();
+                course.instructor_id = Some(9);
+                assert!(manager.add_course(course).is_err());
+                assert!(manager.set_course_prerequisite(102, Some("course:999".parse().unwrap())).is_err());
+                assert!(manager.set_course_capacity(102, Some(0)).is_err());
+
+                let term = Term::new(1, "重复".to_string(), "2025-02-01".parse().unwrap(), "2025-06-30".parse().unwrap()).unwrap();
+                assert!(manager.add_term(term).is_err());
+                assert!(manager.set_term_deadlines(1, Some("2025-03-01".parse().unwrap()), None).is_err());
+
+                assert!(manager.add_instructor(Instructor::new(1, "重复".to_string()).unwrap()).is_err());
+                assert!(manager.assign_instructor(102, 9).is_err());
+
+                assert!(manager.add_programme(Programme::new(1, "重复", 120).unwrap()).is_err());
+                let mut programme = Programme::new(3, "人工智能", 120).unwrap();
+                programme.add_required_course(999).unwrap();
+                assert!(manager.add_programme(programme).is_err());
+            }
+
+            #[test]
+            fn test_add_and_query() {
+                let dir = tempdir().unwrap();
+                let mut manager = manager(dir.path());
+                populate(&mut manager);
+
+                assert_eq!(manager.list_all_students().len(), 2);
+                assert_eq!(manager.get_course(101).unwrap().student_count(), 1);
+                assert_eq!(manager.get_student(1).unwrap().average_grade(), 91.5);
+                assert_eq!(manager.teaching_load(1).unwrap().student_count, 1);
+                assert_eq!(manager.get_programme(1).unwrap().required_courses(), vec![101]);
+                assert_eq!(manager.grading_scale(), &GradingScale::five_level());
+                assert_eq!(manager.attendance_threshold(), 65.0);
+                assert!(manager.get_student(3).is_none());
+            }
+
+            #[test]
+            fn test_failed_changes_leave_data_unchanged() {
+                let dir = tempdir().unwrap();
+                let mut manager = manager(dir.path());
+                populate(&mut manager);
+                let expected = manager.to_snapshot_string();
+
+                attempt_invalid_changes(&mut manager);
+                assert_eq!(manager.to_snapshot_string(), expected);
+            }
+
+            #[test]
+            fn test_rejects_duplicates_and_unknown_ids() {
+                let dir = tempdir().unwrap();
+                let mut manager = manager(dir.path());
+                populate(&mut manager);
+
+                assert!(manager.add_student(Student::new(1, "重复".to_string(), 20).unwrap()).is_err());
+                assert!(manager.enroll_student_in_course(1, 101).is_err());
+                assert!(manager.enroll_student_in_course(9, 101).is_err());
+                assert!(manager.add_grade(9, "数学".to_string(), 60.0).is_err());
+                assert_eq!(manager.get_course(101).unwrap().student_count(), 1);
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    storage_test_suite!(memory_backend, |_| Box::new(MemoryStorage::new()));
+    storage_test_suite!(file_backend, |dir| {
+        Box::new(FileStorage::open(dir.join("data.txt")).unwrap())
+    }, persistent);
+    storage_test_suite!(journal_backend, |dir| {
+        Box::new(JournalStorage::open(dir.join("journal.log"), dir.join("snapshot.dat")).unwrap())
+    }, persistent);
+    #[cfg(feature = "sqlite")]
+    storage_test_suite!(sqlite_backend, |dir| {
+        Box::new(SqliteStorage::open(dir.join("data.db")).unwrap())
+    }, persistent);
+
+    #[test]
+    fn test_file_storage_locks_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::open(dir.path().join("data.txt")).unwrap();
+        assert!(FileStorage::open(dir.path().join("data.txt")).is_err());
+        drop(storage);
+        assert!(FileStorage::open(dir.path().join("data.txt")).is_ok());
+    }
+}
@@ -0,0 +1,919 @@
+//! SQLite存储后端（需要启用 `sqlite` 特性）
+//!
+//! 学生、课程、学期、教师和培养方案各有一张表，它们的子记录（监护人、成绩、
+//! 注册名单、考勤等）分别保存在单独的表中，可以直接用SQL查询。表之间用外键关联，
+//! 外键在事务提交时检查：引用了不存在的记录时整个写操作回滚，缓存不变。
+//!
+//! 数据库结构的版本记录在 `PRAGMA user_version` 中，当前为1；
+//! 新建的数据库（版本0）在打开时建立表结构。
+
+use super::memory::MemoryStorage;
+use super::Storage;
+use crate::course::assessment::AssessmentComponent;
+use crate::course::attendance::check_attendance_threshold;
+use crate::course::instructor::Instructor;
+use crate::course::programme::{ElectivePool, Programme};
+use crate::course::schedule::TimeSlot;
+use crate::course::term::Term;
+use crate::errors::{AppError, AppResult};
+use crate::student::amendment::{GradeAmendment, GradeChange};
+use crate::student::contact::{ContactInfo, Guardian};
+use crate::student::grade::Grade;
+use crate::student::grading_scale::{GradeBand, GradingScale};
+use crate::{Course, Student};
+use rusqlite::{params, Connection, Row};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+/// 当前的数据库结构版本
+const SCHEMA_VERSION: u32 = 1;
+
+/// 当前版本的表结构
+///
+/// 子记录的 `position` 列保存记录在所属对象中的顺序（从0开始）。
+const SCHEMA: &str = "
+CREATE TABLE terms (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    add_drop_deadline TEXT,
+    withdrawal_deadline TEXT,
+    CHECK (start_date <= end_date)
+);
+CREATE TABLE instructors (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+CREATE TABLE students (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    email TEXT UNIQUE COLLATE NOCASE,
+    phone TEXT,
+    address TEXT
+);
+CREATE TABLE guardians (
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    relationship TEXT NOT NULL,
+    email TEXT,
+    phone TEXT,
+    address TEXT,
+    PRIMARY KEY (student_id, position),
+    UNIQUE (student_id, name)
+);
+CREATE TABLE status_changes (
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    date TEXT NOT NULL,
+    PRIMARY KEY (student_id, position)
+);
+CREATE TABLE courses (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    credits INTEGER NOT NULL CHECK (credits BETWEEN 1 AND 10),
+    instructor_id INTEGER REFERENCES instructors (id) DEFERRABLE INITIALLY DEFERRED,
+    term_id INTEGER REFERENCES terms (id) DEFERRABLE INITIALLY DEFERRED,
+    capacity INTEGER CHECK (capacity > 0),
+    prerequisite TEXT
+);
+CREATE TABLE grades (
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    subject TEXT NOT NULL,
+    score REAL CHECK (score BETWEEN 0 AND 100),
+    letter TEXT,
+    mark TEXT,
+    term_id INTEGER REFERENCES terms (id) DEFERRABLE INITIALLY DEFERRED,
+    course_id INTEGER REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    withdrawn INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (student_id, position),
+    CHECK ((score IS NULL) = (letter IS NULL) AND (score IS NULL) <> (mark IS NULL))
+);
+CREATE TABLE grade_amendments (
+    student_id INTEGER NOT NULL,
+    grade_position INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    old_value TEXT NOT NULL,
+    new_value TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    amended_by TEXT NOT NULL,
+    date TEXT NOT NULL,
+    PRIMARY KEY (student_id, grade_position, position),
+    FOREIGN KEY (student_id, grade_position) REFERENCES grades (student_id, position)
+        DEFERRABLE INITIALLY DEFERRED
+);
+CREATE TABLE enrollments (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    PRIMARY KEY (course_id, student_id)
+);
+CREATE TABLE waitlist (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    PRIMARY KEY (course_id, position),
+    UNIQUE (course_id, student_id)
+);
+CREATE TABLE withdrawals (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    date TEXT NOT NULL,
+    PRIMARY KEY (course_id, student_id, date)
+);
+CREATE TABLE time_slots (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    room TEXT NOT NULL,
+    PRIMARY KEY (course_id, position),
+    CHECK (start_time < end_time)
+);
+CREATE TABLE assessment_components (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    weight REAL NOT NULL CHECK (weight > 0),
+    PRIMARY KEY (course_id, position),
+    UNIQUE (course_id, name)
+);
+CREATE TABLE component_scores (
+    course_id INTEGER NOT NULL,
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    component TEXT NOT NULL,
+    score REAL NOT NULL CHECK (score BETWEEN 0 AND 100),
+    PRIMARY KEY (course_id, student_id, component),
+    FOREIGN KEY (course_id, component) REFERENCES assessment_components (course_id, name)
+        DEFERRABLE INITIALLY DEFERRED
+);
+CREATE TABLE attendance (
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    session TEXT NOT NULL,
+    student_id INTEGER NOT NULL REFERENCES students (id) DEFERRABLE INITIALLY DEFERRED,
+    status TEXT NOT NULL,
+    PRIMARY KEY (course_id, session, student_id)
+);
+CREATE TABLE programmes (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    min_credits INTEGER NOT NULL
+);
+CREATE TABLE programme_required_courses (
+    programme_id INTEGER NOT NULL REFERENCES programmes (id) DEFERRABLE INITIALLY DEFERRED,
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    PRIMARY KEY (programme_id, course_id)
+);
+CREATE TABLE elective_pools (
+    programme_id INTEGER NOT NULL REFERENCES programmes (id) DEFERRABLE INITIALLY DEFERRED,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    min_credits INTEGER NOT NULL CHECK (min_credits > 0),
+    PRIMARY KEY (programme_id, position),
+    UNIQUE (programme_id, name)
+);
+CREATE TABLE elective_pool_courses (
+    programme_id INTEGER NOT NULL,
+    pool_position INTEGER NOT NULL,
+    course_id INTEGER NOT NULL REFERENCES courses (id) DEFERRABLE INITIALLY DEFERRED,
+    PRIMARY KEY (programme_id, pool_position, course_id),
+    FOREIGN KEY (programme_id, pool_position) REFERENCES elective_pools (programme_id, position)
+        DEFERRABLE INITIALLY DEFERRED
+);
+CREATE TABLE grading_scale (
+    min_score REAL PRIMARY KEY,
+    label TEXT NOT NULL,
+    letter TEXT NOT NULL,
+    gpa REAL NOT NULL
+);
+CREATE TABLE settings (
+    name TEXT PRIMARY KEY,
+    value REAL NOT NULL
+);
+";
+
+/// 打开时把所有数据读入内存缓存，写操作在一个事务中先写数据库再更新缓存
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Connection,
+    cache: MemoryStorage,
+}
+
+impl SqliteStorage {
+    /// 打开（或创建）数据库文件
+    pub fn open<P: AsRef<Path>>(path: P) -> AppResult<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// 打开一个只存在于内存中的数据库，主要用于测试
+    pub fn open_in_memory() -> AppResult<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut connection: Connection) -> AppResult<Self> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(AppError::StorageError(format!(
+                "数据库结构版本 {} 高于支持的版本 {}", version, SCHEMA_VERSION
+            )));
+        }
+        if version < SCHEMA_VERSION {
+            let transaction = connection.transaction()?;
+            create_schema(&transaction)?;
+            transaction.commit()?;
+        }
+
+        let cache = load_tables(&connection)?;
+        cache.check_references()?;
+        Ok(SqliteStorage { connection, cache })
+    }
+
+    /// 在一个事务中执行写操作，任何一步失败（包括提交时的外键检查）都整体回滚
+    fn write(&mut self, operation: impl FnOnce(&Connection) -> AppResult<()>) -> AppResult<()> {
+        let transaction = self.connection.transaction()?;
+        operation(&transaction)?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+fn create_schema(connection: &Connection) -> AppResult<()> {
+    connection.execute_batch(SCHEMA)?;
+    connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(())
+}
+
+/// 对查询结果的每一行调用 `f`
+fn for_each_row(
+    connection: &Connection,
+    sql: &str,
+    mut f: impl FnMut(&Row) -> AppResult<()>,
+) -> AppResult<()> {
+    let mut statement = connection.prepare(sql)?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        f(row)?;
+    }
+    Ok(())
+}
+
+/// 读取以文本保存的列并解析
+fn parse_column<T>(row: &Row, index: usize) -> AppResult<T>
+where
+    T: FromStr,
+    AppError: From<T::Err>,
+{
+    Ok(row.get::<_, String>(index)?.parse()?)
+}
+
+/// 读取可以为空的文本列并解析
+fn parse_optional_column<T>(row: &Row, index: usize) -> AppResult<Option<T>>
+where
+    T: FromStr,
+    AppError: From<T::Err>,
+{
+    match row.get::<_, Option<String>>(index)? {
+        Some(text) => Ok(Some(text.parse()?)),
+        None => Ok(None),
+    }
+}
+
+fn find<'a, T>(map: &'a mut BTreeMap<u32, T>, id: u32, kind: &str) -> AppResult<&'a mut T> {
+    map.get_mut(&id)
+        .ok_or_else(|| AppError::NotFound(format!("{}ID {} 不存在", kind, id)))
+}
+
+/// 读取所有表，构建内存缓存（不检查引用）
+fn load_tables(connection: &Connection) -> AppResult<MemoryStorage> {
+    let mut cache = MemoryStorage::new();
+    for term in load_terms(connection)? {
+        cache.insert_term(term);
+    }
+    for_each_row(connection, "SELECT id, name FROM instructors ORDER BY id", |row| {
+        cache.insert_instructor(Instructor::new(row.get(0)?, row.get(1)?)?);
+        Ok(())
+    })?;
+    for student in load_students(connection)?.into_values() {
+        cache.insert_student(student);
+    }
+    for course in load_courses(connection)?.into_values() {
+        cache.insert_course(course);
+    }
+    for programme in load_programmes(connection)?.into_values() {
+        cache.insert_programme(programme);
+    }
+    if let Some(scale) = load_grading_scale(connection)? {
+        cache.replace_grading_scale(scale);
+    }
+    for_each_row(connection, "SELECT value FROM settings WHERE name = 'attendance_threshold'", |row| {
+        let threshold = row.get(0)?;
+        check_attendance_threshold(threshold)?;
+        cache.replace_attendance_threshold(threshold);
+        Ok(())
+    })?;
+    Ok(cache)
+}
+
+fn load_terms(connection: &Connection) -> AppResult<Vec<Term>> {
+    let mut terms = Vec::new();
+    for_each_row(
+        connection,
+        "SELECT id, name, start_date, end_date, add_drop_deadline, withdrawal_deadline FROM terms ORDER BY id",
+        |row| {
+            let mut term = Term::new(row.get(0)?, row.get(1)?, parse_column(row, 2)?, parse_column(row, 3)?)?;
+            term.set_deadlines(parse_optional_column(row, 4)?, parse_optional_column(row, 5)?)?;
+            terms.push(term);
+            Ok(())
+        },
+    )?;
+    Ok(terms)
+}
+
+fn load_students(connection: &Connection) -> AppResult<BTreeMap<u32, Student>> {
+    let mut students = BTreeMap::new();
+    for_each_row(connection, "SELECT id, name, age, email, phone, address FROM students ORDER BY id", |row| {
+        let contact = ContactInfo::new(row.get(3)?, row.get(4)?, row.get(5)?)?;
+        let student = Student::new(row.get(0)?, row.get(1)?, row.get(2)?)?.with_contact(contact);
+        students.insert(student.id, student);
+        Ok(())
+    })?;
+
+    for_each_row(
+        connection,
+        "SELECT student_id, name, relationship, email, phone, address FROM guardians ORDER BY student_id, position",
+        |row| {
+            let contact = ContactInfo::new(row.get(3)?, row.get(4)?, row.get(5)?)?;
+            let guardian = Guardian::new(row.get(1)?, row.get(2)?, contact)?;
+            find(&mut students, row.get(0)?, "学生")?.add_guardian(guardian)
+        },
+    )?;
+
+    for_each_row(
+        connection,
+        "SELECT student_id, status, date FROM status_changes ORDER BY student_id, position",
+        |row| find(&mut students, row.get(0)?, "学生")?.change_status(parse_column(row, 1)?, parse_column(row, 2)?),
+    )?;
+
+    // （学生ID，成绩序号）-> 成绩在学生成绩列表中的下标
+    let mut grade_indexes: HashMap<(u32, u32), usize> = HashMap::new();
+    for_each_row(
+        connection,
+        "SELECT student_id, position, subject, score, letter, mark, term_id, course_id, withdrawn
+         FROM grades ORDER BY student_id, position",
+        |row| {
+            let student_id: u32 = row.get(0)?;
+            let subject: String = row.get(2)?;
+            let mut grade = match (row.get::<_, Option<f64>>(3)?, row.get::<_, Option<String>>(4)?) {
+                (Some(score), Some(letter)) => Grade::with_letter_grade(subject, score, letter.parse()?)?,
+                _ => Grade::with_mark(subject, parse_column(row, 5)?)?,
+            };
+            if let Some(term_id) = row.get(6)? {
+                grade = grade.in_term(term_id);
+            }
+            if let Some(course_id) = row.get(7)? {
+                grade = grade.for_course(course_id);
+            }
+            if row.get(8)? {
+                grade.mark_withdrawn()?;
+            }
+            let grades = &mut find(&mut students, student_id, "学生")?.grades;
+            grade_indexes.insert((student_id, row.get(1)?), grades.len());
+            grades.push(grade);
+            Ok(())
+        },
+    )?;
+
+    let mut amendments: BTreeMap<(u32, usize), Vec<GradeAmendment>> = BTreeMap::new();
+    for_each_row(
+        connection,
+        "SELECT student_id, grade_position, old_value, new_value, reason, amended_by, date
+         FROM grade_amendments ORDER BY student_id, grade_position, position",
+        |row| {
+            let student_id = row.get(0)?;
+            let index = grade_indexes.get(&(student_id, row.get(1)?)).copied()
+                .ok_or_else(|| AppError::NotFound(format!("学生ID {} 的成绩修改记录没有对应的成绩", student_id)))?;
+            let reason: String = row.get(4)?;
+            let amended_by: String = row.get(5)?;
+            let change = GradeChange::new(parse_column(row, 3)?, &reason, &amended_by, parse_column(row, 6)?)?;
+            let amendment = GradeAmendment::new(parse_column(row, 2)?, change)?;
+            amendments.entry((student_id, index)).or_default().push(amendment);
+            Ok(())
+        },
+    )?;
+    for ((student_id, index), amendments) in amendments {
+        find(&mut students, student_id, "学生")?.grades[index].restore_amendments(amendments)
+            .map_err(|e| AppError::CorruptedData(format!("学生ID {} 的成绩修改记录: {}", student_id, e)))?;
+    }
+    Ok(students)
+}
+
+/// 子记录按快照文件中的顺序恢复：先注册学生再设置容量，课程满员后才能恢复候补名单
+fn load_courses(connection: &Connection) -> AppResult<BTreeMap<u32, Course>> {
+    let mut courses = BTreeMap::new();
+    let mut capacities = Vec::new();
+    for_each_row(
+        connection,
+        "SELECT id, name, credits, instructor_id, term_id, capacity, prerequisite FROM courses ORDER BY id",
+        |row| {
+            let mut course = Course::new(row.get(0)?, row.get(1)?, row.get(2)?)?;
+            course.instructor_id = row.get(3)?;
+            course.term_id = row.get(4)?;
+            if let Some(capacity) = row.get::<_, Option<u32>>(5)? {
+                capacities.push((course.id, capacity));
+            }
+            if let Some(prerequisite) = parse_optional_column(row, 6)? {
+                course.set_prerequisite(Some(prerequisite))?;
+            }
+            courses.insert(course.id, course);
+            Ok(())
+        },
+    )?;
+
+    for_each_row(connection, "SELECT course_id, student_id FROM enrollments ORDER BY course_id, student_id", |row| {
+        find(&mut courses, row.get(0)?, "课程")?.enroll_student(row.get(1)?)?;
+        Ok(())
+    })?;
+    for (course_id, capacity) in capacities {
+        find(&mut courses, course_id, "课程")?.set_capacity_limit(Some(capacity))?;
+    }
+    for_each_row(
+        connection,
+        "SELECT course_id, day, start_time, end_time, room FROM time_slots ORDER BY course_id, position",
+        |row| {
+            let room: String = row.get(4)?;
+            let slot = TimeSlot::new(parse_column(row, 1)?, parse_column(row, 2)?, parse_column(row, 3)?, &room)?;
+            find(&mut courses, row.get(0)?, "课程")?.add_time_slot(slot)
+        },
+    )?;
+    for_each_row(connection, "SELECT course_id, student_id FROM waitlist ORDER BY course_id, position", |row| {
+        find(&mut courses, row.get(0)?, "课程")?.add_to_waitlist(row.get(1)?)
+    })?;
+    for_each_row(connection, "SELECT course_id, student_id, date FROM withdrawals ORDER BY course_id, student_id, date", |row| {
+        find(&mut courses, row.get(0)?, "课程")?.restore_withdrawal(row.get(1)?, parse_column(row, 2)?)
+    })?;
+    for_each_row(
+        connection,
+        "SELECT course_id, session, student_id, status FROM attendance ORDER BY course_id, session, student_id",
+        |row| {
+            let course = find(&mut courses, row.get(0)?, "课程")?;
+            course.restore_attendance(parse_column(row, 1)?, row.get(2)?, parse_column(row, 3)?)
+        },
+    )?;
+
+    let mut components: BTreeMap<u32, Vec<AssessmentComponent>> = BTreeMap::new();
+    for_each_row(
+        connection,
+        "SELECT course_id, name, weight FROM assessment_components ORDER BY course_id, position",
+        |row| {
+            let name: String = row.get(1)?;
+            components.entry(row.get(0)?).or_default().push(AssessmentComponent::new(&name, row.get(2)?)?);
+            Ok(())
+        },
+    )?;
+    for (course_id, components) in components {
+        find(&mut courses, course_id, "课程")?.set_assessment_components(components)?;
+    }
+    for_each_row(
+        connection,
+        "SELECT course_id, student_id, component, score FROM component_scores ORDER BY course_id, student_id, component",
+        |row| {
+            let component: String = row.get(2)?;
+            find(&mut courses, row.get(0)?, "课程")?.restore_component_score(row.get(1)?, &component, row.get(3)?)
+        },
+    )?;
+    Ok(courses)
+}
+
+fn load_programmes(connection: &Connection) -> AppResult<BTreeMap<u32, Programme>> {
+    let mut programmes = BTreeMap::new();
+    for_each_row(connection, "SELECT id, name, min_credits FROM programmes ORDER BY id", |row| {
+        let name: String = row.get(1)?;
+        let programme = Programme::new(row.get(0)?, &name, row.get(2)?)?;
+        programmes.insert(programme.id, programme);
+        Ok(())
+    })?;
+    for_each_row(
+        connection,
+        "SELECT programme_id, course_id FROM programme_required_courses ORDER BY programme_id, course_id",
+        |row| find(&mut programmes, row.get(0)?, "培养方案")?.add_required_course(row.get(1)?),
+    )?;
+
+    let mut pool_courses: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for_each_row(
+        connection,
+        "SELECT programme_id, pool_position, course_id FROM elective_pool_courses
+         ORDER BY programme_id, pool_position, course_id",
+        |row| {
+            pool_courses.entry((row.get(0)?, row.get(1)?)).or_default().push(row.get(2)?);
+            Ok(())
+        },
+    )?;
+    for_each_row(
+        connection,
+        "SELECT programme_id, position, name, min_credits FROM elective_pools ORDER BY programme_id, position",
+        |row| {
+            let programme_id = row.get(0)?;
+            let name: String = row.get(2)?;
+            let course_ids = pool_courses.remove(&(programme_id, row.get(1)?)).unwrap_or_default();
+            let pool = ElectivePool::new(&name, course_ids, row.get(3)?)?;
+            find(&mut programmes, programme_id, "培养方案")?.add_elective_pool(pool)
+        },
+    )?;
+    Ok(programmes)
+}
+
+/// 读取保存的等级制，表为空时返回 `None`
+fn load_grading_scale(connection: &Connection) -> AppResult<Option<GradingScale>> {
+    let mut bands = Vec::new();
+    for_each_row(
+        connection,
+        "SELECT label, min_score, letter, gpa FROM grading_scale ORDER BY min_score DESC",
+        |row| {
+            let label: String = row.get(0)?;
+            bands.push(GradeBand::new(&label, row.get(1)?, parse_column(row, 2)?, row.get(3)?));
+            Ok(())
+        },
+    )?;
+    if bands.is_empty() {
+        return Ok(None);
+    }
+    GradingScale::new(bands).map(Some)
+}
+
+/// 删除对象在各子表中的记录，之后重新写入
+fn delete_children(connection: &Connection, tables: &[&str], column: &str, id: u32) -> AppResult<()> {
+    for table in tables {
+        connection.execute(&format!("DELETE FROM {} WHERE {} = ?1", table, column), [id])?;
+    }
+    Ok(())
+}
+
+fn write_term(connection: &Connection, term: &Term) -> AppResult<()> {
+    connection.execute(
+        "INSERT INTO terms (id, name, start_date, end_date, add_drop_deadline, withdrawal_deadline)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name, start_date = excluded.start_date,
+             end_date = excluded.end_date, add_drop_deadline = excluded.add_drop_deadline,
+             withdrawal_deadline = excluded.withdrawal_deadline",
+        params![
+            term.id,
+            term.name,
+            term.start_date.to_string(),
+            term.end_date.to_string(),
+            term.add_drop_deadline.map(|d| d.to_string()),
+            term.withdrawal_deadline.map(|d| d.to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+fn write_instructor(connection: &Connection, instructor: &Instructor) -> AppResult<()> {
+    connection.execute(
+        "INSERT INTO instructors (id, name) VALUES (?1, ?2)
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name",
+        params![instructor.id, instructor.name],
+    )?;
+    Ok(())
+}
+
+fn write_student(connection: &Connection, student: &Student) -> AppResult<()> {
+    let contact = &student.contact;
+    connection.execute(
+        "INSERT INTO students (id, name, age, email, phone, address) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name, age = excluded.age,
+             email = excluded.email, phone = excluded.phone, address = excluded.address",
+        params![student.id, student.name, student.age, contact.email(), contact.phone(), contact.address()],
+    )?;
+    delete_children(
+        connection, &["grade_amendments", "grades", "guardians", "status_changes"], "student_id", student.id,
+    )?;
+
+    for (position, guardian) in student.guardians.iter().enumerate() {
+        let contact = guardian.contact();
+        connection.execute(
+            "INSERT INTO guardians (student_id, position, name, relationship, email, phone, address)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                student.id, position as u32, guardian.name(), guardian.relationship(),
+                contact.email(), contact.phone(), contact.address(),
+            ],
+        )?;
+    }
+    for (position, change) in student.status_history().iter().enumerate() {
+        connection.execute(
+            "INSERT INTO status_changes (student_id, position, status, date) VALUES (?1, ?2, ?3, ?4)",
+            params![student.id, position as u32, change.to.as_str(), change.date.to_string()],
+        )?;
+    }
+    for (position, grade) in student.grades.iter().enumerate() {
+        connection.execute(
+            "INSERT INTO grades (student_id, position, subject, score, letter, mark, term_id, course_id, withdrawn)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                student.id,
+                position as u32,
+                grade.subject(),
+                grade.score(),
+                grade.letter_grade().map(|letter| letter.to_string()),
+                grade.mark().map(|mark| mark.to_string()),
+                grade.term_id(),
+                grade.course_id(),
+                grade.is_withdrawn(),
+            ],
+        )?;
+        for (amendment_position, amendment) in grade.amendments().iter().enumerate() {
+            connection.execute(
+                "INSERT INTO grade_amendments
+                     (student_id, grade_position, position, old_value, new_value, reason, amended_by, date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    student.id,
+                    position as u32,
+                    amendment_position as u32,
+                    amendment.old_value.to_string(),
+                    amendment.new_value.to_string(),
+                    amendment.reason,
+                    amendment.amended_by,
+                    amendment.date.to_string(),
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_course(connection: &Connection, course: &Course) -> AppResult<()> {
+    connection.execute(
+        "INSERT INTO courses (id, name, credits, instructor_id, term_id, capacity, prerequisite)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name, credits = excluded.credits,
+             instructor_id = excluded.instructor_id, term_id = excluded.term_id,
+             capacity = excluded.capacity, prerequisite = excluded.prerequisite",
+        params![
+            course.id,
+            course.name,
+            course.credits,
+            course.instructor_id,
+            course.term_id,
+            course.capacity(),
+            course.prerequisite().map(|p| p.to_string()),
+        ],
+    )?;
+    delete_children(
+        connection,
+        &[
+            "enrollments", "waitlist", "withdrawals", "time_slots",
+            "component_scores", "assessment_components", "attendance",
+        ],
+        "course_id",
+        course.id,
+    )?;
+
+    for student_id in course.student_ids() {
+        connection.execute(
+            "INSERT INTO enrollments (course_id, student_id) VALUES (?1, ?2)",
+            params![course.id, student_id],
+        )?;
+    }
+    for (position, student_id) in course.waitlist().into_iter().enumerate() {
+        connection.execute(
+            "INSERT INTO waitlist (course_id, position, student_id) VALUES (?1, ?2, ?3)",
+            params![course.id, position as u32, student_id],
+        )?;
+    }
+    for student_id in course.withdrawn_student_ids() {
+        for date in course.withdrawal_dates(student_id) {
+            connection.execute(
+                "INSERT INTO withdrawals (course_id, student_id, date) VALUES (?1, ?2, ?3)",
+                params![course.id, student_id, date.to_string()],
+            )?;
+        }
+    }
+    for (position, slot) in course.time_slots().iter().enumerate() {
+        connection.execute(
+            "INSERT INTO time_slots (course_id, position, day, start_time, end_time, room)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                course.id, position as u32, slot.day.as_str(), slot.start.to_string(), slot.end.to_string(), slot.room,
+            ],
+        )?;
+    }
+    for (position, component) in course.assessment_components().iter().enumerate() {
+        connection.execute(
+            "INSERT INTO assessment_components (course_id, position, name, weight) VALUES (?1, ?2, ?3, ?4)",
+            params![course.id, position as u32, component.name, component.weight],
+        )?;
+    }
+    for (student_id, component, score) in course.component_score_records() {
+        connection.execute(
+            "INSERT INTO component_scores (course_id, student_id, component, score) VALUES (?1, ?2, ?3, ?4)",
+            params![course.id, student_id, component, score],
+        )?;
+    }
+    for (session, student_id, status) in course.attendance_records() {
+        connection.execute(
+            "INSERT INTO attendance (course_id, session, student_id, status) VALUES (?1, ?2, ?3, ?4)",
+            params![course.id, session.to_string(), student_id, status.as_str()],
+        )?;
+    }
+    Ok(())
+}
+
+fn write_programme(connection: &Connection, programme: &Programme) -> AppResult<()> {
+    connection.execute(
+        "INSERT INTO programmes (id, name, min_credits) VALUES (?1, ?2, ?3)
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name, min_credits = excluded.min_credits",
+        params![programme.id, programme.name, programme.min_credits],
+    )?;
+    delete_children(
+        connection,
+        &["elective_pool_courses", "elective_pools", "programme_required_courses"],
+        "programme_id",
+        programme.id,
+    )?;
+
+    for course_id in programme.required_courses() {
+        connection.execute(
+            "INSERT INTO programme_required_courses (programme_id, course_id) VALUES (?1, ?2)",
+            params![programme.id, course_id],
+        )?;
+    }
+    for (position, pool) in programme.elective_pools().iter().enumerate() {
+        connection.execute(
+            "INSERT INTO elective_pools (programme_id, position, name, min_credits) VALUES (?1, ?2, ?3, ?4)",
+            params![programme.id, position as u32, pool.name, pool.min_credits],
+        )?;
+        for course_id in pool.course_ids() {
+            connection.execute(
+                "INSERT INTO elective_pool_courses (programme_id, pool_position, course_id) VALUES (?1, ?2, ?3)",
+                params![programme.id, position as u32, course_id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_grading_scale(connection: &Connection, scale: &GradingScale) -> AppResult<()> {
+    connection.execute("DELETE FROM grading_scale", [])?;
+    for band in scale.bands() {
+        connection.execute(
+            "INSERT INTO grading_scale (min_score, label, letter, gpa) VALUES (?1, ?2, ?3, ?4)",
+            params![band.min_score, band.label, band.letter.as_str(), band.gpa],
+        )?;
+    }
+    Ok(())
+}
+
+fn write_attendance_threshold(connection: &Connection, threshold: f64) -> AppResult<()> {
+    connection.execute(
+        "INSERT INTO settings (name, value) VALUES ('attendance_threshold', ?1)
+         ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+        params![threshold],
+    )?;
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn get_student(&self, id: u32) -> Option<&Student> {
+        self.cache.get_student(id)
+    }
+
+    fn list_students(&self) -> Vec<&Student> {
+        self.cache.list_students()
+    }
+
+    fn save_student(&mut self, student: Student) -> AppResult<()> {
+        self.write(|connection| write_student(connection, &student))?;
+        self.cache.insert_student(student);
+        Ok(())
+    }
+
+    fn get_course(&self, id: u32) -> Option<&Course> {
+        self.cache.get_course(id)
+    }
+
+    fn list_courses(&self) -> Vec<&Course> {
+        self.cache.list_courses()
+    }
+
+    fn save_course(&mut self, course: Course) -> AppResult<()> {
+        self.write(|connection| write_course(connection, &course))?;
+        self.cache.insert_course(course);
+        Ok(())
+    }
+
+    fn get_term(&self, id: u32) -> Option<&Term> {
+        self.cache.get_term(id)
+    }
+
+    fn list_terms(&self) -> Vec<&Term> {
+        self.cache.list_terms()
+    }
+
+    fn save_term(&mut self, term: Term) -> AppResult<()> {
+        self.write(|connection| write_term(connection, &term))?;
+        self.cache.insert_term(term);
+        Ok(())
+    }
+
+    fn get_instructor(&self, id: u32) -> Option<&Instructor> {
+        self.cache.get_instructor(id)
+    }
+
+    fn list_instructors(&self) -> Vec<&Instructor> {
+        self.cache.list_instructors()
+    }
+
+    fn save_instructor(&mut self, instructor: Instructor) -> AppResult<()> {
+        self.write(|connection| write_instructor(connection, &instructor))?;
+        self.cache.insert_instructor(instructor);
+        Ok(())
+    }
+
+    fn get_programme(&self, id: u32) -> Option<&Programme> {
+        self.cache.get_programme(id)
+    }
+
+    fn list_programmes(&self) -> Vec<&Programme> {
+        self.cache.list_programmes()
+    }
+
+    fn save_programme(&mut self, programme: Programme) -> AppResult<()> {
+        self.write(|connection| write_programme(connection, &programme))?;
+        self.cache.insert_programme(programme);
+        Ok(())
+    }
+
+    fn grading_scale(&self) -> &GradingScale {
+        self.cache.grading_scale()
+    }
+
+    fn save_grading_scale(&mut self, scale: GradingScale) -> AppResult<()> {
+        self.write(|connection| write_grading_scale(connection, &scale))?;
+        self.cache.replace_grading_scale(scale);
+        Ok(())
+    }
+
+    fn attendance_threshold(&self) -> f64 {
+        self.cache.attendance_threshold()
+    }
+
+    fn save_attendance_threshold(&mut self, threshold: f64) -> AppResult<()> {
+        self.write(|connection| write_attendance_threshold(connection, threshold))?;
+        self.cache.replace_attendance_threshold(threshold);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn count(storage: &SqliteStorage, sql: &str) -> u32 {
+        storage.connection.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.db");
+        Connection::open(&path).unwrap().pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        assert!(SqliteStorage::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_tables_enforce_references() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        storage.save_student(Student::new(1, "张三".to_string(), 20).unwrap()).unwrap();
+        let mut course = Course::new(101, "Rust编程".to_string(), 3).unwrap();
+        course.enroll_student(1).unwrap();
+        storage.save_course(course).unwrap();
+
+        let name: String = storage.connection.query_row(
+            "SELECT s.name FROM students s JOIN enrollments e ON e.student_id = s.id WHERE e.course_id = 101",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(name, "张三");
+
+        // 引用不存在的记录时整个写操作回滚，缓存不变
+        let mut course = Course::new(102, "数据库".to_string(), 2).unwrap();
+        course.instructor_id = Some(9);
+        assert!(storage.save_course(course).is_err());
+        assert!(storage.get_course(102).is_none());
+        let mut course = storage.get_course(101).unwrap().clone();
+        course.enroll_student(9).unwrap();
+        assert!(storage.save_course(course).is_err());
+        assert_eq!(storage.get_course(101).unwrap().student_ids(), vec![1]);
+
+        assert!(storage.connection.execute("INSERT INTO enrollments (course_id, student_id) VALUES (101, 9)", []).is_err());
+        assert_eq!(count(&storage, "SELECT COUNT(*) FROM enrollments"), 1);
+    }
+}
@@ -0,0 +1,134 @@
+//! 成绩修改记录模块
+//!
+//! 成绩录入后只能通过修改操作更正，每次修改都在成绩上留下一条记录
+//! （原成绩、新成绩、原因、修改人和日期），供审计和成绩申诉查阅。
+
+use super::grade::GradeMark;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+use std::fmt;
+use std::str::FromStr;
+
+/// 成绩的取值：分数，或不以分数记录的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GradeValue {
+    Score(f64),
+    Mark(GradeMark),
+}
+
+impl fmt::Display for GradeValue {
+    /// 分数原样输出，结果输出代码，例如 `85.5`、`P`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradeValue::Score(score) => write!(f, "{}", score),
+            GradeValue::Mark(mark) => write!(f, "{}", mark),
+        }
+    }
+}
+
+impl FromStr for GradeValue {
+    type Err = AppError;
+
+    /// 接受结果代码或中文名称，否则按分数解析；不检查分数范围
+    fn from_str(s: &str) -> AppResult<Self> {
+        if let Ok(mark) = s.parse::<GradeMark>() {
+            return Ok(GradeValue::Mark(mark));
+        }
+        s.trim().parse()
+            .map(GradeValue::Score)
+            .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的成绩", s)))
+    }
+}
+
+/// 一次成绩修改的申请：新成绩、原因、修改人和修改日期
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradeChange {
+    pub new_value: GradeValue,
+    pub reason: String,
+    pub amended_by: String,
+    pub date: Date,
+}
+
+impl GradeChange {
+    /// 原因和修改人都不能为空
+    pub fn new(new_value: GradeValue, reason: &str, amended_by: &str, date: Date) -> AppResult<Self> {
+        Ok(GradeChange {
+            new_value,
+            reason: required_text(reason, "修改原因")?,
+            amended_by: required_text(amended_by, "修改人")?,
+            date,
+        })
+    }
+}
+
+/// 成绩上保存的一条修改记录
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GradeAmendmentData"))]
+pub struct GradeAmendment {
+    pub old_value: GradeValue,
+    pub new_value: GradeValue,
+    pub reason: String,
+    pub amended_by: String,
+    pub date: Date,
+}
+
+impl GradeAmendment {
+    /// 新成绩必须与原成绩不同
+    pub fn new(old_value: GradeValue, change: GradeChange) -> AppResult<Self> {
+        if old_value == change.new_value {
+            return Err(AppError::ValidationError(
+                format!("新成绩 {} 与原成绩相同", change.new_value)
+            ));
+        }
+        let change = GradeChange::new(change.new_value, &change.reason, &change.amended_by, change.date)?;
+        Ok(GradeAmendment {
+            old_value,
+            new_value: change.new_value,
+            reason: change.reason,
+            amended_by: change.amended_by,
+            date: change.date,
+        })
+    }
+}
+
+impl fmt::Display for GradeAmendment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} -> {}（{}）",
+            self.date, self.amended_by, self.old_value, self.new_value, self.reason
+        )
+    }
+}
+
+/// 反序列化时的原始数据，经过 `GradeAmendment::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GradeAmendmentData {
+    old_value: GradeValue,
+    new_value: GradeValue,
+    reason: String,
+    amended_by: String,
+    date: Date,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GradeAmendmentData> for GradeAmendment {
+    type Error = AppError;
+
+    fn try_from(data: GradeAmendmentData) -> AppResult<Self> {
+        let change = GradeChange::new(data.new_value, &data.reason, &data.amended_by, data.date)?;
+        GradeAmendment::new(data.old_value, change)
+    }
+}
+
+fn required_text(text: &str, what: &str) -> AppResult<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AppError::ValidationError(format!("{}不能为空", what)));
+    }
+    Ok(text.to_string())
+}
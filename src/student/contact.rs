@@ -0,0 +1,217 @@
+//! 学生联系方式和监护人模块
+//!
+//! 邮箱、电话和地址都是可选的，设置时经过验证；空白文本视为没有填写。
+
+use super::validator::{validate_name, validate_phone};
+use crate::errors::{AppError, AppResult};
+use crate::utils::validate_email;
+
+/// 地址的最大长度（字符数）
+const MAX_ADDRESS_LENGTH: usize = 200;
+
+/// 联系方式
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ContactData"))]
+pub struct ContactInfo {
+    email: Option<String>,
+    phone: Option<String>,
+    address: Option<String>,
+}
+
+impl ContactInfo {
+    pub fn new(email: Option<String>, phone: Option<String>, address: Option<String>) -> AppResult<Self> {
+        let mut contact = ContactInfo::default();
+        contact.set_email(email)?;
+        contact.set_phone(phone)?;
+        contact.set_address(address)?;
+        Ok(contact)
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn phone(&self) -> Option<&str> {
+        self.phone.as_deref()
+    }
+
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.email.is_none() && self.phone.is_none() && self.address.is_none()
+    }
+
+    pub fn set_email(&mut self, email: Option<String>) -> AppResult<()> {
+        let email = normalize(email);
+        if let Some(email) = &email {
+            validate_email(email)?;
+        }
+        self.email = email;
+        Ok(())
+    }
+
+    pub fn set_phone(&mut self, phone: Option<String>) -> AppResult<()> {
+        let phone = normalize(phone);
+        if let Some(phone) = &phone {
+            validate_phone(phone)?;
+        }
+        self.phone = phone;
+        Ok(())
+    }
+
+    pub fn set_address(&mut self, address: Option<String>) -> AppResult<()> {
+        let address = normalize(address);
+        if let Some(address) = &address {
+            if address.chars().count() > MAX_ADDRESS_LENGTH {
+                return Err(AppError::ValidationError(
+                    format!("地址长度不能超过{}个字符", MAX_ADDRESS_LENGTH)
+                ));
+            }
+        }
+        self.address = address;
+        Ok(())
+    }
+}
+
+/// 去掉首尾空白，空文本视为没有填写
+fn normalize(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// 反序列化时的原始数据，经过 `ContactInfo::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ContactData {
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    phone: Option<String>,
+    #[serde(default)]
+    address: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ContactData> for ContactInfo {
+    type Error = AppError;
+
+    fn try_from(data: ContactData) -> AppResult<Self> {
+        ContactInfo::new(data.email, data.phone, data.address)
+    }
+}
+
+/// 监护人，至少要有电话或邮箱之一
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GuardianData"))]
+pub struct Guardian {
+    name: String,
+    /// 与学生的关系，例如“父亲”
+    relationship: String,
+    contact: ContactInfo,
+}
+
+impl Guardian {
+    pub fn new(name: String, relationship: String, contact: ContactInfo) -> AppResult<Self> {
+        validate_name(&name)?;
+        let relationship = relationship.trim().to_string();
+        if relationship.is_empty() {
+            return Err(AppError::ValidationError("监护人关系不能为空".to_string()));
+        }
+        let mut guardian = Guardian { name, relationship, contact: ContactInfo::default() };
+        guardian.set_contact(contact)?;
+        Ok(guardian)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn relationship(&self) -> &str {
+        &self.relationship
+    }
+
+    pub fn contact(&self) -> &ContactInfo {
+        &self.contact
+    }
+
+    pub fn set_contact(&mut self, contact: ContactInfo) -> AppResult<()> {
+        if contact.email.is_none() && contact.phone.is_none() {
+            return Err(AppError::ValidationError(
+                format!("监护人 {} 至少需要电话或邮箱", self.name)
+            ));
+        }
+        self.contact = contact;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Guardian {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}（{}）", self.name, self.relationship)?;
+        for value in [self.contact.phone(), self.contact.email()].into_iter().flatten() {
+            write!(f, " {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// 反序列化时的原始数据，经过 `Guardian::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GuardianData {
+    name: String,
+    relationship: String,
+    contact: ContactInfo,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GuardianData> for Guardian {
+    type Error = AppError;
+
+    fn try_from(data: GuardianData) -> AppResult<Self> {
+        Guardian::new(data.name, data.relationship, data.contact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn test_contact_validation() {
+        let contact = ContactInfo::new(text(" zhangsan@example.com "), text("+86 138-0000-0000"), text("  ")).unwrap();
+        assert_eq!(contact.email(), Some("zhangsan@example.com"));
+        assert_eq!(contact.address(), None);
+        assert!(ContactInfo::default().is_empty());
+
+        assert!(ContactInfo::new(text("zhangsan.example.com"), None, None).is_err());
+        assert!(ContactInfo::new(None, text("138-abc"), None).is_err());
+        assert!(ContactInfo::new(None, text("123"), None).is_err());
+        assert!(ContactInfo::new(None, None, Some("路".repeat(201))).is_err());
+
+        // 修改失败时保留原来的值
+        let mut contact = contact;
+        assert!(contact.set_email(text("bad@")).is_err());
+        assert_eq!(contact.email(), Some("zhangsan@example.com"));
+    }
+
+    #[test]
+    fn test_guardian_requires_phone_or_email() {
+        let address_only = ContactInfo::new(None, None, text("北京市海淀区")).unwrap();
+        assert!(Guardian::new("张父".to_string(), "父亲".to_string(), address_only).is_err());
+        assert!(Guardian::new("张父".to_string(), " ".to_string(), ContactInfo::default()).is_err());
+
+        let phone = ContactInfo::new(None, text("13800000000"), None).unwrap();
+        let mut guardian = Guardian::new("张父".to_string(), "父亲".to_string(), phone).unwrap();
+        assert_eq!(guardian.to_string(), "张父（父亲） 13800000000");
+        assert!(guardian.set_contact(ContactInfo::default()).is_err());
+        assert_eq!(guardian.contact().phone(), Some("13800000000"));
+    }
+}
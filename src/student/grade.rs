@@ -1,102 +1,601 @@
 //! 成绩模块
 //! 
-//! 处理学生成绩相关功能
+//! 处理学生成绩相关功能。成绩通常是0-100的分数，也可以是通过/不通过、
+//! 未完成、旁听、免修这样不以分数记录的结果（见 `GradeMark`）。
 
+use super::amendment::{GradeAmendment, GradeChange, GradeValue};
+use super::grading_scale::{standard_scale, GradingScale};
 use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GradeData", into = "GradeData"))]
 pub struct Grade {
     subject: String,
-    score: f64,
-    letter_grade: LetterGrade,
+    result: GradeResult,
+    term_id: Option<u32>,
+    course_id: Option<u32>,
+    /// 修改记录，按修改顺序排列
+    amendments: Vec<GradeAmendment>,
+    /// 属于学生已退出（W）的那次课程修读
+    withdrawn: bool,
 }
 
+/// 成绩的结果：带等级的分数，或不以分数记录的结果
 #[derive(Debug, Clone, PartialEq)]
+enum GradeResult {
+    Score { score: f64, letter: LetterGrade },
+    Mark(GradeMark),
+}
+
+/// 字母等级
+///
+/// 标准等级制只使用 A-F 五档；带加减号的等级需要在等级制中显式启用
+/// （见 `GradingScale::plus_minus`）。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LetterGrade {
     A,  // 90-100
+    #[cfg_attr(feature = "serde", serde(rename = "A-"))]
+    AMinus,
+    #[cfg_attr(feature = "serde", serde(rename = "B+"))]
+    BPlus,
     B,  // 80-89
+    #[cfg_attr(feature = "serde", serde(rename = "B-"))]
+    BMinus,
+    #[cfg_attr(feature = "serde", serde(rename = "C+"))]
+    CPlus,
     C,  // 70-79
+    #[cfg_attr(feature = "serde", serde(rename = "C-"))]
+    CMinus,
+    #[cfg_attr(feature = "serde", serde(rename = "D+"))]
+    DPlus,
     D,  // 60-69
+    #[cfg_attr(feature = "serde", serde(rename = "D-"))]
+    DMinus,
     F,  // 0-59
 }
 
+/// 不以分数记录的成绩
+///
+/// 这些成绩都不计入平均分和绩点；通过和免修算作及格（获得学分），
+/// 不通过、未完成和旁听不算。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GradeMark {
+    /// 通过（P），用于只评定通过与否的课程
+    Pass,
+    /// 不通过（NP）
+    Fail,
+    /// 未完成（I），课程结束时还没有完成考核
+    Incomplete,
+    /// 旁听（AU），不获得学分
+    Audit,
+    /// 免修（EX），不用修读即获得学分
+    Exempt,
+}
+
 impl Grade {
+    /// 按标准等级制创建成绩
     pub fn new(subject: String, score: f64) -> AppResult<Self> {
-        if score < 0.0 || score > 100.0 {
+        Self::with_scale(subject, score, standard_scale())
+    }
+    
+    /// 按指定等级制创建成绩
+    pub fn with_scale(subject: String, score: f64, scale: &GradingScale) -> AppResult<Self> {
+        let letter_grade = scale.letter_for(score);
+        Self::with_letter_grade(subject, score, letter_grade)
+    }
+    
+    /// 使用已经确定的等级创建成绩，用于读取保存过的数据
+    pub(crate) fn with_letter_grade(
+        subject: String,
+        score: f64,
+        letter_grade: LetterGrade,
+    ) -> AppResult<Self> {
+        if !(0.0..=100.0).contains(&score) {
             return Err(AppError::ValidationError(
                 format!("成绩 {} 必须在0-100之间", score)
             ));
         }
         
-        if subject.trim().is_empty() {
-            return Err(AppError::ValidationError(
-                "科目名称不能为空".to_string()
-            ));
-        }
+        validate_subject(&subject)?;
         
-        let letter_grade = LetterGrade::from_score(score);
+        Ok(Grade {
+            subject,
+            result: GradeResult::Score { score, letter: letter_grade },
+            term_id: None,
+            course_id: None,
+            amendments: Vec::new(),
+            withdrawn: false,
+        })
+    }
+    
+    /// 创建不以分数记录的成绩
+    pub fn with_mark(subject: String, mark: GradeMark) -> AppResult<Self> {
+        validate_subject(&subject)?;
         
         Ok(Grade {
             subject,
-            score,
-            letter_grade,
+            result: GradeResult::Mark(mark),
+            term_id: None,
+            course_id: None,
+            amendments: Vec::new(),
+            withdrawn: false,
         })
     }
+
+    /// 按指定等级制重新评定分数成绩的等级，不以分数记录的成绩不变
+    pub(crate) fn regrade(&mut self, scale: &GradingScale) {
+        if let GradeResult::Score { score, letter } = &mut self.result {
+            *letter = scale.letter_for(*score);
+        }
+    }
+
+    /// 将成绩归入指定学期
+    pub fn in_term(mut self, term_id: u32) -> Self {
+        self.term_id = Some(term_id);
+        self
+    }
+    
+    /// 将成绩记为某门课程的成绩
+    pub fn for_course(mut self, course_id: u32) -> Self {
+        self.course_id = Some(course_id);
+        self
+    }
     
     pub fn subject(&self) -> &str {
         &self.subject
     }
     
-    pub fn score(&self) -> f64 {
-        self.score
+    /// 分数，不以分数记录的成绩为 `None`
+    pub fn score(&self) -> Option<f64> {
+        match self.result {
+            GradeResult::Score { score, .. } => Some(score),
+            GradeResult::Mark(_) => None,
+        }
+    }
+    
+    /// 等级，不以分数记录的成绩为 `None`
+    pub fn letter_grade(&self) -> Option<&LetterGrade> {
+        match &self.result {
+            GradeResult::Score { letter, .. } => Some(letter),
+            GradeResult::Mark(_) => None,
+        }
+    }
+    
+    /// 不以分数记录的结果，分数成绩为 `None`
+    pub fn mark(&self) -> Option<GradeMark> {
+        match self.result {
+            GradeResult::Score { .. } => None,
+            GradeResult::Mark(mark) => Some(mark),
+        }
+    }
+    
+    /// 是否是分数成绩，只有分数成绩计入平均分和绩点
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.result, GradeResult::Score { .. })
+    }
+    
+    /// 当前的成绩取值
+    pub fn value(&self) -> GradeValue {
+        match self.result {
+            GradeResult::Score { score, .. } => GradeValue::Score(score),
+            GradeResult::Mark(mark) => GradeValue::Mark(mark),
+        }
     }
     
-    pub fn letter_grade(&self) -> &LetterGrade {
-        &self.letter_grade
+    /// 修改记录，按修改顺序排列
+    pub fn amendments(&self) -> &[GradeAmendment] {
+        &self.amendments
     }
     
+    /// 修改成绩并留下修改记录，新分数按指定等级制重新评定等级
+    ///
+    /// 科目、学期和课程不变；新成绩与当前成绩相同，或修改日期早于上一次修改时返回错误。
+    pub fn amend(&mut self, change: GradeChange, scale: &GradingScale) -> AppResult<&GradeAmendment> {
+        let replacement = match change.new_value {
+            GradeValue::Score(score) => Grade::with_scale(self.subject.clone(), score, scale)?,
+            GradeValue::Mark(mark) => Grade::with_mark(self.subject.clone(), mark)?,
+        };
+        if let Some(last) = self.amendments.last() {
+            check_amendment_date(last, change.date)?;
+        }
+        let amendment = GradeAmendment::new(self.value(), change)?;
+        self.result = replacement.result;
+        self.amendments.push(amendment);
+        Ok(&self.amendments[self.amendments.len() - 1])
+    }
+    
+    /// 恢复保存过的全部修改记录，用于读取保存过的数据
+    ///
+    /// 修改记录必须首尾相接：每条的原成绩是上一条的新成绩，日期不早于上一条，
+    /// 最后一条的新成绩就是当前成绩。
+    pub(crate) fn restore_amendments(&mut self, amendments: Vec<GradeAmendment>) -> AppResult<()> {
+        for pair in amendments.windows(2) {
+            if pair[1].old_value != pair[0].new_value {
+                return Err(AppError::ValidationError(format!(
+                    "成绩 {} 的修改记录不连续：修改为 {} 之后的原成绩是 {}",
+                    self.subject, pair[0].new_value, pair[1].old_value
+                )));
+            }
+            check_amendment_date(&pair[0], pair[1].date)?;
+        }
+        if let Some(last) = amendments.last() {
+            if last.new_value != self.value() {
+                return Err(AppError::ValidationError(format!(
+                    "成绩 {} 的最后一次修改为 {}，与当前成绩 {} 不符",
+                    self.subject, last.new_value, self.value()
+                )));
+            }
+        }
+        self.amendments = amendments;
+        Ok(())
+    }
+    
+    /// 成绩所属的学期，未分配时为 `None`
+    pub fn term_id(&self) -> Option<u32> {
+        self.term_id
+    }
+    
+    /// 成绩所属的课程，不属于任何课程时为 `None`
+    pub fn course_id(&self) -> Option<u32> {
+        self.course_id
+    }
+    
+    /// 是否属于学生已退出（W）的课程修读，这样的成绩不计入平均分和课程成绩
+    pub fn is_withdrawn(&self) -> bool {
+        self.withdrawn
+    }
+    
+    /// 学生退出成绩所属的课程时标记成绩，只有课程成绩可以标记
+    pub(crate) fn mark_withdrawn(&mut self) -> AppResult<()> {
+        if self.course_id.is_none() {
+            return Err(AppError::ValidationError(
+                format!("成绩 {} 不属于任何课程，不能记为退出", self.subject)
+            ));
+        }
+        self.withdrawn = true;
+        Ok(())
+    }
+    
+    /// 是否及格
+    ///
+    /// 分数成绩由创建成绩时使用的等级制决定；其他成绩见 `GradeMark::is_passing`。
     pub fn is_passing(&self) -> bool {
-        self.score >= 60.0
+        match &self.result {
+            GradeResult::Score { letter, .. } => *letter != LetterGrade::F,
+            GradeResult::Mark(mark) => mark.is_passing(),
+        }
     }
 }
 
-impl LetterGrade {
-    fn from_score(score: f64) -> Self {
-        match score {
-            90.0..=100.0 => LetterGrade::A,
-            80.0..=89.9 => LetterGrade::B,
-            70.0..=79.9 => LetterGrade::C,
-            60.0..=69.9 => LetterGrade::D,
-            _ => LetterGrade::F,
+/// 修改日期不能早于上一次修改
+fn check_amendment_date(previous: &GradeAmendment, date: Date) -> AppResult<()> {
+    if date < previous.date {
+        return Err(AppError::ValidationError(
+            format!("修改日期 {} 早于上一次修改的日期 {}", date, previous.date)
+        ));
+    }
+    Ok(())
+}
+
+fn validate_subject(subject: &str) -> AppResult<()> {
+    if subject.trim().is_empty() {
+        return Err(AppError::ValidationError(
+            "科目名称不能为空".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// 序列化和反序列化时的原始数据
+///
+/// 与快照读取一致，保存下来的等级原样保留（它可能来自录入时的其他等级制），
+/// 只有缺少等级时才按标准等级制由分数计算；修改记录经过 `restore_amendments` 验证。
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GradeData {
+    subject: String,
+    #[serde(default)]
+    score: Option<f64>,
+    #[serde(default)]
+    letter_grade: Option<LetterGrade>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mark: Option<GradeMark>,
+    #[serde(default)]
+    term_id: Option<u32>,
+    #[serde(default)]
+    course_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    amendments: Vec<GradeAmendment>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    withdrawn: bool,
+}
+
+#[cfg(feature = "serde")]
+impl From<Grade> for GradeData {
+    fn from(grade: Grade) -> Self {
+        GradeData {
+            score: grade.score(),
+            letter_grade: grade.letter_grade().cloned(),
+            mark: grade.mark(),
+            subject: grade.subject,
+            term_id: grade.term_id,
+            course_id: grade.course_id,
+            amendments: grade.amendments,
+            withdrawn: grade.withdrawn,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GradeData> for Grade {
+    type Error = AppError;
+
+    fn try_from(data: GradeData) -> AppResult<Self> {
+        let mut grade = match (data.score, data.letter_grade, data.mark) {
+            (Some(score), Some(letter), None) => Grade::with_letter_grade(data.subject, score, letter)?,
+            (Some(score), None, None) => Grade::new(data.subject, score)?,
+            (None, None, Some(mark)) => Grade::with_mark(data.subject, mark)?,
+            _ => return Err(AppError::ValidationError(
+                format!("成绩 {} 必须有分数或非分数结果之一", data.subject)
+            )),
+        };
+        grade.term_id = data.term_id;
+        grade.course_id = data.course_id;
+        grade.restore_amendments(data.amendments)?;
+        if data.withdrawn {
+            grade.mark_withdrawn()?;
         }
+        Ok(grade)
     }
+}
+
+impl LetterGrade {
+    /// 所有等级，从高到低
+    pub const ALL: [LetterGrade; 12] = [
+        LetterGrade::A, LetterGrade::AMinus,
+        LetterGrade::BPlus, LetterGrade::B, LetterGrade::BMinus,
+        LetterGrade::CPlus, LetterGrade::C, LetterGrade::CMinus,
+        LetterGrade::DPlus, LetterGrade::D, LetterGrade::DMinus,
+        LetterGrade::F,
+    ];
     
     pub fn to_gpa(&self) -> f64 {
         match self {
             LetterGrade::A => 4.0,
+            LetterGrade::AMinus => 3.7,
+            LetterGrade::BPlus => 3.3,
             LetterGrade::B => 3.0,
+            LetterGrade::BMinus => 2.7,
+            LetterGrade::CPlus => 2.3,
             LetterGrade::C => 2.0,
+            LetterGrade::CMinus => 1.7,
+            LetterGrade::DPlus => 1.3,
             LetterGrade::D => 1.0,
+            LetterGrade::DMinus => 0.7,
             LetterGrade::F => 0.0,
         }
     }
-}
-
-impl std::fmt::Display for LetterGrade {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let letter = match self {
+    
+    /// 去掉加减号后的基本等级，例如 B+ 和 B- 都属于 B
+    pub fn base(&self) -> LetterGrade {
+        match self {
+            LetterGrade::A | LetterGrade::AMinus => LetterGrade::A,
+            LetterGrade::BPlus | LetterGrade::B | LetterGrade::BMinus => LetterGrade::B,
+            LetterGrade::CPlus | LetterGrade::C | LetterGrade::CMinus => LetterGrade::C,
+            LetterGrade::DPlus | LetterGrade::D | LetterGrade::DMinus => LetterGrade::D,
+            LetterGrade::F => LetterGrade::F,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
             LetterGrade::A => "A",
+            LetterGrade::AMinus => "A-",
+            LetterGrade::BPlus => "B+",
             LetterGrade::B => "B",
+            LetterGrade::BMinus => "B-",
+            LetterGrade::CPlus => "C+",
             LetterGrade::C => "C",
+            LetterGrade::CMinus => "C-",
+            LetterGrade::DPlus => "D+",
             LetterGrade::D => "D",
+            LetterGrade::DMinus => "D-",
             LetterGrade::F => "F",
-        };
-        write!(f, "{}", letter)
+        }
+    }
+}
+
+impl std::fmt::Display for LetterGrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // 使用 pad 以支持表格中的宽度和对齐参数
+        f.pad(self.as_str())
+    }
+}
+
+impl std::str::FromStr for LetterGrade {
+    type Err = AppError;
+    
+    fn from_str(s: &str) -> AppResult<Self> {
+        let s = s.trim();
+        LetterGrade::ALL.iter()
+            .find(|letter| letter.as_str() == s)
+            .cloned()
+            .ok_or_else(|| AppError::InvalidInput(format!("'{}' 不是有效的等级", s)))
+    }
+}
+
+impl GradeMark {
+    pub const ALL: [GradeMark; 5] = [
+        GradeMark::Pass, GradeMark::Fail, GradeMark::Incomplete, GradeMark::Audit, GradeMark::Exempt,
+    ];
+    
+    /// 成绩单上使用的代码
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GradeMark::Pass => "P",
+            GradeMark::Fail => "NP",
+            GradeMark::Incomplete => "I",
+            GradeMark::Audit => "AU",
+            GradeMark::Exempt => "EX",
+        }
+    }
+    
+    /// 中文名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            GradeMark::Pass => "通过",
+            GradeMark::Fail => "不通过",
+            GradeMark::Incomplete => "未完成",
+            GradeMark::Audit => "旁听",
+            GradeMark::Exempt => "免修",
+        }
+    }
+    
+    /// 是否算作及格（获得学分）
+    pub fn is_passing(&self) -> bool {
+        matches!(self, GradeMark::Pass | GradeMark::Exempt)
+    }
+}
+
+impl std::fmt::Display for GradeMark {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl std::str::FromStr for GradeMark {
+    type Err = AppError;
+    
+    /// 接受代码（不区分大小写）或中文名称
+    fn from_str(s: &str) -> AppResult<Self> {
+        let s = s.trim();
+        GradeMark::ALL.iter()
+            .find(|mark| mark.as_str().eq_ignore_ascii_case(s) || mark.label() == s)
+            .copied()
+            .ok_or_else(|| AppError::InvalidInput(format!("'{}' 不是有效的成绩标记", s)))
     }
 }
 
 impl std::fmt::Display for Grade {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}: {:.1} ({})", self.subject, self.score, self.letter_grade)
+        match &self.result {
+            GradeResult::Score { score, letter } => write!(f, "{}: {:.1} ({})", self.subject, score, letter),
+            GradeResult::Mark(mark) => write!(f, "{}: {} ({})", self.subject, mark.label(), mark),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn letter(score: f64) -> LetterGrade {
+        Grade::new("数学".to_string(), score).unwrap().letter_grade().unwrap().clone()
+    }
+    
+    #[test]
+    fn test_letter_grade_boundaries() {
+        let cases = [
+            (100.0, LetterGrade::A),
+            (90.0, LetterGrade::A),
+            (89.999, LetterGrade::B),
+            (89.95, LetterGrade::B),
+            (89.9, LetterGrade::B),
+            (80.0, LetterGrade::B),
+            (79.95, LetterGrade::C),
+            (70.0, LetterGrade::C),
+            (69.95, LetterGrade::D),
+            (60.0, LetterGrade::D),
+            (59.99, LetterGrade::F),
+            (0.0, LetterGrade::F),
+        ];
+        for (score, expected) in cases {
+            assert_eq!(letter(score), expected, "分数 {}", score);
+        }
+        assert_eq!(letter(90.0f64.next_down()), LetterGrade::B);
+        assert_eq!(letter(60.0f64.next_down()), LetterGrade::F);
+    }
+    
+    #[test]
+    fn test_rejects_scores_outside_range() {
+        for score in [-0.01, 100.01, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(Grade::new("数学".to_string(), score).is_err(), "分数 {}", score);
+        }
+    }
+    
+    #[test]
+    fn test_plus_minus_letters() {
+        assert_eq!(LetterGrade::AMinus.to_string(), "A-");
+        assert_eq!(format!("[{:3}]", LetterGrade::BPlus), "[B+ ]");
+        assert_eq!(LetterGrade::CMinus.to_gpa(), 1.7);
+        assert_eq!(LetterGrade::DPlus.base(), LetterGrade::D);
+        for letter in LetterGrade::ALL {
+            assert_eq!(letter.as_str().parse::<LetterGrade>().unwrap(), letter);
+        }
+        assert!("E".parse::<LetterGrade>().is_err());
+    }
+    
+    #[test]
+    fn test_is_passing_boundary() {
+        assert!(Grade::new("数学".to_string(), 60.0).unwrap().is_passing());
+        assert!(!Grade::new("数学".to_string(), 59.95).unwrap().is_passing());
+    }
+    
+    #[test]
+    fn test_grade_marks() {
+        let pass = Grade::with_mark("研讨课".to_string(), GradeMark::Pass).unwrap();
+        assert!(pass.is_passing());
+        assert!(!pass.is_numeric());
+        assert_eq!(pass.score(), None);
+        assert_eq!(pass.letter_grade(), None);
+        assert_eq!(pass.to_string(), "研讨课: 通过 (P)");
+        
+        let passing: Vec<bool> = GradeMark::ALL.iter().map(|m| m.is_passing()).collect();
+        assert_eq!(passing, vec![true, false, false, false, true]);
+        for mark in GradeMark::ALL {
+            assert_eq!(mark.as_str().parse::<GradeMark>().unwrap(), mark);
+            assert_eq!(mark.label().parse::<GradeMark>().unwrap(), mark);
+        }
+        assert_eq!("au".parse::<GradeMark>().unwrap(), GradeMark::Audit);
+        assert!("A".parse::<GradeMark>().is_err());
+        assert!(Grade::with_mark(" ".to_string(), GradeMark::Exempt).is_err());
+    }
+    
+    fn change(value: GradeValue, date: &str) -> GradeChange {
+        GradeChange::new(value, "复核", "王老师", date.parse().unwrap()).unwrap()
+    }
+    
+    #[test]
+    fn test_amendment_chain() {
+        let mut grade = Grade::new("数学".to_string(), 58.0).unwrap();
+        grade.amend(change(GradeValue::Score(62.0), "2024-07-01"), standard_scale()).unwrap();
+        assert!(grade.amend(change(GradeValue::Score(65.0), "2024-06-30"), standard_scale()).is_err());
+        grade.amend(change(GradeValue::Mark(GradeMark::Pass), "2024-07-01"), standard_scale()).unwrap();
+        assert_eq!(grade.value(), GradeValue::Mark(GradeMark::Pass));
+        let chain = grade.amendments().to_vec();
+        
+        let mut restored = Grade::with_mark("数学".to_string(), GradeMark::Pass).unwrap();
+        restored.restore_amendments(chain.clone()).unwrap();
+        assert_eq!(restored, grade);
+        
+        // 最后一次修改必须得到当前成绩
+        let mut other = Grade::new("数学".to_string(), 62.0).unwrap();
+        assert!(other.restore_amendments(chain.clone()).is_err());
+        assert!(other.amendments().is_empty());
+        
+        // 中间缺少一次修改
+        let mut forged = chain.clone();
+        forged[1].old_value = GradeValue::Score(70.0);
+        assert!(restored.restore_amendments(forged).is_err());
+        
+        // 日期倒序
+        let mut forged = chain;
+        forged[0].date = "2024-07-02".parse().unwrap();
+        assert!(restored.restore_amendments(forged).is_err());
     }
 }
\ No newline at end of file
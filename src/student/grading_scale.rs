@@ -0,0 +1,291 @@
+//! 等级制模块
+//!
+//! 定义分数到等级和绩点的映射。不同培养方案的分数线不同
+//! （例如85分即为A，或者优/良/中/及格/不及格五级制），
+//! 成绩、绩点计算和课程统计都通过 `GradingScale` 完成映射。
+//!
+//! 配置文件每行定义一档，格式为 `名称 最低分 等级 [绩点]`，
+//! `#` 开头的行是注释，绩点省略时使用等级的标准绩点：
+//!
+//! ```text
+//! # 五级制
+//! 优   90 A 4.0
+//! 良   80 B 3.0
+//! 中   70 C 2.0
+//! 及格 60 D 1.0
+//! 不及格 0 F 0.0
+//! ```
+
+use super::grade::LetterGrade;
+use crate::errors::{AppError, AppResult};
+use crate::utils::file_handler::read_file_to_string;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 等级制中的一档
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradeBand {
+    /// 达到这一档的最低分（含）
+    pub min_score: f64,
+    pub letter: LetterGrade,
+    /// 显示名称，例如 "A" 或 "优"
+    pub label: String,
+    pub gpa: f64,
+}
+
+impl GradeBand {
+    pub fn new(label: &str, min_score: f64, letter: LetterGrade, gpa: f64) -> Self {
+        GradeBand {
+            min_score,
+            letter,
+            label: label.to_string(),
+            gpa,
+        }
+    }
+}
+
+/// 等级制，各档按最低分从高到低排列
+///
+/// 分数按半开区间划分：每一档覆盖 `[本档最低分, 上一档最低分)`，
+/// 最高一档一直覆盖到100分。因此0-100之间的任何分数（包括89.95这样的
+/// 小数）都恰好落在一档中，不会因为区间之间的空隙被误判为F。
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradingScale {
+    bands: Vec<GradeBand>,
+}
+
+impl GradingScale {
+    /// 创建等级制并验证
+    ///
+    /// 要求：分数线在0-100之间且互不相同，最低一档从0分开始并且是F，
+    /// 分数越高绩点不能越低。
+    pub fn new(mut bands: Vec<GradeBand>) -> AppResult<Self> {
+        if bands.is_empty() {
+            return Err(AppError::ValidationError("等级制至少需要一档".to_string()));
+        }
+
+        for band in &bands {
+            if !(0.0..=100.0).contains(&band.min_score) {
+                return Err(AppError::ValidationError(
+                    format!("{} 的分数线 {} 必须在0-100之间", band.label, band.min_score)
+                ));
+            }
+            if !(0.0..=5.0).contains(&band.gpa) {
+                return Err(AppError::ValidationError(
+                    format!("{} 的绩点 {} 必须在0-5之间", band.label, band.gpa)
+                ));
+            }
+            if band.label.trim().is_empty() {
+                return Err(AppError::ValidationError("等级名称不能为空".to_string()));
+            }
+        }
+
+        bands.sort_by(|a, b| b.min_score.total_cmp(&a.min_score));
+
+        for pair in bands.windows(2) {
+            if pair[0].min_score == pair[1].min_score {
+                return Err(AppError::ValidationError(
+                    format!("分数线 {} 重复", pair[0].min_score)
+                ));
+            }
+            if pair[0].gpa < pair[1].gpa {
+                return Err(AppError::ValidationError(
+                    format!("{} 的绩点不能低于 {}", pair[0].label, pair[1].label)
+                ));
+            }
+        }
+
+        let lowest = &bands[bands.len() - 1];
+        if lowest.min_score != 0.0 {
+            return Err(AppError::ValidationError("最低一档必须从0分开始".to_string()));
+        }
+        if lowest.letter != LetterGrade::F
+            || bands[..bands.len() - 1].iter().any(|b| b.letter == LetterGrade::F)
+        {
+            return Err(AppError::ValidationError(
+                "最低一档必须是F（不及格），且只能有这一档是F".to_string()
+            ));
+        }
+
+        Ok(GradingScale { bands })
+    }
+
+    /// 标准等级制：90/80/70/60
+    pub fn standard() -> Self {
+        standard_scale().clone()
+    }
+
+    /// 五级制：优/良/中/及格/不及格
+    pub fn five_level() -> Self {
+        GradingScale::new(vec![
+            GradeBand::new("优", 90.0, LetterGrade::A, 4.0),
+            GradeBand::new("良", 80.0, LetterGrade::B, 3.0),
+            GradeBand::new("中", 70.0, LetterGrade::C, 2.0),
+            GradeBand::new("及格", 60.0, LetterGrade::D, 1.0),
+            GradeBand::new("不及格", 0.0, LetterGrade::F, 0.0),
+        ])
+        .expect("内置等级制必须有效")
+    }
+
+    /// 带加减号的等级制：A 93、A- 90、B+ 87 …… D- 60
+    pub fn plus_minus() -> Self {
+        let thresholds = [93.0, 90.0, 87.0, 83.0, 80.0, 77.0, 73.0, 70.0, 67.0, 63.0, 60.0, 0.0];
+        let bands = LetterGrade::ALL.iter()
+            .zip(thresholds)
+            .map(|(letter, min_score)| {
+                GradeBand::new(letter.as_str(), min_score, letter.clone(), letter.to_gpa())
+            })
+            .collect();
+        GradingScale::new(bands).expect("内置等级制必须有效")
+    }
+
+    /// 从配置文本解析等级制
+    pub fn from_config_str(content: &str) -> AppResult<Self> {
+        let mut bands = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let band = parse_band(line).map_err(|e| AppError::InvalidInput(
+                format!("等级制配置第{}行: {}", index + 1, e)
+            ))?;
+            bands.push(band);
+        }
+
+        GradingScale::new(bands)
+    }
+
+    /// 从配置文件加载等级制
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> AppResult<Self> {
+        Self::from_config_str(&read_file_to_string(path)?)
+    }
+
+    pub fn bands(&self) -> &[GradeBand] {
+        &self.bands
+    }
+
+    /// 分数所在的一档，即最低分不超过该分数的最高一档
+    pub fn band_for(&self, score: f64) -> &GradeBand {
+        self.bands.iter()
+            .find(|band| score >= band.min_score)
+            .unwrap_or(&self.bands[self.bands.len() - 1])
+    }
+
+    pub fn letter_for(&self, score: f64) -> LetterGrade {
+        self.band_for(score).letter.clone()
+    }
+
+    pub fn gpa_for(&self, score: f64) -> f64 {
+        self.band_for(score).gpa
+    }
+
+    /// 及格线，即最低一个非F档的分数线
+    pub fn passing_score(&self) -> f64 {
+        self.bands.iter()
+            .rev()
+            .find(|band| band.letter != LetterGrade::F)
+            .map(|band| band.min_score)
+            .unwrap_or(f64::INFINITY)
+    }
+
+    pub fn is_passing(&self, score: f64) -> bool {
+        self.band_for(score).letter != LetterGrade::F
+    }
+}
+
+impl Default for GradingScale {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// 共享的标准等级制，避免每次计算都重新构建
+pub(crate) fn standard_scale() -> &'static GradingScale {
+    static STANDARD: OnceLock<GradingScale> = OnceLock::new();
+    STANDARD.get_or_init(|| {
+        GradingScale::new(vec![
+            GradeBand::new("A", 90.0, LetterGrade::A, 4.0),
+            GradeBand::new("B", 80.0, LetterGrade::B, 3.0),
+            GradeBand::new("C", 70.0, LetterGrade::C, 2.0),
+            GradeBand::new("D", 60.0, LetterGrade::D, 1.0),
+            GradeBand::new("F", 0.0, LetterGrade::F, 0.0),
+        ])
+        .expect("内置等级制必须有效")
+    })
+}
+
+fn parse_band(line: &str) -> AppResult<GradeBand> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return Err(AppError::InvalidInput(
+            "格式应为 `名称 最低分 等级 [绩点]`".to_string()
+        ));
+    }
+
+    let min_score: f64 = parts[1].parse()
+        .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的分数", parts[1])))?;
+    let letter: LetterGrade = parts[2].parse()?;
+    let gpa = match parts.get(3) {
+        Some(gpa) => gpa.parse()
+            .map_err(|_| AppError::InvalidInput(format!("'{}' 不是有效的绩点", gpa)))?,
+        None => letter.to_gpa(),
+    };
+
+    Ok(GradeBand::new(parts[0], min_score, letter, gpa))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_threshold_scale() {
+        let scale = GradingScale::from_config_str(
+            "# 85分即为A\nA 85 A\nB 75 B\nC 65 C\nD 60 D\nF 0 F\n"
+        ).unwrap();
+
+        assert_eq!(scale.letter_for(86.0), LetterGrade::A);
+        assert_eq!(scale.letter_for(84.9), LetterGrade::B);
+        assert_eq!(scale.gpa_for(85.0), 4.0);
+        assert_eq!(scale.passing_score(), 60.0);
+    }
+
+    #[test]
+    fn test_five_level_scale() {
+        let scale = GradingScale::five_level();
+        assert_eq!(scale.band_for(95.0).label, "优");
+        assert_eq!(scale.band_for(59.0).label, "不及格");
+        assert_eq!(scale.band_for(85.0).gpa, 3.0);
+        assert!(!scale.is_passing(59.9));
+    }
+
+    #[test]
+    fn test_plus_minus_scale() {
+        let scale = GradingScale::plus_minus();
+        assert_eq!(scale.letter_for(93.0), LetterGrade::A);
+        assert_eq!(scale.letter_for(92.9), LetterGrade::AMinus);
+        assert_eq!(scale.letter_for(88.0), LetterGrade::BPlus);
+        assert_eq!(scale.gpa_for(81.0), 2.7);
+        assert_eq!(scale.letter_for(60.0), LetterGrade::DMinus);
+        assert_eq!(scale.letter_for(59.9), LetterGrade::F);
+
+        let custom = GradingScale::from_config_str("A 90 A\nA- 85 A-\nB+ 80 B+\nF 0 F\n").unwrap();
+        assert_eq!(custom.gpa_for(86.0), 3.7);
+    }
+
+    #[test]
+    fn test_invalid_scales_are_rejected() {
+        // 没有从0分开始
+        assert!(GradingScale::from_config_str("A 90 A\nF 10 F\n").is_err());
+        // 重复分数线
+        assert!(GradingScale::from_config_str("A 60 A\nB 60 B\nF 0 F\n").is_err());
+        // 最低档不是F
+        assert!(GradingScale::from_config_str("A 60 A\nD 0 D\n").is_err());
+        // 高分绩点更低
+        assert!(GradingScale::from_config_str("A 90 A 3.0\nB 80 B 3.5\nF 0 F\n").is_err());
+        // 格式错误
+        assert!(GradingScale::from_config_str("A ninety A\nF 0 F\n").is_err());
+    }
+}
@@ -2,18 +2,34 @@
 //! 
 //! 包含学生相关的所有功能
 
+pub mod amendment;
+pub mod contact;
 pub mod grade;
+pub mod grading_scale;
+pub mod status;
 pub mod validator;
 
 use crate::errors::{AppError, AppResult};
-use self::grade::Grade;
+use self::amendment::{GradeAmendment, GradeChange};
+use self::contact::{ContactInfo, Guardian};
+use self::grade::{Grade, GradeMark};
+use self::grading_scale::{standard_scale, GradingScale};
+use self::status::StatusChange;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "StudentData"))]
 pub struct Student {
     pub id: u32,
     pub name: String,
     pub age: u8,
     pub grades: Vec<Grade>,
+    pub contact: ContactInfo,
+    /// 按添加顺序排列，姓名不重复
+    pub guardians: Vec<Guardian>,
+    /// 学籍状态变更记录，只能通过 `change_status` 追加
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    status_history: Vec<StatusChange>,
 }
 
 impl Student {
@@ -26,27 +42,168 @@ impl Student {
             name,
             age,
             grades: Vec::new(),
+            contact: ContactInfo::default(),
+            guardians: Vec::new(),
+            status_history: Vec::new(),
         })
     }
     
+    /// 设置联系方式，联系方式在创建时已经验证过
+    pub fn with_contact(mut self, contact: ContactInfo) -> Self {
+        self.contact = contact;
+        self
+    }
+    
+    /// 学生的邮箱，没有填写时为 `None`
+    pub fn email(&self) -> Option<&str> {
+        self.contact.email()
+    }
+    
+    /// 添加监护人，同名监护人只能有一位
+    pub fn add_guardian(&mut self, guardian: Guardian) -> AppResult<()> {
+        if self.guardians.iter().any(|g| g.name() == guardian.name()) {
+            return Err(AppError::ValidationError(
+                format!("学生 {} 已有监护人 {}", self.name, guardian.name())
+            ));
+        }
+        self.guardians.push(guardian);
+        Ok(())
+    }
+    
+    /// 按姓名移除监护人
+    pub fn remove_guardian(&mut self, name: &str) -> AppResult<Guardian> {
+        let position = self.guardians.iter()
+            .position(|g| g.name() == name)
+            .ok_or_else(|| AppError::NotFound(
+                format!("学生 {} 没有监护人 {}", self.name, name)
+            ))?;
+        Ok(self.guardians.remove(position))
+    }
+    
+    /// 按标准等级制录入分数成绩
+    ///
+    /// 学生加入 `EnrollmentManager` 时，分数成绩按管理器的等级制重新评定等级。
     pub fn add_grade(&mut self, subject: String, score: f64) -> AppResult<()> {
         let grade = Grade::new(subject, score)?;
         self.grades.push(grade);
         Ok(())
     }
     
+    pub fn add_grade_with_scale(
+        &mut self,
+        subject: String,
+        score: f64,
+        scale: &GradingScale,
+    ) -> AppResult<()> {
+        let grade = Grade::with_scale(subject, score, scale)?;
+        self.grades.push(grade);
+        Ok(())
+    }
+    
+    /// 录入不以分数记录的成绩
+    pub fn add_mark(&mut self, subject: String, mark: GradeMark) -> AppResult<()> {
+        let grade = Grade::with_mark(subject, mark)?;
+        self.grades.push(grade);
+        Ok(())
+    }
+    
+    /// 属于指定学期的成绩
+    pub fn grades_in_term(&self, term_id: u32) -> Vec<&Grade> {
+        self.grades.iter()
+            .filter(|g| g.term_id() == Some(term_id))
+            .collect()
+    }
+    
+    /// 属于指定课程的成绩
+    pub fn grades_in_course(&self, course_id: u32) -> Vec<&Grade> {
+        self.grades.iter()
+            .filter(|g| g.course_id() == Some(course_id))
+            .collect()
+    }
+    
+    /// 分数成绩的平均分，通过、旁听等不以分数记录的成绩和已退出（W）课程的成绩不计入；
+    /// 没有分数成绩时为0
     pub fn average_grade(&self) -> f64 {
-        if self.grades.is_empty() {
+        let scores: Vec<f64> = self.grades.iter()
+            .filter(|g| !g.is_withdrawn())
+            .filter_map(|g| g.score())
+            .collect();
+        if scores.is_empty() {
             0.0
         } else {
-            let sum: f64 = self.grades.iter().map(|g| g.score()).sum();
-            sum / self.grades.len() as f64
+            scores.iter().sum::<f64>() / scores.len() as f64
         }
     }
     
     pub fn get_grade_by_subject(&self, subject: &str) -> Option<&Grade> {
         self.grades.iter().find(|g| g.subject() == subject)
     }
+    
+    /// 按标准等级制修改不属于任何课程的成绩，见 `amend_grade_in`
+    pub fn amend_grade(&mut self, subject: &str, change: GradeChange) -> AppResult<&GradeAmendment> {
+        self.amend_grade_in(None, subject, change, standard_scale())
+    }
+    
+    /// 修改指定课程（`None` 表示不属于任何课程）中某个科目的成绩
+    ///
+    /// 同一科目有多条成绩时修改最近录入的一条。
+    pub fn amend_grade_in(
+        &mut self,
+        course_id: Option<u32>,
+        subject: &str,
+        change: GradeChange,
+        scale: &GradingScale,
+    ) -> AppResult<&GradeAmendment> {
+        let grade = self.grades.iter_mut()
+            .rev()
+            .find(|g| g.course_id() == course_id && g.subject() == subject)
+            .ok_or_else(|| AppError::NotFound(match course_id {
+                Some(course_id) => format!("学生 {} 在课程ID {} 中没有 {} 的成绩", self.name, course_id, subject),
+                None => format!("学生 {} 没有 {} 的成绩", self.name, subject),
+            }))?;
+        grade.amend(change, scale)
+    }
+    
+    /// 所有成绩的修改记录，按成绩录入顺序和修改顺序排列
+    pub fn grade_amendments(&self) -> Vec<(&Grade, &GradeAmendment)> {
+        self.grades.iter()
+            .flat_map(|grade| grade.amendments().iter().map(move |amendment| (grade, amendment)))
+            .collect()
+    }
+}
+
+/// 反序列化时的原始数据，经过 `Student::new` 验证
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct StudentData {
+    id: u32,
+    name: String,
+    age: u8,
+    #[serde(default)]
+    grades: Vec<Grade>,
+    #[serde(default)]
+    contact: ContactInfo,
+    #[serde(default)]
+    guardians: Vec<Guardian>,
+    #[serde(default)]
+    status_history: Vec<StatusChange>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<StudentData> for Student {
+    type Error = crate::errors::AppError;
+
+    fn try_from(data: StudentData) -> AppResult<Self> {
+        let mut student = Student::new(data.id, data.name, data.age)?.with_contact(data.contact);
+        student.grades = data.grades;
+        for guardian in data.guardians {
+            student.add_guardian(guardian)?;
+        }
+        for change in data.status_history {
+            student.change_status(change.to, change.date)?;
+        }
+        Ok(student)
+    }
 }
 
 impl fmt::Display for Student {
@@ -55,4 +212,68 @@ impl fmt::Display for Student {
     }
 }
 
-use std::fmt;
\ No newline at end of file
+use std::fmt;
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_student_json_round_trip() {
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        student.add_grade("数学".to_string(), 92.5).unwrap();
+        student.add_mark("研讨课".to_string(), GradeMark::Pass).unwrap();
+        let change = GradeChange::new(
+            amendment::GradeValue::Mark(GradeMark::Fail), "缺交报告", "王老师", "2024-12-20".parse().unwrap(),
+        ).unwrap();
+        student.amend_grade("研讨课", change).unwrap();
+        let contact = ContactInfo::new(Some("zhangsan@example.com".to_string()), None, None).unwrap();
+        student = student.with_contact(contact.clone());
+        student.add_guardian(Guardian::new("张父".to_string(), "父亲".to_string(), contact).unwrap()).unwrap();
+        student.change_status(status::StudentStatus::OnLeave, "2024-03-01".parse().unwrap()).unwrap();
+
+        let json = serde_json::to_string(&student).unwrap();
+        assert!(json.contains("\"letter_grade\":\"A\""));
+        assert_eq!(serde_json::from_str::<Student>(&json).unwrap(), student);
+    }
+
+    #[test]
+    fn test_status_json_names() {
+        let json = serde_json::to_string(&status::StudentStatus::DroppedOut).unwrap();
+        assert_eq!(json, "\"dropped_out\"");
+        assert_eq!(serde_json::from_str::<status::StudentStatus>(&json).unwrap(), status::StudentStatus::DroppedOut);
+        assert!(serde_json::from_str::<status::StudentStatus>("\"withdrawn\"").is_err());
+    }
+
+    #[test]
+    fn test_invalid_json_is_rejected() {
+        let bad_age = r#"{"id":1,"name":"张三","age":3,"grades":[]}"#;
+        assert!(serde_json::from_str::<Student>(bad_age).is_err());
+
+        let bad_email = r#"{"id":1,"name":"张三","age":20,"contact":{"email":"zhangsan"}}"#;
+        assert!(serde_json::from_str::<Student>(bad_email).is_err());
+
+        let bad_score = r#"{"subject":"数学","score":120.0}"#;
+        assert!(serde_json::from_str::<Grade>(bad_score).is_err());
+
+        // 保存的等级原样保留，缺少等级时按标准等级制计算
+        let other_scale = r#"{"subject":"数学","score":72.0,"letter_grade":"B"}"#;
+        let grade: Grade = serde_json::from_str(other_scale).unwrap();
+        assert_eq!(grade.letter_grade(), Some(&grade::LetterGrade::B));
+        let no_letter = r#"{"subject":"数学","score":72.0}"#;
+        let grade: Grade = serde_json::from_str(no_letter).unwrap();
+        assert_eq!(grade.letter_grade(), Some(&grade::LetterGrade::C));
+        let mark_with_letter = r#"{"subject":"研讨课","letter_grade":"A","mark":"pass"}"#;
+        assert!(serde_json::from_str::<Grade>(mark_with_letter).is_err());
+
+        let mark = r#"{"subject":"研讨课","mark":"pass"}"#;
+        assert_eq!(serde_json::from_str::<Grade>(mark).unwrap().mark(), Some(GradeMark::Pass));
+        let both = r#"{"subject":"数学","score":50.0,"mark":"pass"}"#;
+        assert!(serde_json::from_str::<Grade>(both).is_err());
+
+        let amended = r#"{"subject":"数学","score":62.0,"amendments":[{"old_value":{"score":58.0},"new_value":{"score":62.0},"reason":"复核","amended_by":"王老师","date":"2024-07-01"}]}"#;
+        assert_eq!(serde_json::from_str::<Grade>(amended).unwrap().amendments().len(), 1);
+        let forged = amended.replace(r#""score":62.0,"amendments""#, r#""score":90.0,"amendments""#);
+        assert!(serde_json::from_str::<Grade>(&forged).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! 学籍状态模块
+//!
+//! 新学生都是在读状态，之后只能按 `StudentStatus::can_transition_to`
+//! 允许的方向变更，每次变更都记录日期。毕业和退学是终止状态。
+
+use super::Student;
+use crate::errors::{AppError, AppResult};
+use crate::utils::date::Date;
+use std::fmt;
+use std::str::FromStr;
+
+/// 学籍状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StudentStatus {
+    #[default]
+    Active,
+    /// 停学，处分期间不能选课
+    Suspended,
+    /// 休学，之后可以复学
+    OnLeave,
+    Graduated,
+    /// 退学；与退出单门课程（成绩单上的W）无关
+    DroppedOut,
+}
+
+impl StudentStatus {
+    pub const ALL: [StudentStatus; 5] = [
+        StudentStatus::Active, StudentStatus::Suspended, StudentStatus::OnLeave,
+        StudentStatus::Graduated, StudentStatus::DroppedOut,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StudentStatus::Active => "在读",
+            StudentStatus::Suspended => "停学",
+            StudentStatus::OnLeave => "休学",
+            StudentStatus::Graduated => "毕业",
+            StudentStatus::DroppedOut => "退学",
+        }
+    }
+
+    /// 只有在读的学生可以注册课程或排入候补名单
+    pub fn can_enroll(&self) -> bool {
+        *self == StudentStatus::Active
+    }
+
+    /// 是否允许从当前状态变更为 `next`
+    ///
+    /// 在读可以变为其他任何状态；停学和休学只能恢复在读或退学；
+    /// 毕业和退学之后不能再变更。
+    pub fn can_transition_to(&self, next: StudentStatus) -> bool {
+        use StudentStatus::*;
+        match self {
+            Active => next != Active,
+            Suspended | OnLeave => matches!(next, Active | DroppedOut),
+            Graduated | DroppedOut => false,
+        }
+    }
+}
+
+impl fmt::Display for StudentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for StudentStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let s = s.trim();
+        StudentStatus::ALL.iter()
+            .find(|status| status.as_str() == s)
+            .copied()
+            .ok_or_else(|| AppError::InvalidInput(format!("'{}' 不是有效的学籍状态", s)))
+    }
+}
+
+/// 一次学籍状态变更
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusChange {
+    pub from: StudentStatus,
+    pub to: StudentStatus,
+    pub date: Date,
+}
+
+impl Student {
+    /// 当前的学籍状态
+    pub fn status(&self) -> StudentStatus {
+        self.status_history.last().map_or(StudentStatus::Active, |change| change.to)
+    }
+
+    /// 进入当前状态的日期，从未变更过时为 `None`
+    pub fn status_since(&self) -> Option<Date> {
+        self.status_history.last().map(|change| change.date)
+    }
+
+    /// 学籍状态的变更记录，按时间顺序排列
+    pub fn status_history(&self) -> &[StatusChange] {
+        &self.status_history
+    }
+
+    /// 变更学籍状态，日期不能早于上一次变更
+    pub fn change_status(&mut self, to: StudentStatus, date: Date) -> AppResult<()> {
+        let from = self.status();
+        if !from.can_transition_to(to) {
+            return Err(AppError::ValidationError(
+                format!("学生 {} 的学籍状态不能从{}变为{}", self.name, from, to)
+            ));
+        }
+        if let Some(since) = self.status_since().filter(|&since| date < since) {
+            return Err(AppError::ValidationError(
+                format!("状态变更日期 {} 早于上一次变更 {}", date, since)
+            ));
+        }
+        self.status_history.push(StatusChange { from, to, date });
+        Ok(())
+    }
+
+    /// 检查学生当前可以选课
+    pub(crate) fn require_can_enroll(&self) -> AppResult<()> {
+        let status = self.status();
+        if !status.can_enroll() {
+            return Err(AppError::ValidationError(
+                format!("学生 {} 当前为{}状态，不能选课", self.name, status)
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::course::test_support::date;
+
+    #[test]
+    fn test_allowed_transitions() {
+        use StudentStatus::*;
+        assert!(Active.can_transition_to(OnLeave));
+        assert!(OnLeave.can_transition_to(Active));
+        assert!(Suspended.can_transition_to(DroppedOut));
+        assert!(!Suspended.can_transition_to(Graduated));
+        assert!(!Active.can_transition_to(Active));
+        assert!(StudentStatus::ALL.iter().all(|&next| !Graduated.can_transition_to(next)));
+        assert_eq!("休学".parse::<StudentStatus>().unwrap(), OnLeave);
+    }
+
+    #[test]
+    fn test_status_history() {
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        assert_eq!(student.status(), StudentStatus::Active);
+        assert_eq!(student.status_since(), None);
+
+        student.change_status(StudentStatus::OnLeave, date("2024-03-01")).unwrap();
+        assert!(student.change_status(StudentStatus::Graduated, date("2024-06-30")).is_err());
+        assert!(student.change_status(StudentStatus::Active, date("2024-02-01")).is_err());
+        student.change_status(StudentStatus::Active, date("2024-09-01")).unwrap();
+        student.change_status(StudentStatus::Graduated, date("2025-06-30")).unwrap();
+
+        assert_eq!(student.status(), StudentStatus::Graduated);
+        assert_eq!(student.status_since(), Some(date("2025-06-30")));
+        assert_eq!(student.status_history().len(), 3);
+        assert_eq!(student.status_history()[1].from, StudentStatus::OnLeave);
+        assert!(student.require_can_enroll().is_err());
+    }
+}
@@ -41,6 +41,22 @@ pub fn validate_name(name: &str) -> AppResult<()> {
     Ok(())
 }
 
+/// 电话号码：可以以 `+` 开头，数字之间可以用空格或 `-` 分隔，共7-15位数字
+pub fn validate_phone(phone: &str) -> AppResult<()> {
+    let digits = phone.strip_prefix('+').unwrap_or(phone);
+    // 分隔符只能单个出现在数字之间
+    let valid_groups = digits.split([' ', '-'])
+        .all(|group| !group.is_empty() && group.chars().all(|c| c.is_ascii_digit()));
+    let digit_count = digits.chars().filter(|c| c.is_ascii_digit()).count();
+    if !valid_groups || !(7..=15).contains(&digit_count) {
+        return Err(AppError::ValidationError(
+            format!("电话号码 '{}' 格式不正确", phone)
+        ));
+    }
+    
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,4 +86,16 @@ mod tests {
         assert!(validate_name("张三123").is_err());
         assert!(validate_name(&"a".repeat(51)).is_err());
     }
+    
+    #[test]
+    fn test_phone() {
+        assert!(validate_phone("13800000000").is_ok());
+        assert!(validate_phone("+86 10-6275-1234").is_ok());
+        assert!(validate_phone("123456").is_err());
+        assert!(validate_phone("1380000000a").is_err());
+        assert!(validate_phone("++8613800000000").is_err());
+        assert!(validate_phone("1-------2345678").is_err());
+        assert!(validate_phone("138 -0000-0000").is_err());
+        assert!(validate_phone("-13800000000").is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+//! CSV工具
+//!
+//! 只支持本项目需要的简单CSV：逗号分隔，字段可以用双引号包裹，
+//! 引号内的 `""` 表示一个双引号。
+
+use crate::errors::{AppError, AppResult};
+
+/// 解析一行CSV文本
+pub fn parse_line(line: &str) -> AppResult<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    current.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if current.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(AppError::InvalidInput("引号没有闭合".to_string()));
+    }
+    fields.push(current);
+    Ok(fields)
+}
+
+/// 将字段列表格式化为一行CSV（不含换行符）
+pub fn format_line<S: AsRef<str>>(fields: &[S]) -> String {
+    fields.iter()
+        .map(|field| escape_field(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("1,张三,20").unwrap(), vec!["1", "张三", "20"]);
+        assert_eq!(parse_line("\"a,b\",\"say \"\"hi\"\"\",").unwrap(), vec!["a,b", "say \"hi\"", ""]);
+        assert!(parse_line("\"open").is_err());
+    }
+
+    #[test]
+    fn test_format_line_round_trip() {
+        let fields = ["普通", "带,逗号", "带\"引号"];
+        let line = format_line(&fields);
+        assert_eq!(line, "普通,\"带,逗号\",\"带\"\"引号\"");
+        assert_eq!(parse_line(&line).unwrap(), fields);
+    }
+}
@@ -0,0 +1,266 @@
+//! 日期工具
+//!
+//! 只需要年月日精度的简单日期类型，格式为 `YYYY-MM-DD`；
+//! 以及课程表使用的星期和一天中的时刻（`HH:MM`）。
+
+use crate::errors::{AppError, AppResult};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    pub fn new(year: u16, month: u8, day: u8) -> AppResult<Self> {
+        if !(1..=12).contains(&month) {
+            return Err(AppError::ValidationError(format!("月份 {} 必须在1-12之间", month)));
+        }
+        let max_day = days_in_month(year, month);
+        if day == 0 || day > max_day {
+            return Err(AppError::ValidationError(
+                format!("{}年{}月只有{}天，日期 {} 无效", year, month, max_day, day)
+            ));
+        }
+        Ok(Date { year, month, day })
+    }
+
+    /// 当前日期（UTC）
+    pub fn today() -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() / 86_400;
+        Self::from_days_since_epoch(days as i64)
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// 由1970-01-01起的天数换算日期（Howard Hinnant 的 civil_from_days 算法）
+    fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as u16;
+        Date { year, month, day }
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+impl FromStr for Date {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidInput(format!("'{}' 不是有效的日期，格式应为 YYYY-MM-DD", s));
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        let year = parts[0].parse().map_err(|_| invalid())?;
+        let month = parts[1].parse().map_err(|_| invalid())?;
+        let day = parts[2].parse().map_err(|_| invalid())?;
+        Date::new(year, month, day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        value.parse()
+    }
+}
+
+/// 星期，从周一开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday,
+        Weekday::Friday, Weekday::Saturday, Weekday::Sunday,
+    ];
+
+    /// 1表示周一，7表示周日
+    pub fn number(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Weekday::Monday => "周一",
+            Weekday::Tuesday => "周二",
+            Weekday::Wednesday => "周三",
+            Weekday::Thursday => "周四",
+            Weekday::Friday => "周五",
+            Weekday::Saturday => "周六",
+            Weekday::Sunday => "周日",
+        }
+    }
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = AppError;
+
+    /// 接受 `周一` 这样的中文名称或 `1`-`7` 的数字
+    fn from_str(s: &str) -> AppResult<Self> {
+        let s = s.trim();
+        Weekday::ALL.iter()
+            .find(|day| day.as_str() == s || day.number().to_string() == s)
+            .copied()
+            .ok_or_else(|| AppError::InvalidInput(format!("'{}' 不是有效的星期", s)))
+    }
+}
+
+/// 一天中的时刻，精确到分钟
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    pub fn new(hour: u8, minute: u8) -> AppResult<Self> {
+        if hour > 23 || minute > 59 {
+            return Err(AppError::ValidationError(
+                format!("时刻 {}:{:02} 无效", hour, minute)
+            ));
+        }
+        Ok(TimeOfDay { hour, minute })
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidInput(format!("'{}' 不是有效的时刻，格式应为 HH:MM", s));
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(invalid)?;
+        TimeOfDay::new(hour.parse().map_err(|_| invalid())?, minute.parse().map_err(|_| invalid())?)
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let date: Date = "2024-02-29".parse().unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024/01/01".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_ordering_and_epoch_conversion() {
+        assert!(Date::new(2024, 9, 1).unwrap() < Date::new(2025, 1, 15).unwrap());
+        assert_eq!(Date::from_days_since_epoch(0), Date::new(1970, 1, 1).unwrap());
+        assert_eq!(Date::from_days_since_epoch(19_782), Date::new(2024, 2, 29).unwrap());
+    }
+
+    #[test]
+    fn test_weekday_and_time_of_day() {
+        assert_eq!("周三".parse::<Weekday>().unwrap(), Weekday::Wednesday);
+        assert_eq!("7".parse::<Weekday>().unwrap(), Weekday::Sunday);
+        assert!("周八".parse::<Weekday>().is_err());
+
+        let time: TimeOfDay = "8:05".parse().unwrap();
+        assert_eq!(time.to_string(), "08:05");
+        assert!(time < "13:00".parse().unwrap());
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("0830".parse::<TimeOfDay>().is_err());
+    }
+}
@@ -1,21 +1,138 @@
 //! 文件处理工具
 
 use crate::errors::{AppError, AppResult};
-use std::fs;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 校验和行的前缀，后面跟8位十六进制的CRC32
+const CHECKSUM_PREFIX: &str = "# crc32 ";
+
+/// 目录锁文件名
+const LOCK_FILE_NAME: &str = ".lock";
+
+/// 临时文件序号，保证同一进程内并发写入使用不同的临时文件
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 pub fn read_file_to_string<P: AsRef<Path>>(path: P) -> AppResult<String> {
     fs::read_to_string(path).map_err(AppError::from)
 }
 
+/// 原子地写入文件
+///
+/// 先写入同目录下的临时文件并刷到磁盘，再重命名覆盖目标文件。
+/// 中途崩溃时目标文件要么是旧内容，要么是完整的新内容。
+/// 每次调用使用独立的临时文件，并发写入同一目标时以最后完成的重命名为准。
 pub fn write_string_to_file<P: AsRef<Path>>(path: P, content: &str) -> AppResult<()> {
-    fs::write(path, content).map_err(AppError::from)
+    let path = path.as_ref();
+    let file_name = path.file_name()
+        .ok_or_else(|| AppError::InvalidInput(format!("{} 不是文件路径", path.display())))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = write_and_sync(&temp_path, content)
+        .and_then(|_| fs::rename(&temp_path, path).map_err(AppError::from));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    sync_parent_directory(path)
+}
+
+fn write_and_sync(path: &Path, content: &str) -> AppResult<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
 }
 
-pub fn ensure_directory_exists<P: AsRef<Path>>(path: P) -> AppResult<()> {
-    if !path.as_ref().exists() {
-        fs::create_dir_all(path).map_err(AppError::from)?;
+/// 把目录项的变化（重命名）也刷到磁盘
+#[cfg(unix)]
+fn sync_parent_directory(path: &Path) -> AppResult<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_directory(_path: &Path) -> AppResult<()> {
+    Ok(())
+}
+
+/// 在内容末尾附加一行CRC32校验和
+pub fn append_checksum(content: &str) -> String {
+    let mut output = content.to_string();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
     }
+    let checksum = crc32(output.as_bytes());
+    output.push_str(&format!("{}{:08x}\n", CHECKSUM_PREFIX, checksum));
+    output
+}
+
+/// 校验末尾的校验和行，返回校验和之前的内容
+///
+/// 缺少校验和行或校验和不一致时返回 `AppError::CorruptedData`。
+pub fn verify_checksum(content: &str) -> AppResult<&str> {
+    let (body, expected) = split_checksum(content)
+        .ok_or_else(|| AppError::CorruptedData("缺少校验和，文件可能被截断".to_string()))?;
+
+    let expected = u32::from_str_radix(expected, 16)
+        .map_err(|_| AppError::CorruptedData(format!("校验和 '{}' 格式错误", expected)))?;
+    let actual = crc32(body.as_bytes());
+    if actual != expected {
+        return Err(AppError::CorruptedData(format!(
+            "校验和不匹配（期望 {:08x}，实际 {:08x}）", expected, actual
+        )));
+    }
+    Ok(body)
+}
+
+fn split_checksum(content: &str) -> Option<(&str, &str)> {
+    let trimmed = content.strip_suffix('\n').unwrap_or(content);
+    let line_start = trimmed.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let checksum = trimmed[line_start..].strip_prefix(CHECKSUM_PREFIX)?;
+    Some((&trimmed[..line_start], checksum.trim()))
+}
+
+/// CRC32（IEEE 802.3 多项式）
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// 在文件末尾追加一行并立即刷到磁盘，文件不存在时自动创建
+pub fn append_line_to_file<P: AsRef<Path>>(path: P, line: &str) -> AppResult<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// 把文件截断到指定长度（字节）并立即刷到磁盘
+pub fn truncate_file<P: AsRef<Path>>(path: P, len: u64) -> AppResult<()> {
+    let file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(len)?;
+    file.sync_data()?;
     Ok(())
 }
 
@@ -23,10 +140,47 @@ pub fn file_exists<P: AsRef<Path>>(path: P) -> bool {
     path.as_ref().exists()
 }
 
+/// 数据目录的咨询锁
+///
+/// 锁保存在目录下的 `.lock` 文件上，只对同样使用本锁的进程有效。
+/// 值被丢弃（或进程退出）时自动释放。
+#[derive(Debug)]
+pub struct DirectoryLock {
+    file: File,
+}
+
+impl DirectoryLock {
+    /// 尝试获取目录锁，锁被占用时立即返回错误
+    pub fn try_acquire<P: AsRef<Path>>(directory: P) -> AppResult<Self> {
+        let file = open_lock_file(directory.as_ref())?;
+        match file.try_lock() {
+            Ok(()) => Ok(DirectoryLock { file }),
+            Err(TryLockError::WouldBlock) => Err(AppError::StorageError(
+                format!("目录 {} 正被其他进程使用", directory.as_ref().display())
+            )),
+            Err(TryLockError::Error(e)) => Err(AppError::from(e)),
+        }
+    }
+}
+
+impl Drop for DirectoryLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn open_lock_file(directory: &Path) -> AppResult<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(directory.join(LOCK_FILE_NAME))?;
+    Ok(file)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use tempfile::tempdir;
     
     #[test]
@@ -44,12 +198,76 @@ mod tests {
     }
     
     #[test]
-    fn test_ensure_directory_exists() {
+    fn test_append_line_to_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("journal.log");
+        
+        append_line_to_file(&file_path, "first").unwrap();
+        append_line_to_file(&file_path, "second").unwrap();
+        
+        assert_eq!(read_file_to_string(&file_path).unwrap(), "first\nsecond\n");
+        
+        truncate_file(&file_path, 6).unwrap();
+        assert_eq!(read_file_to_string(&file_path).unwrap(), "first\n");
+    }
+    
+    #[test]
+    fn test_atomic_write_replaces_content() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("data.txt");
+        
+        write_string_to_file(&file_path, "old").unwrap();
+        write_string_to_file(&file_path, "new").unwrap();
+        
+        assert_eq!(read_file_to_string(&file_path).unwrap(), "new");
+        // 临时文件不应残留
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+    
+    #[test]
+    fn test_concurrent_atomic_writes_do_not_collide() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("data.txt");
+        
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let file_path = &file_path;
+                scope.spawn(move || {
+                    let content = format!("writer {}\n", i).repeat(1000);
+                    write_string_to_file(file_path, &content).unwrap();
+                });
+            }
+        });
+        
+        let content = read_file_to_string(&file_path).unwrap();
+        let first_line = content.lines().next().unwrap();
+        assert!(content.lines().all(|line| line == first_line));
+        assert_eq!(content.lines().count(), 1000);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+    
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("data.txt");
+        
+        write_string_to_file(&file_path, &append_checksum("line 1\nline 2\n")).unwrap();
+        let content = read_file_to_string(&file_path).unwrap();
+        assert_eq!(verify_checksum(&content).unwrap(), "line 1\nline 2\n");
+        
+        let corrupted = content.replace("line 2", "line 3");
+        assert!(matches!(verify_checksum(&corrupted), Err(AppError::CorruptedData(_))));
+        assert!(matches!(verify_checksum("line 1\n"), Err(AppError::CorruptedData(_))));
+    }
+    
+    #[test]
+    fn test_directory_lock_is_exclusive() {
         let dir = tempdir().unwrap();
-        let new_dir = dir.path().join("new_directory");
         
-        assert!(!new_dir.exists());
-        ensure_directory_exists(&new_dir).unwrap();
-        assert!(new_dir.exists());
+        let lock = DirectoryLock::try_acquire(dir.path()).unwrap();
+        assert!(DirectoryLock::try_acquire(dir.path()).is_err());
+        
+        drop(lock);
+        assert!(DirectoryLock::try_acquire(dir.path()).is_ok());
     }
 }
\ No newline at end of file
@@ -1,5 +1,9 @@
 //! 格式化工具
 
+use crate::course::instructor::TeachingLoad;
+use crate::course::programme::{CourseAudit, DegreeAudit};
+use crate::course::schedule::Timetable;
+use crate::course::transcript::{TermTranscript, Transcript, TranscriptEntry};
 use crate::Student;
 
 pub fn format_student_list(students: &[&Student]) -> String {
@@ -31,21 +35,194 @@ pub fn format_grade_table(student: &Student) -> String {
     table.push_str("│      科目       │ 分数 │ 等级 │\n");
     table.push_str("├─────────────────┼──────┼──────┤\n");
     
+    // 不以分数记录的成绩没有分数，等级列显示结果代码
     for grade in &student.grades {
+        let (score, letter) = match (grade.score(), grade.letter_grade(), grade.mark()) {
+            (Some(score), Some(letter), _) => (format!("{:4.1}", score), letter.to_string()),
+            (_, _, Some(mark)) => ("  --".to_string(), mark.to_string()),
+            _ => ("  --".to_string(), "--".to_string()),
+        };
         table.push_str(&format!(
-            "│ {:15} │ {:4.1} │  {:2}  │\n",
+            "│ {:15} │ {} │  {:2}  │\n",
             grade.subject(),
-            grade.score(),
-            grade.letter_grade()
+            score,
+            letter
         ));
     }
     
     table.push_str("└─────────────────┴──────┴──────┘\n");
-    table.push_str(&format!("平均分: {:.1}", student.average_grade()));
+    if student.grades.iter().any(|g| g.is_numeric()) {
+        table.push_str(&format!("平均分: {:.1}", student.average_grade()));
+    } else {
+        table.push_str("平均分: --");
+    }
+    
+    table
+}
+
+/// 格式化按课程排列的成绩单，末尾给出学分加权绩点
+pub fn format_transcript(transcript: &Transcript) -> String {
+    let mut table = format!("{}的课程成绩单:\n", transcript.student_name);
+    if transcript.entries.is_empty() {
+        table.push_str("还没有注册任何课程");
+        return table;
+    }
+    
+    push_course_table(&mut table, &transcript.entries);
+    table.push_str(&format!(
+        "加权绩点: {:.2} (计入学分: {})",
+        transcript.gpa.gpa,
+        transcript.gpa.total_credits
+    ));
     
     table
 }
 
+/// 按学期分段输出成绩单，每个学期后面给出学期绩点和累计绩点
+pub fn format_term_transcript(transcript: &TermTranscript) -> String {
+    let mut output = format!("{}的学期成绩单:\n", transcript.student_name);
+    if transcript.terms.is_empty() {
+        output.push_str("还没有任何课程或成绩");
+        return output;
+    }
+    
+    for term in &transcript.terms {
+        output.push_str(&format!("【{}】\n", term.term_name));
+        if !term.entries.is_empty() {
+            push_course_table(&mut output, &term.entries);
+        }
+        for grade in &term.grades {
+            output.push_str(&format!("  {}\n", grade));
+        }
+        output.push_str(&format!(
+            "学期绩点: {:.2} (注册学分: {}, 计入学分: {})  累计绩点: {:.2}\n",
+            term.term_gpa.gpa,
+            term.enrolled_credits,
+            term.term_gpa.total_credits,
+            term.cumulative_gpa.gpa
+        ));
+    }
+    
+    output.push_str(&format!(
+        "总加权绩点: {:.2} (计入学分: {})",
+        transcript.cumulative_gpa.gpa,
+        transcript.cumulative_gpa.total_credits
+    ));
+    output
+}
+
+fn push_course_table(table: &mut String, entries: &[TranscriptEntry]) {
+    table.push_str("┌─────────────────┬──────┬──────┬──────┐\n");
+    table.push_str("│      课程       │ 学分 │ 分数 │ 等级 │\n");
+    table.push_str("├─────────────────┼──────┼──────┼──────┤\n");
+    
+    for entry in entries {
+        let (score, letter) = match (entry.score, &entry.letter_grade, entry.mark) {
+            _ if entry.withdrawn => ("  --".to_string(), "W".to_string()),
+            (Some(score), Some(letter), _) => (format!("{:4.1}", score), letter.to_string()),
+            (_, _, Some(mark)) => ("  --".to_string(), mark.to_string()),
+            _ => ("  --".to_string(), "--".to_string()),
+        };
+        table.push_str(&format!(
+            "│ {:15} │ {:4} │ {} │  {:2}  │\n",
+            entry.course_name,
+            entry.credits,
+            score,
+            letter
+        ));
+    }
+    
+    table.push_str("└─────────────────┴──────┴──────┴──────┘\n");
+}
+
+/// 按星期分组输出周课表
+pub fn format_timetable(timetable: &Timetable) -> String {
+    let mut output = format!("{}的周课表:\n", timetable.student_name);
+    if timetable.entries.is_empty() {
+        output.push_str("没有安排上课时间的课程");
+        return output;
+    }
+    
+    let mut current_day = None;
+    for entry in &timetable.entries {
+        if current_day != Some(entry.slot.day) {
+            current_day = Some(entry.slot.day);
+            output.push_str(&format!("{}\n", entry.slot.day));
+        }
+        output.push_str(&format!(
+            "  {}-{}  {}  ({})\n",
+            entry.slot.start,
+            entry.slot.end,
+            entry.course_name,
+            entry.slot.room
+        ));
+    }
+    
+    output.pop();
+    output
+}
+
+/// 格式化教师教学工作量报告，每位教师一段，列出所授课程
+pub fn format_teaching_loads(loads: &[TeachingLoad]) -> String {
+    if loads.is_empty() {
+        return "没有教师".to_string();
+    }
+    
+    let mut output = String::from("教学工作量:\n");
+    for load in loads {
+        output.push_str(&format!(
+            "{} (ID: {}): {}门课程, {}学分, {}名学生\n",
+            load.instructor_name,
+            load.instructor_id,
+            load.course_count(),
+            load.total_credits,
+            load.student_count
+        ));
+        for course in &load.courses {
+            output.push_str(&format!(
+                "  [{}] {}  {}学分  {}人\n",
+                course.course_id, course.course_name, course.credits, course.student_count
+            ));
+        }
+    }
+    
+    output.pop();
+    output
+}
+
+/// 学位审核报告，已满足的要求标记为 ✓，未满足的标记为 ✗
+pub fn format_degree_audit(audit: &DegreeAudit) -> String {
+    let mark = |satisfied: bool| if satisfied { "✓" } else { "✗" };
+    let course_line = |course: &CourseAudit| format!(
+        "  {} [{}] {}  {}学分  {}\n",
+        mark(course.progress.is_completed()), course.course_id, course.course_name, course.credits, course.progress
+    );
+    
+    let mut output = format!(
+        "学位审核: {} (ID: {}) - {}\n",
+        audit.student_name, audit.student_id, audit.programme_name
+    );
+    output.push_str("必修课程:\n");
+    for course in &audit.required {
+        output.push_str(&course_line(course));
+    }
+    for pool in &audit.pools {
+        output.push_str(&format!(
+            "{} 选修课组 {}: {}/{}学分\n",
+            mark(pool.is_satisfied()), pool.name, pool.earned_credits, pool.required_credits
+        ));
+        for course in &pool.courses {
+            output.push_str(&course_line(course));
+        }
+    }
+    output.push_str(&format!(
+        "{} 总学分: {}/{}\n",
+        mark(audit.earned_credits >= audit.min_credits), audit.earned_credits, audit.min_credits
+    ));
+    output.push_str(if audit.is_complete() { "结论: 已满足全部要求" } else { "结论: 尚有要求未满足" });
+    output
+}
+
 pub fn format_progress_bar(current: usize, total: usize, width: usize) -> String {
     if total == 0 {
         return "█".repeat(width);
@@ -65,7 +242,230 @@ pub fn format_progress_bar(current: usize, total: usize, width: usize) -> String
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::student::Student;
+    use crate::student::grading_scale::GradingScale;
+    
+    #[test]
+    fn test_format_grade_table_with_plus_minus() {
+        let scale = GradingScale::plus_minus();
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        student.add_grade_with_scale("数学".to_string(), 91.0, &scale).unwrap();
+        student.add_grade_with_scale("物理".to_string(), 95.0, &scale).unwrap();
+        
+        let table = format_grade_table(&student);
+        assert!(table.contains("│ 91.0 │  A-  │"));
+        assert!(table.contains("│ 95.0 │  A   │"));
+    }
+    
+    #[test]
+    fn test_format_grade_table_with_marks() {
+        use crate::student::grade::GradeMark;
+        
+        let mut student = Student::new(1, "张三".to_string(), 20).unwrap();
+        student.add_mark("研讨课".to_string(), GradeMark::Pass).unwrap();
+        assert!(format_grade_table(&student).ends_with("平均分: --"));
+        
+        student.add_grade("数学".to_string(), 80.0).unwrap();
+        student.add_mark("体育".to_string(), GradeMark::Exempt).unwrap();
+        let table = format_grade_table(&student);
+        assert!(table.contains("│ 研讨课             │   -- │  P   │"));
+        assert!(table.contains("│ 体育              │   -- │  EX  │"));
+        assert!(table.ends_with("平均分: 80.0"));
+    }
+    
+    #[test]
+    fn test_format_transcript() {
+        use crate::course::transcript::WeightedGpa;
+        use crate::student::grade::LetterGrade;
+        
+        let transcript = Transcript {
+            student_id: 1,
+            student_name: "张三".to_string(),
+            entries: vec![
+                TranscriptEntry {
+                    course_id: 101,
+                    course_name: "Rust".to_string(),
+                    credits: 3,
+                    term_id: None,
+                    score: Some(92.0),
+                    letter_grade: Some(LetterGrade::A),
+                    grade_points: Some(4.0),
+                    mark: None,
+                    withdrawn: false,
+                },
+                TranscriptEntry {
+                    course_id: 102,
+                    course_name: "SQL".to_string(),
+                    credits: 2,
+                    term_id: None,
+                    score: None,
+                    letter_grade: None,
+                    grade_points: None,
+                    mark: None,
+                    withdrawn: false,
+                },
+                TranscriptEntry {
+                    course_id: 103,
+                    course_name: "体育".to_string(),
+                    credits: 1,
+                    term_id: None,
+                    score: None,
+                    letter_grade: None,
+                    grade_points: None,
+                    mark: None,
+                    withdrawn: true,
+                },
+            ],
+            gpa: WeightedGpa { gpa: 4.0, total_credits: 3 },
+        };
+        
+        let output = format_transcript(&transcript);
+        assert!(output.contains("│ Rust            │    3 │ 92.0 │  A   │"));
+        assert!(output.contains("│ SQL             │    2 │   -- │  --  │"));
+        assert!(output.contains("│ 体育              │    1 │   -- │  W   │"));
+        assert!(output.ends_with("加权绩点: 4.00 (计入学分: 3)"));
+    }
+    
+    #[test]
+    fn test_format_term_transcript() {
+        use crate::course::transcript::{TermRecord, WeightedGpa};
+        use crate::student::grade::{Grade, LetterGrade};
+        
+        let gpa = WeightedGpa { gpa: 3.0, total_credits: 2 };
+        let transcript = TermTranscript {
+            student_id: 1,
+            student_name: "张三".to_string(),
+            terms: vec![TermRecord {
+                term_id: Some(1),
+                term_name: "2024秋".to_string(),
+                entries: vec![TranscriptEntry {
+                    course_id: 101,
+                    course_name: "Rust".to_string(),
+                    credits: 2,
+                    term_id: Some(1),
+                    score: Some(85.0),
+                    letter_grade: Some(LetterGrade::B),
+                    grade_points: Some(3.0),
+                    mark: None,
+                    withdrawn: false,
+                }],
+                grades: vec![Grade::new("英语".to_string(), 88.0).unwrap().in_term(1)],
+                enrolled_credits: 2,
+                term_gpa: gpa,
+                cumulative_gpa: gpa,
+            }],
+            cumulative_gpa: gpa,
+        };
+        
+        let output = format_term_transcript(&transcript);
+        assert!(output.contains("【2024秋】\n"));
+        assert!(output.contains("│ Rust            │    2 │ 85.0 │  B   │"));
+        assert!(output.contains("  英语: 88.0 (B)\n"));
+        assert!(output.contains("学期绩点: 3.00 (注册学分: 2, 计入学分: 2)  累计绩点: 3.00"));
+        assert!(output.ends_with("总加权绩点: 3.00 (计入学分: 2)"));
+    }
+    
+    #[test]
+    fn test_format_timetable() {
+        use crate::course::schedule::{TimeSlot, TimetableEntry};
+        use crate::utils::date::Weekday;
+        
+        let entry = |day, start: &str, end: &str, name: &str| TimetableEntry {
+            course_id: 101,
+            course_name: name.to_string(),
+            slot: TimeSlot::new(day, start.parse().unwrap(), end.parse().unwrap(), "A101").unwrap(),
+        };
+        let timetable = Timetable {
+            student_id: 1,
+            student_name: "张三".to_string(),
+            term_id: None,
+            entries: vec![
+                entry(Weekday::Monday, "08:00", "09:40", "Rust"),
+                entry(Weekday::Monday, "10:00", "11:40", "SQL"),
+                entry(Weekday::Friday, "14:00", "15:40", "Rust"),
+            ],
+        };
+        
+        assert_eq!(
+            format_timetable(&timetable),
+            "张三的周课表:\n周一\n  08:00-09:40  Rust  (A101)\n  10:00-11:40  SQL  (A101)\n\
+             周五\n  14:00-15:40  Rust  (A101)"
+        );
+    }
+    
+    #[test]
+    fn test_format_teaching_loads() {
+        use crate::course::instructor::TaughtCourse;
+        
+        let loads = vec![
+            TeachingLoad {
+                instructor_id: 1,
+                instructor_name: "王老师".to_string(),
+                courses: vec![TaughtCourse {
+                    course_id: 101,
+                    course_name: "Rust编程".to_string(),
+                    credits: 3,
+                    term_id: None,
+                    student_count: 25,
+                }],
+                total_credits: 3,
+                student_count: 25,
+            },
+            TeachingLoad {
+                instructor_id: 2,
+                instructor_name: "李老师".to_string(),
+                courses: Vec::new(),
+                total_credits: 0,
+                student_count: 0,
+            },
+        ];
+        
+        assert_eq!(
+            format_teaching_loads(&loads),
+            "教学工作量:\n王老师 (ID: 1): 1门课程, 3学分, 25名学生\n  [101] Rust编程  3学分  25人\n\
+             李老师 (ID: 2): 0门课程, 0学分, 0名学生"
+        );
+        assert_eq!(format_teaching_loads(&[]), "没有教师");
+    }
+    
+    #[test]
+    fn test_format_degree_audit() {
+        use crate::course::programme::{CourseProgress, PoolAudit};
+        
+        let course = |course_id, course_name: &str, credits, progress| CourseAudit {
+            course_id,
+            course_name: course_name.to_string(),
+            credits,
+            progress,
+        };
+        let audit = DegreeAudit {
+            student_id: 1,
+            student_name: "张三".to_string(),
+            programme_id: 1,
+            programme_name: "软件工程".to_string(),
+            required: vec![
+                course(101, "程序设计", 4, CourseProgress::Completed("A".to_string())),
+                course(102, "数据结构", 4, CourseProgress::InProgress),
+            ],
+            pools: vec![PoolAudit {
+                name: "专业选修".to_string(),
+                required_credits: 3,
+                earned_credits: 3,
+                courses: vec![course(201, "编译原理", 3, CourseProgress::Completed("P".to_string()))],
+            }],
+            earned_credits: 7,
+            min_credits: 10,
+        };
+        
+        assert_eq!(
+            format_degree_audit(&audit),
+            "学位审核: 张三 (ID: 1) - 软件工程\n必修课程:\n\
+             \x20 ✓ [101] 程序设计  4学分  已完成（A）\n\
+             \x20 ✗ [102] 数据结构  4学分  在读\n\
+             ✓ 选修课组 专业选修: 3/3学分\n\
+             \x20 ✓ [201] 编译原理  3学分  已完成（P）\n\
+             ✗ 总学分: 7/10\n结论: 尚有要求未满足"
+        );
+    }
     
     #[test]
     fn test_format_progress_bar() {
@@ -1,27 +1,29 @@
 //! 数学工具函数
 
 use crate::errors::{AppError, AppResult};
+use crate::student::grading_scale::{standard_scale, GradingScale};
 
+/// 按标准等级制计算平均绩点
 pub fn calculate_gpa(grades: &[f64]) -> f64 {
+    calculate_gpa_with_scale(grades, standard_scale())
+}
+
+/// 按指定等级制计算平均绩点
+pub fn calculate_gpa_with_scale(grades: &[f64], scale: &GradingScale) -> f64 {
     if grades.is_empty() {
         return 0.0;
     }
     
     let total_points: f64 = grades.iter()
-        .map(|&grade| score_to_gpa(grade))
+        .map(|&grade| scale.gpa_for(grade))
         .sum();
     
     total_points / grades.len() as f64
 }
 
+/// 按标准等级制把分数换算为绩点
 pub fn score_to_gpa(score: f64) -> f64 {
-    match score {
-        90.0..=100.0 => 4.0,
-        80.0..=89.9 => 3.0,
-        70.0..=79.9 => 2.0,
-        60.0..=69.9 => 1.0,
-        _ => 0.0,
-    }
+    standard_scale().gpa_for(score)
 }
 
 pub fn calculate_standard_deviation(values: &[f64]) -> AppResult<f64> {
@@ -47,7 +49,7 @@ pub fn find_median(values: &mut [f64]) -> Option<f64> {
     values.sort_by(|a, b| a.partial_cmp(b).unwrap());
     let len = values.len();
     
-    if len % 2 == 0 {
+    if len.is_multiple_of(2) {
         Some((values[len / 2 - 1] + values[len / 2]) / 2.0)
     } else {
         Some(values[len / 2])
@@ -67,6 +69,19 @@ mod tests {
         assert_eq!(score_to_gpa(55.0), 0.0);
     }
     
+    #[test]
+    fn test_score_to_gpa_boundaries() {
+        let cases = [
+            (100.0, 4.0), (90.0, 4.0), (89.95, 3.0), (89.9, 3.0),
+            (80.0, 3.0), (79.95, 2.0), (70.0, 2.0), (69.95, 1.0),
+            (60.0, 1.0), (59.95, 0.0), (0.0, 0.0),
+        ];
+        for (score, expected) in cases {
+            assert_eq!(score_to_gpa(score), expected, "分数 {}", score);
+        }
+        assert_eq!(score_to_gpa(80.0f64.next_down()), 2.0);
+    }
+    
     #[test]
     fn test_calculate_gpa() {
         let grades = vec![90.0, 85.0, 75.0, 65.0];
@@ -74,6 +89,13 @@ mod tests {
         assert_eq!(gpa, 2.5); // (4.0 + 3.0 + 2.0 + 1.0) / 4
     }
     
+    #[test]
+    fn test_calculate_gpa_with_scale() {
+        let scale = GradingScale::from_config_str("A 85 A\nB 75 B\nC 65 C\nD 60 D\nF 0 F\n").unwrap();
+        assert_eq!(calculate_gpa_with_scale(&[86.0, 76.0], &scale), 3.5);
+        assert_eq!(calculate_gpa_with_scale(&[], &scale), 0.0);
+    }
+    
     #[test]
     fn test_find_median() {
         let mut values = vec![1.0, 3.0, 2.0, 5.0, 4.0];
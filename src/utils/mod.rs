@@ -1,5 +1,7 @@
 //! 工具模块
 
+pub mod csv;
+pub mod date;
 pub mod file_handler;
 pub mod formatter;
 pub mod math;
@@ -16,11 +18,20 @@ pub fn generate_id() -> u32 {
         .as_secs() as u32
 }
 
-/// 验证邮箱格式
+/// 验证邮箱格式：`@` 前后都不能为空，域名中要有 `.` 且不在两端，不能含空白
 pub fn validate_email(email: &str) -> AppResult<()> {
-    if !email.contains('@') || !email.contains('.') {
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty()
+            && !domain.contains('@')
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && !email.chars().any(char::is_whitespace),
+        None => false,
+    };
+    if !valid {
         return Err(AppError::ValidationError(
-            "邮箱格式不正确".to_string()
+            format!("邮箱 '{}' 格式不正确", email)
         ));
     }
     Ok(())